@@ -0,0 +1,67 @@
+//! Benchmarks a user-defined method against two of the built-in ones.
+//!
+//! Run with `cargo run --release --example custom_method`.
+
+use std::io;
+
+use compare_speed::{
+    print_graph, print_table, run_benchmark, terminal_width, Binary, GraphOptions, GraphScale,
+    Hash, Intersect, MethodRegistry, TableOptions, TimeUnit,
+};
+
+/// Keeps the elements of `big` that a binary search finds in a sorted copy of
+/// `small`.
+struct SortedRetain;
+
+impl Intersect for SortedRetain {
+    fn name(&self) -> String {
+        "Sorted retain".to_string()
+    }
+
+    fn description(&self) -> &str {
+        "sorts a copy of small, retains the elements of big it finds in it"
+    }
+
+    fn complexity(&self) -> &str {
+        "O((n + m) log m)"
+    }
+
+    fn intersect(&self, big: &[usize], small: &[usize]) -> Vec<usize> {
+        self.intersect_seq(big, small)
+    }
+
+    fn intersect_seq(&self, big: &[usize], small: &[usize]) -> Vec<usize> {
+        let mut small = small.to_vec();
+        small.sort_unstable();
+        let mut result = big.to_vec();
+        result.retain(|x| small.binary_search(x).is_ok());
+        result
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut registry = MethodRegistry::new();
+    registry.register(Box::new(Hash))?;
+    registry.register(Box::new(Binary))?;
+    registry.register(Box::new(SortedRetain))?;
+
+    let a: Vec<usize> = (0..200_000).map(|x| x * 7 % 100_003).collect();
+    let b: Vec<usize> = (0..20_000).map(|x| x * 13 % 50_021).collect();
+    let products: Vec<_> = run_benchmark(registry.methods(), &a, &b)
+        .into_iter()
+        .filter(|product| product.skipped.is_none())
+        .collect();
+
+    let mut out = io::stdout();
+    print_table(&mut out, &products, &[], &TableOptions::default(), false)?;
+    let graph = GraphOptions {
+        width: terminal_width(),
+        scale: GraphScale::Log,
+        ascii: false,
+        pairs: false,
+        labels: Some(TimeUnit::Auto),
+        axis: true,
+    };
+    print_graph(&mut out, &products, graph, false)?;
+    Ok(())
+}
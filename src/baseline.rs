@@ -0,0 +1,221 @@
+//! Saving the times of a run and comparing later runs against them.
+
+use std::{
+    fmt::Debug,
+    io::{self, Read, Write},
+    time::Duration,
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    record::{check_schema, ProductRecord, SCHEMA_VERSION},
+    Environment, Preset, Product, Scenario,
+};
+
+/// The times of a run, together with the input they were measured on.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Baseline {
+    /// The [`SCHEMA_VERSION`] the baseline was written with.
+    #[serde(default)]
+    pub schema_version: u32,
+    /// The seed the arrays were generated with.
+    pub seed: u64,
+    /// The number of elements in the first array.
+    pub size_a: usize,
+    /// The number of elements in the second array.
+    pub size_b: usize,
+    /// The shape the arrays were generated in, random in files written
+    /// before it was recorded.
+    #[serde(default)]
+    pub preset: Preset,
+    /// The times of the products that ran.
+    pub times: Vec<ProductRecord>,
+    /// Where the times were measured, missing in files written before it was
+    /// recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub environment: Option<Environment>,
+    /// The scenario the settings of the run came from, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scenario: Option<Scenario>,
+}
+
+/// How the time of a product changed since the baseline.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Delta {
+    /// The time of the product in the baseline.
+    pub baseline: Duration,
+    /// The change in percent of the baseline time, positive if the product
+    /// got slower, or `None` if the baseline took no time.
+    pub percent: Option<f64>,
+    /// Whether the product got slower by more than the threshold.
+    pub regressed: bool,
+}
+
+impl Delta {
+    /// The change formatted like `+12.50%`, followed by a flag if it regressed.
+    pub fn format(&self) -> String {
+        let change = self
+            .percent
+            .map_or("n/a".to_string(), |percent| format!("{:+.2}%", percent));
+        if self.regressed {
+            format!("{} (regressed)", change)
+        } else {
+            change
+        }
+    }
+}
+
+/// The products that couldn't be compared and the ones that regressed.
+pub struct BaselineComparison {
+    /// The names of the products that ran but aren't in the baseline.
+    pub only_current: Vec<String>,
+    /// The names of the products in the baseline that didn't run.
+    pub only_baseline: Vec<String>,
+    /// The names of the products that regressed.
+    pub regressed: Vec<String>,
+    /// The baseline ran on other input, as `(seed, size_a, size_b)`.
+    pub other_input: Option<(u64, usize, usize)>,
+    /// The baseline ran on arrays of another shape.
+    pub other_preset: Option<Preset>,
+    /// How the environment of the baseline differs from the current one, see
+    /// [`Environment::differences`].
+    pub other_environment: Vec<String>,
+}
+
+impl Baseline {
+    /// Records the times of the products that ran and finished, in the
+    /// current environment, on random arrays.
+    pub fn new<T: Debug>(seed: u64, sizes: (usize, usize), products: &[Product<T>]) -> Self {
+        Baseline {
+            schema_version: SCHEMA_VERSION,
+            seed,
+            size_a: sizes.0,
+            size_b: sizes.1,
+            preset: Preset::Random,
+            times: products
+                .iter()
+                .filter(|product| product.skipped.is_none() && product.timed_out.is_none())
+                .map(ProductRecord::from)
+                .collect(),
+            environment: Some(Environment::current()),
+            scenario: None,
+        }
+    }
+
+    /// Writes the baseline as JSON.
+    pub fn write(&self, out: impl Write) -> Result<(), String> {
+        serde_json::to_writer_pretty(out, self).map_err(|e| e.to_string())
+    }
+
+    /// Reads a baseline written by [`Baseline::write`], failing for a newer
+    /// schema.
+    pub fn read(reader: impl Read) -> Result<Self, String> {
+        let baseline: Self = serde_json::from_reader(reader).map_err(|e| e.to_string())?;
+        check_schema(baseline.schema_version)?;
+        Ok(baseline)
+    }
+
+    /// Sets the [`Product::baseline`] of every product that ran and is in
+    /// the baseline, flagging the ones that got slower by more than
+    /// `threshold` percent.
+    pub fn compare<T>(
+        &self,
+        products: &mut [Product<T>],
+        seed: u64,
+        sizes: (usize, usize),
+        preset: Preset,
+        threshold: f64,
+    ) -> BaselineComparison {
+        let mut only_current = Vec::new();
+        let mut regressed = Vec::new();
+        for product in products.iter_mut().filter(|p| p.skipped.is_none()) {
+            let Some(time) = self.times.iter().find(|t| t.name == product.name) else {
+                only_current.push(product.name.clone());
+                continue;
+            };
+            let baseline = Duration::from_nanos(time.time_ns);
+            let percent = (time.time_ns > 0)
+                .then(|| (product.time.as_nanos() as f64 / time.time_ns as f64 - 1.0) * 100.0);
+            let delta = Delta {
+                baseline,
+                percent,
+                regressed: percent.is_some_and(|percent| percent > threshold),
+            };
+            if delta.regressed {
+                regressed.push(product.name.clone());
+            }
+            product.baseline = Some(delta);
+        }
+        let only_baseline = self
+            .times
+            .iter()
+            .filter(|t| {
+                !products
+                    .iter()
+                    .any(|p| p.skipped.is_none() && p.name == t.name)
+            })
+            .map(|t| t.name.clone())
+            .collect();
+        let input = (self.seed, self.size_a, self.size_b);
+        BaselineComparison {
+            only_current,
+            only_baseline,
+            regressed,
+            other_input: (input != (seed, sizes.0, sizes.1)).then_some(input),
+            other_preset: (self.preset != preset).then_some(self.preset),
+            other_environment: self.environment.as_ref().map_or_else(Vec::new, |baseline| {
+                Environment::current().differences(baseline)
+            }),
+        }
+    }
+}
+
+impl BaselineComparison {
+    /// Whether any product got slower by more than the threshold.
+    pub fn regressed(&self) -> bool {
+        !self.regressed.is_empty()
+    }
+
+    /// Writes the products that couldn't be compared and the ones that
+    /// regressed.
+    pub fn write(&self, out: &mut impl Write) -> io::Result<()> {
+        if let Some((seed, size_a, size_b)) = self.other_input {
+            writeln!(
+                out,
+                "note: the baseline ran on arrays of the sizes {} and {} (seed {})",
+                size_a, size_b, seed
+            )?;
+        }
+        if let Some(preset) = self.other_preset {
+            writeln!(
+                out,
+                "note: the baseline ran on arrays of the {} preset",
+                preset.name()
+            )?;
+        }
+        if !self.other_environment.is_empty() {
+            writeln!(
+                out,
+                "warning: the baseline ran in another environment, its times may not compare:"
+            )?;
+            for difference in &self.other_environment {
+                writeln!(out, "  {}", difference)?;
+            }
+        }
+        if !self.only_current.is_empty() {
+            writeln!(out, "not in the baseline: {}", self.only_current.join(", "))?;
+        }
+        if !self.only_baseline.is_empty() {
+            writeln!(
+                out,
+                "only in the baseline: {}",
+                self.only_baseline.join(", ")
+            )?;
+        }
+        match self.regressed.len() {
+            0 => writeln!(out, "no method regressed"),
+            _ => writeln!(out, "regressed: {}", self.regressed.join(", ")),
+        }
+    }
+}
@@ -0,0 +1,1147 @@
+//! Timing methods and collecting their results.
+
+use std::{
+    cmp::{max_by_key, min_by_key, Reverse},
+    hint::black_box,
+    sync::{
+        atomic::{AtomicBool, Ordering::Relaxed},
+        mpsc::{self, RecvTimeoutError},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use clap::ValueEnum;
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use rayon::{
+    current_num_threads,
+    iter::{IntoParallelIterator, IntoParallelRefIterator, ParallelIterator},
+    ThreadPoolBuilder,
+};
+
+use crate::{
+    distinct, memory::measure, relative_standard_error, Delta, Element, Intersect, Memory, Op,
+    Prepared, Progress, Semantics,
+};
+
+/// Appended to the names of products that ran with the smaller array first.
+pub(crate) const SWITCHED_ORDER: &str = " switched order";
+
+/// Whether a method runs on all threads or on a single one.
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Flavor {
+    /// Runs [`Intersect::intersect`].
+    Parallel,
+    /// Runs [`Intersect::intersect_seq`], marked with " seq" in the name.
+    Sequential,
+}
+
+impl Flavor {
+    fn appendage(self) -> &'static str {
+        match self {
+            Flavor::Parallel => "",
+            Flavor::Sequential => " seq",
+        }
+    }
+
+    fn is_parallel(self) -> bool {
+        matches!(self, Flavor::Parallel)
+    }
+
+    fn run<T>(self, method: &dyn Intersect<T>, op: Op, a: &[T], b: &[T]) -> Vec<T> {
+        match self {
+            Flavor::Parallel => method.apply(op, a, b),
+            Flavor::Sequential => method.apply_seq(op, a, b),
+        }
+    }
+
+    fn run_sorted<T>(self, method: &dyn Intersect<T>, a: &[T], b: &[T]) -> Vec<T> {
+        match self {
+            Flavor::Parallel => method.intersect_sorted(a, b),
+            Flavor::Sequential => method.intersect_sorted_seq(a, b),
+        }
+    }
+
+    fn probe<T>(self, prepared: &dyn Prepared<T>, big: &[T]) -> Vec<T> {
+        match self {
+            Flavor::Parallel => prepared.probe(big),
+            Flavor::Sequential => prepared.probe_seq(big),
+        }
+    }
+
+    fn hits<T: Element>(self, prepared: &dyn Prepared<T>, queries: &[T]) -> usize {
+        match self {
+            Flavor::Parallel => queries.par_iter().filter(|x| prepared.contains(x)).count(),
+            Flavor::Sequential => queries.iter().filter(|x| prepared.contains(x)).count(),
+        }
+    }
+
+    fn run_many<T: Element>(self, method: &dyn Intersect<T>, arrays: &[Vec<T>]) -> Vec<T> {
+        match self {
+            Flavor::Parallel => method.intersect_many(arrays),
+            Flavor::Sequential => method.intersect_many_seq(arrays),
+        }
+    }
+
+    fn count<T>(self, method: &dyn Intersect<T>, a: &[T], b: &[T]) -> usize {
+        match self {
+            Flavor::Parallel => method.intersect_count(a, b),
+            Flavor::Sequential => method.intersect_count_seq(a, b),
+        }
+    }
+
+    fn run_as<T>(
+        self,
+        method: &dyn Intersect<T>,
+        semantics: Semantics,
+        a: &[T],
+        b: &[T],
+    ) -> Vec<T> {
+        match self {
+            Flavor::Parallel => method.intersect_as(semantics, a, b),
+            Flavor::Sequential => method.intersect_as_seq(semantics, a, b),
+        }
+    }
+
+    fn count_as<T>(
+        self,
+        method: &dyn Intersect<T>,
+        semantics: Semantics,
+        a: &[T],
+        b: &[T],
+    ) -> usize {
+        match self {
+            Flavor::Parallel => method.intersect_count_as(semantics, a, b),
+            Flavor::Sequential => method.intersect_count_as_seq(semantics, a, b),
+        }
+    }
+}
+
+/// How the result of an intersection is taken from every method.
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum Api {
+    /// Collects it into a vector.
+    #[default]
+    Vec,
+    /// Drains [`Intersect::intersect_iter`] into a counter, so that building
+    /// the output vector isn't timed. Only the sequential flavor runs.
+    Iter,
+}
+
+/// What every method is timed on.
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum Mode {
+    /// Computing the set operation of the arrays.
+    #[default]
+    Intersect,
+    /// Answering membership queries: the first array holds the queries, and
+    /// every method builds its [`Intersect::prepare`] structure from the
+    /// second one, which is timed as the setup. The time of a product is how
+    /// long answering the queries took, and its count how many hit.
+    ///
+    /// Methods that build no structure are skipped, and only the normal order
+    /// runs.
+    Probe,
+}
+
+/// Which orders of the arrays every method runs in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum Orders {
+    /// Only with the bigger array first.
+    Normal,
+    /// Only with the smaller array first, marked with " switched order" in
+    /// the name.
+    Switched,
+    /// In both orders.
+    #[default]
+    Both,
+}
+
+impl Orders {
+    fn includes(self, switched: bool) -> bool {
+        match self {
+            Orders::Normal => !switched,
+            Orders::Switched => switched,
+            Orders::Both => true,
+        }
+    }
+}
+
+/// The measured result of running one method in one flavor and order.
+pub struct Product<T = usize> {
+    /// The method name, followed by the flavor and order it ran in.
+    pub name: String,
+    /// The samples combined with [`Product::aggregate`].
+    pub time: Duration,
+    /// How the samples are combined into the time, the median by default.
+    pub aggregate: Aggregate,
+    /// Every timed run, sorted from fastest to slowest.
+    pub samples: Vec<Duration>,
+    /// Time spent on the untimed runs before measuring.
+    pub warmup: Duration,
+    /// The result of the last timed run, empty when only counting.
+    pub result: Vec<T>,
+    /// The number of elements counted by the last timed run, when only counting.
+    pub count: Option<usize>,
+    /// Why the method didn't run, if it was skipped.
+    pub skipped: Option<String>,
+    /// Whether the smaller array was passed as `big`.
+    pub switched: bool,
+    /// What the last timed run allocated, when measuring memory.
+    pub memory: Option<Memory>,
+    /// The median time of building the lookup structure and of probing it,
+    /// when timing the phases apart.
+    pub phases: Option<Phases>,
+    /// The limit the method exceeded before it finished, if it timed out.
+    ///
+    /// The time is set to the limit and there are no samples.
+    pub timed_out: Option<Duration>,
+    /// How the time changed since the baseline, when comparing against one
+    /// that contains the product.
+    pub baseline: Option<Delta>,
+    /// Whether the samples got stable before the runs ran out, when timing
+    /// until stable, see [`BenchmarkOptions::until_stable`].
+    pub converged: Option<bool>,
+}
+
+/// How long a method spent in each of the phases of
+/// [`Intersect::prepare`] and [`Prepared::probe`](crate::Prepared::probe).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Phases {
+    /// The median time of building the lookup structure, zero for methods
+    /// that don't build one.
+    pub setup: Duration,
+    /// The median time of probing the structure with the other array.
+    pub probe: Duration,
+}
+
+/// When [`BenchmarkOptions::until_stable`] stops timing a method.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Stability {
+    /// The relative standard error of the samples below which they count as
+    /// stable, like `0.02` for 2%.
+    pub threshold: f64,
+    /// The most timed runs of a method.
+    pub max_runs: u32,
+    /// How long the timed runs of a method may take together. The run that
+    /// crosses it still finishes, so a method slower than the budget is
+    /// timed once.
+    pub budget: Duration,
+}
+
+/// How the samples of a product are combined into its time.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum Aggregate {
+    /// The fastest sample, also known as best of N.
+    Min,
+    /// The middle sample, or the mean of the two middle ones.
+    Median,
+    /// The mean of all samples.
+    Mean,
+    /// The mean of the samples without the fastest and slowest 10%.
+    TrimmedMean,
+}
+
+impl Aggregate {
+    /// The name shown in the table header.
+    pub fn name(self) -> &'static str {
+        match self {
+            Aggregate::Min => "Min",
+            Aggregate::Median => "Median",
+            Aggregate::Mean => "Mean",
+            Aggregate::TrimmedMean => "Trimmed mean",
+        }
+    }
+
+    /// Combines sorted samples, of which there is at least one.
+    pub fn of(self, samples: &[Duration]) -> Duration {
+        let mean = |samples: &[Duration]| samples.iter().sum::<Duration>() / samples.len() as u32;
+        match self {
+            Aggregate::Min => samples[0],
+            Aggregate::Median => median(samples),
+            Aggregate::Mean => mean(samples),
+            Aggregate::TrimmedMean => {
+                let trimmed = samples.len() / 10;
+                mean(&samples[trimmed..samples.len() - trimmed])
+            }
+        }
+    }
+}
+
+/// The median of sorted durations, of which there is at least one.
+pub(crate) fn median(samples: &[Duration]) -> Duration {
+    let middle = samples.len() / 2;
+    if samples.len().is_multiple_of(2) {
+        (samples[middle - 1] + samples[middle]) / 2
+    } else {
+        samples[middle]
+    }
+}
+
+impl<T> Product<T> {
+    /// Creates a product from at least one sample.
+    pub fn new(name: String, mut samples: Vec<Duration>, warmup: Duration, result: Vec<T>) -> Self {
+        samples.sort();
+        Product {
+            name,
+            time: median(&samples),
+            aggregate: Aggregate::Median,
+            samples,
+            warmup,
+            result,
+            count: None,
+            skipped: None,
+            switched: false,
+            memory: None,
+            phases: None,
+            timed_out: None,
+            baseline: None,
+            converged: None,
+        }
+    }
+
+    /// Creates a product for a method that didn't run.
+    pub fn skipped(name: String, reason: String) -> Self {
+        Product {
+            name,
+            time: Duration::ZERO,
+            aggregate: Aggregate::Median,
+            samples: Vec::new(),
+            warmup: Duration::ZERO,
+            result: Vec::new(),
+            count: None,
+            skipped: Some(reason),
+            switched: false,
+            memory: None,
+            phases: None,
+            timed_out: None,
+            baseline: None,
+            converged: None,
+        }
+    }
+
+    /// Creates a product for a method that ran for longer than `limit`.
+    pub fn timed_out(name: String, limit: Duration) -> Self {
+        Product {
+            time: limit,
+            skipped: None,
+            timed_out: Some(limit),
+            ..Product::skipped(name, String::new())
+        }
+    }
+
+    /// The name without the order the product ran in, shared by both orders
+    /// of a method and flavor.
+    pub fn pair_name(&self) -> &str {
+        self.name.strip_suffix(SWITCHED_ORDER).unwrap_or(&self.name)
+    }
+
+    /// A copy of everything but the result, which is left empty.
+    pub(crate) fn summary<U>(&self) -> Product<U> {
+        Product {
+            name: self.name.clone(),
+            time: self.time,
+            aggregate: self.aggregate,
+            samples: self.samples.clone(),
+            warmup: self.warmup,
+            result: Vec::new(),
+            count: self.count,
+            skipped: self.skipped.clone(),
+            switched: self.switched,
+            memory: self.memory,
+            phases: self.phases,
+            timed_out: self.timed_out,
+            baseline: self.baseline,
+            converged: self.converged,
+        }
+    }
+
+    /// The number of elements in the result, or the count when only counting.
+    pub fn result_len(&self) -> usize {
+        self.count.unwrap_or(self.result.len())
+    }
+
+    /// The mean of the samples.
+    pub fn mean(&self) -> Duration {
+        self.samples.iter().sum::<Duration>() / self.samples.len() as u32
+    }
+
+    /// The population standard deviation of the samples.
+    pub fn std_dev(&self) -> Duration {
+        let mean = self.mean().as_nanos() as f64;
+        let variance = self
+            .samples
+            .iter()
+            .map(|x| (x.as_nanos() as f64 - mean).powi(2))
+            .sum::<f64>()
+            / self.samples.len() as f64;
+        Duration::from_nanos(variance.sqrt() as u64)
+    }
+}
+
+/// How [`run_benchmark_with`] runs every method.
+#[derive(Clone)]
+pub struct BenchmarkOptions {
+    /// The flavors every method runs in.
+    pub flavors: Vec<Flavor>,
+    /// The orders of the arrays every method runs in.
+    pub orders: Orders,
+    /// What every method is timed on.
+    pub mode: Mode,
+    /// Number of timed runs per method, at least one.
+    pub repeats: u32,
+    /// Keeps timing every method after its repeats, and at least two runs,
+    /// until its samples are stable or it runs out of runs or time.
+    pub until_stable: Option<Stability>,
+    /// Number of untimed runs per method before measuring.
+    pub warmup: u32,
+    /// The set operation every method computes.
+    pub op: Op,
+    /// Count the elements of the intersection instead of collecting them.
+    ///
+    /// Only supported for [`Op::Intersection`].
+    pub count_only: bool,
+    /// How often the intersection contains duplicate elements.
+    ///
+    /// Methods that don't [honor](Intersect::honors) the semantics are
+    /// skipped, and so is every method with [`Api::Iter`] and other semantics
+    /// than [`Semantics::MultisetBig`].
+    pub semantics: Semantics,
+    /// How the result of an intersection is taken, ignored for the other
+    /// operations.
+    pub api: Api,
+    /// Both arrays are sorted, so intersections run [`Intersect::intersect_sorted`],
+    /// unless they run [`Intersect::intersect_as`] for other semantics.
+    pub pre_sorted: bool,
+    /// Skip methods whose [`Intersect::is_quadratic`] is set when the bigger
+    /// array has more elements than this.
+    pub quadratic_limit: Option<usize>,
+    /// Count what every method allocates in an extra untimed run after its
+    /// timed ones, running the methods one after another even with
+    /// `concurrent_methods`, so that the counts don't mix.
+    ///
+    /// Requires [`CountingAllocator`](crate::CountingAllocator) to be the
+    /// global allocator.
+    pub measure_memory: bool,
+    /// Time [`Intersect::prepare`] apart from probing what it built.
+    ///
+    /// Only applies to intersections that collect their result with
+    /// [`Semantics::MultisetBig`].
+    pub phases: bool,
+    /// How the samples of every product are combined into its time.
+    pub aggregate: Aggregate,
+    /// Runs the methods at the same time, which finishes sooner but makes
+    /// them compete for the threads their parallel flavors use.
+    pub concurrent_methods: bool,
+    /// Runs the methods one at a time in rounds of one timed run each,
+    /// shuffling their order every round with this seed, instead of running
+    /// all repeats of a method at once.
+    pub interleave: Option<u64>,
+    /// Shows the running method on a status line, which is cleared before
+    /// the products are returned.
+    pub progress: Option<Arc<Progress>>,
+    /// Set to stop the benchmark early, like on Ctrl+C: no method starts and
+    /// no more runs are timed once it is set, and only the products measured
+    /// so far are returned. Methods that already run finish their current
+    /// run, and are skipped if it wasn't a timed one.
+    pub interrupted: Option<Arc<AtomicBool>>,
+}
+
+impl BenchmarkOptions {
+    /// Whether [`BenchmarkOptions::interrupted`] is set.
+    pub fn interrupted(&self) -> bool {
+        self.interrupted
+            .as_ref()
+            .is_some_and(|interrupted| interrupted.load(Relaxed))
+    }
+
+    /// Whether intersections are drained from [`Intersect::intersect_iter`].
+    fn iterates(&self) -> bool {
+        self.api == Api::Iter && self.op == Op::Intersection
+    }
+}
+
+impl Default for BenchmarkOptions {
+    fn default() -> Self {
+        BenchmarkOptions {
+            flavors: vec![Flavor::Parallel, Flavor::Sequential],
+            orders: Orders::Both,
+            mode: Mode::Intersect,
+            repeats: 1,
+            until_stable: None,
+            warmup: 1,
+            op: Op::Intersection,
+            count_only: false,
+            semantics: Semantics::MultisetBig,
+            api: Api::Vec,
+            pre_sorted: false,
+            quadratic_limit: None,
+            measure_memory: false,
+            phases: false,
+            aggregate: Aggregate::Median,
+            concurrent_methods: false,
+            interleave: None,
+            progress: None,
+            interrupted: None,
+        }
+    }
+}
+
+/// A method with the arrays, flavor and order to run it in, followed by all
+/// arrays when intersecting more than two, of which the first two are the
+/// biggest and the smallest.
+type Job<'a, T> = (
+    &'a dyn Intersect<T>,
+    &'a [T],
+    &'a [T],
+    Flavor,
+    bool,
+    &'a [Vec<T>],
+);
+
+/// Runs every method with the default options, see [`run_benchmark_with`].
+pub fn run_benchmark<T: Element>(
+    methods: &[Box<dyn Intersect<T>>],
+    a: &[T],
+    b: &[T],
+) -> Vec<Product<T>> {
+    run_benchmark_with(methods, a, b, &BenchmarkOptions::default())
+}
+
+/// Runs every method in every flavor, once with the bigger array first and
+/// once in switched order, unless [`BenchmarkOptions::orders`] picks one.
+///
+/// The methods run one after another with the whole thread pool to
+/// themselves, unless [`BenchmarkOptions::concurrent_methods`] is set.
+///
+/// The products are sorted from slowest to fastest, followed by the skipped
+/// ones. Methods that don't support the operation are skipped once, without
+/// running them in every flavor and order.
+pub fn run_benchmark_with<T: Element>(
+    methods: &[Box<dyn Intersect<T>>],
+    a: &[T],
+    b: &[T],
+    options: &BenchmarkOptions,
+) -> Vec<Product<T>> {
+    run_benchmark_observed(methods, a, b, options, |_| {})
+}
+
+/// Does the same as [`run_benchmark_with`], passing every product to
+/// `observe` as soon as it is measured.
+///
+/// With [`BenchmarkOptions::interleave`] every product is measured before the
+/// first one is observed.
+pub fn run_benchmark_observed<T: Element>(
+    methods: &[Box<dyn Intersect<T>>],
+    a: &[T],
+    b: &[T],
+    options: &BenchmarkOptions,
+    observe: impl Fn(&Product<T>) + Send + Sync,
+) -> Vec<Product<T>> {
+    run_jobs(methods, a, b, &[], options, |jobs| {
+        drive(jobs, options, observe)
+    })
+}
+
+/// Runs every method in every flavor on all `arrays` at once, see
+/// [`Intersect::intersect_many`], sorting the products like
+/// [`run_benchmark_with`].
+///
+/// The products hold every distinct element that occurs in all arrays.
+/// Quadratic methods are skipped when there are more than two arrays. The
+/// options for the orders, operation, semantics, API and phases are ignored.
+pub fn run_benchmark_many<T: Element>(
+    methods: &[Box<dyn Intersect<T>>],
+    arrays: &[Vec<T>],
+    options: &BenchmarkOptions,
+) -> Vec<Product<T>> {
+    let options = BenchmarkOptions {
+        op: Op::Intersection,
+        ..options.clone()
+    };
+    let big = arrays.iter().max_by_key(|x| x.len()).map_or(&[][..], |x| x);
+    let small = arrays.iter().min_by_key(|x| x.len()).map_or(&[][..], |x| x);
+    run_jobs(methods, big, small, arrays, &options, |jobs| {
+        drive(jobs, &options, |_| {})
+    })
+}
+
+/// Runs the jobs the way the options ask for, passing every product to
+/// `observe`.
+fn drive<'a, T: Element>(
+    jobs: Vec<Job<'a, T>>,
+    options: &'a BenchmarkOptions,
+    observe: impl Fn(&Product<T>) + Send + Sync,
+) -> Vec<Product<T>> {
+    match options.interleave {
+        Some(seed) => interleave(jobs, options, seed, &observe),
+        None => {
+            let test = |job| {
+                let product = test_method(job, options);
+                observe(&product);
+                product
+            };
+            let sequential = !options.concurrent_methods || options.measure_memory;
+            each(jobs, options, sequential, test)
+        }
+    }
+}
+
+/// Does the same as [`run_benchmark_with`], but stops waiting for a method
+/// once it ran for longer than `timeout`, marking it as timed out.
+///
+/// Every method runs on a thread of its own, one method after another. The
+/// threads of timed out methods are abandoned and keep running in the
+/// background, which is why the methods and arrays must live forever.
+///
+/// The parallel flavors run in a thread pool per method, with as many
+/// threads as the current one. [`BenchmarkOptions::interleave`] is ignored.
+pub fn run_benchmark_with_timeout<T: Element + 'static>(
+    methods: &'static [Box<dyn Intersect<T>>],
+    a: &'static [T],
+    b: &'static [T],
+    options: &BenchmarkOptions,
+    timeout: Duration,
+) -> Vec<Product<T>> {
+    // abandoned methods would keep drawing the status line, so only the
+    // start of every method is shown
+    let shared = Arc::new(BenchmarkOptions {
+        progress: None,
+        ..options.clone()
+    });
+    let test = |job @ (method, a, b, flavor, switched, arrays): Job<'static, T>| {
+        let name = job_name(job, options);
+        if let Some(progress) = &options.progress {
+            progress.update(&name, "running");
+        }
+        let (sender, receiver) = mpsc::channel();
+        let options = Arc::clone(&shared);
+        // An abandoned method keeps the threads of its pool busy, so it gets a
+        // pool of its own instead of blocking the global one.
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(current_num_threads())
+            .build()
+            .expect("failed to build a thread pool");
+        thread::spawn(move || {
+            let product =
+                pool.install(|| test_method((method, a, b, flavor, switched, arrays), &options));
+            // the receiver is gone if the method timed out
+            let _ = sender.send(product);
+        });
+        match receiver.recv_timeout(timeout) {
+            Ok(product) => product,
+            Err(RecvTimeoutError::Timeout) => Product {
+                aggregate: shared.aggregate,
+                switched,
+                ..Product::timed_out(name, timeout)
+            },
+            Err(RecvTimeoutError::Disconnected) => panic!("{} panicked", name),
+        }
+    };
+    run_jobs(methods, a, b, &[], options, |jobs| {
+        each(jobs, options, true, test)
+    })
+}
+
+/// The names of the products [`run_benchmark_with`] measures with these
+/// options, or [`run_benchmark_many`] if there are more than two `arrays`,
+/// followed by the names of the methods that don't support the operation.
+///
+/// Methods can still be skipped for the arrays they get, see
+/// [`Intersect::skip_reason`].
+pub fn plan<T: Element>(
+    methods: &[Box<dyn Intersect<T>>],
+    arrays: usize,
+    options: &BenchmarkOptions,
+) -> (Vec<String>, Vec<String>) {
+    let arrays: Vec<Vec<T>> = match arrays {
+        0..=2 => Vec::new(),
+        n => vec![Vec::new(); n],
+    };
+    let (jobs, unsupported) = jobs(methods, &[], &[], &arrays, options);
+    (
+        jobs.into_iter()
+            .map(|(method, _, _, flavor, switched, _)| {
+                product_name(&method.name(), flavor, switched)
+            })
+            .collect(),
+        unsupported
+            .into_iter()
+            .map(|method| method.name())
+            .collect(),
+    )
+}
+
+/// Every method that supports the operation in every flavor and order, only
+/// in the normal order if there are more `arrays` to intersect, followed by
+/// the methods that don't support it.
+fn jobs<'a, T: Element>(
+    methods: &'a [Box<dyn Intersect<T>>],
+    a: &'a [T],
+    b: &'a [T],
+    arrays: &'a [Vec<T>],
+    options: &BenchmarkOptions,
+) -> (Vec<Job<'a, T>>, Vec<&'a dyn Intersect<T>>) {
+    let (big, small) = match options.mode {
+        // the queries come first, however many there are
+        Mode::Probe => (a, b),
+        Mode::Intersect => (max_by_key(a, b, |x| x.len()), min_by_key(a, b, |x| x.len())),
+    };
+    let (supported, unsupported): (Vec<_>, Vec<_>) = methods
+        .iter()
+        .partition(|method| method.supports(options.op));
+    let jobs = supported
+        .iter()
+        .flat_map(|method| {
+            options.flavors.iter().flat_map(move |&flavor| {
+                [
+                    (&***method, big, small, flavor, false, arrays),
+                    (&***method, small, big, flavor, true, arrays),
+                ]
+            })
+        })
+        .filter(|&(_, _, _, _, switched, _)| match arrays {
+            [] if options.mode == Mode::Intersect => options.orders.includes(switched),
+            _ => !switched,
+        })
+        .filter(|&(_, _, _, flavor, _, _)| !(flavor.is_parallel() && options.iterates()))
+        .collect();
+    (
+        jobs,
+        unsupported.into_iter().map(|method| &**method).collect(),
+    )
+}
+
+/// Passes the [`jobs`] to `run` and sorts the products it returns.
+fn run_jobs<'a, T: Element>(
+    methods: &'a [Box<dyn Intersect<T>>],
+    a: &'a [T],
+    b: &'a [T],
+    arrays: &'a [Vec<T>],
+    options: &BenchmarkOptions,
+    run: impl FnOnce(Vec<Job<'a, T>>) -> Vec<Product<T>>,
+) -> Vec<Product<T>> {
+    let (jobs, unsupported) = jobs(methods, a, b, arrays, options);
+    if let Some(progress) = &options.progress {
+        progress.begin(jobs.len());
+    }
+    let (mut products, skipped): (Vec<_>, Vec<_>) =
+        run(jobs).into_iter().partition(|p| p.skipped.is_none());
+    if let Some(progress) = &options.progress {
+        progress.clear();
+    }
+
+    products.sort_by_key(|product| Reverse(product.time));
+    products.extend(skipped);
+    products.extend(unsupported.into_iter().map(|method| {
+        let reason = format!("does not support the {}", options.op.name());
+        Product::skipped(method.name(), reason)
+    }));
+    products
+}
+
+/// Runs `test` on every job, one after another if `sequential` is set.
+fn each<'a, T: Element>(
+    jobs: Vec<Job<'a, T>>,
+    options: &BenchmarkOptions,
+    sequential: bool,
+    test: impl Fn(Job<'a, T>) -> Product<T> + Send + Sync,
+) -> Vec<Product<T>> {
+    let test = |job| {
+        let product = test(job);
+        if let Some(progress) = &options.progress {
+            progress.finish();
+        }
+        product
+    };
+    if sequential {
+        jobs.into_iter()
+            .take_while(|_| !options.interrupted())
+            .map(test)
+            .collect()
+    } else {
+        jobs.into_par_iter()
+            .filter(|_| !options.interrupted())
+            .map(test)
+            .collect()
+    }
+}
+
+/// Runs the jobs one after another in rounds of one timed run of every job,
+/// shuffled with `seed` before the warmup and before every round, so that no
+/// method always runs first on cold caches or after the same neighbour.
+fn interleave<'a, T: Element>(
+    jobs: Vec<Job<'a, T>>,
+    options: &'a BenchmarkOptions,
+    seed: u64,
+    observe: impl Fn(&Product<T>),
+) -> Vec<Product<T>> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let (mut trials, mut products) = (Vec::new(), Vec::new());
+    for job in jobs {
+        match skipped(job, options) {
+            Some(skipped) => products.push(skipped),
+            None => trials.push(Trial::new(job, options)),
+        }
+    }
+    let mut order: Vec<usize> = (0..trials.len()).collect();
+    order.shuffle(&mut rng);
+    for &i in &order {
+        if options.interrupted() {
+            break;
+        }
+        trials[i].warm_up();
+    }
+    while !options.interrupted() && trials.iter().any(|trial| !trial.done()) {
+        order.shuffle(&mut rng);
+        for &i in &order {
+            if options.interrupted() {
+                break;
+            }
+            if !trials[i].done() {
+                trials[i].measure();
+            }
+        }
+    }
+    products.extend(trials.into_iter().map(Trial::finish));
+    for product in &products {
+        observe(product);
+        if let Some(progress) = &options.progress {
+            progress.finish();
+        }
+    }
+    products
+}
+
+fn product_name(name: &str, flavor: Flavor, switched: bool) -> String {
+    let appendage = if switched { SWITCHED_ORDER } else { "" };
+    format!("{}{}{}", name, flavor.appendage(), appendage)
+}
+
+/// The name of the product a job measures, see [`Intersect::name_for`].
+fn job_name<T>((method, a, b, flavor, switched, _): Job<T>, options: &BenchmarkOptions) -> String {
+    let name = match options.mode {
+        // the structure is built before the queries are known
+        Mode::Probe => method.name_for(&[], b),
+        Mode::Intersect => method.name_for(a, b),
+    };
+    product_name(&name, flavor, switched)
+}
+
+/// Returns the skipped product of a job whose method doesn't run on its arrays.
+fn skipped<T: Element>(
+    job @ (method, a, b, _, switched, arrays): Job<T>,
+    options: &BenchmarkOptions,
+) -> Option<Product<T>> {
+    let too_big = |limit: usize| method.is_quadratic() && a.len().max(b.len()) > limit;
+    let default_semantics = options.semantics == Semantics::MultisetBig;
+    let reason = match options.quadratic_limit {
+        _ if arrays.len() > 2 && method.is_quadratic() => {
+            Some("quadratic methods only intersect two arrays".to_string())
+        }
+        // building a structure from nothing is the only way to ask for one
+        _ if options.mode == Mode::Probe && method.prepare(&[]).is_none() => {
+            Some("builds no structure to answer queries with".to_string())
+        }
+        Some(limit) if too_big(limit) => Some(format!(
+            "quadratic methods are skipped above {} elements",
+            limit
+        )),
+        _ if options.iterates() && arrays.is_empty() && !default_semantics => Some(format!(
+            "iterators only yield the {} semantics",
+            Semantics::MultisetBig.name()
+        )),
+        _ if options.op == Op::Intersection
+            && options.mode == Mode::Intersect
+            && arrays.is_empty()
+            && !method.honors(options.semantics) =>
+        {
+            Some(format!(
+                "doesn't compute the {} semantics",
+                options.semantics.name()
+            ))
+        }
+        _ if options.op == Op::Intersection
+            && default_semantics
+            && arrays.is_empty()
+            && !method.keeps_duplicates()
+            && distinct(a) < a.len() =>
+        {
+            Some(
+                "returns every distinct element once, but the first array has duplicates"
+                    .to_string(),
+            )
+        }
+        // the methods may build their structures from any of the arrays
+        _ if !arrays.is_empty() => arrays.iter().find_map(|small| method.skip_reason(a, small)),
+        _ => method.skip_reason(a, b),
+    }?;
+    Some(Product {
+        switched,
+        ..Product::skipped(job_name(job, options), reason)
+    })
+}
+
+fn test_method<T: Element>(job: Job<T>, options: &BenchmarkOptions) -> Product<T> {
+    if let Some(skipped) = skipped(job, options) {
+        return skipped;
+    }
+    let mut trial = Trial::new(job, options);
+    trial.warm_up();
+    while !trial.done() && !options.interrupted() {
+        trial.measure();
+    }
+    trial.finish()
+}
+
+/// The runs of one method in one flavor and order, timed one at a time so
+/// that the runs of several methods can take turns.
+struct Trial<'a, T> {
+    method: &'a dyn Intersect<T>,
+    a: &'a [T],
+    b: &'a [T],
+    flavor: Flavor,
+    switched: bool,
+    arrays: &'a [Vec<T>],
+    options: &'a BenchmarkOptions,
+    name: String,
+    warmup: Duration,
+    samples: Vec<Duration>,
+    /// The time the timed runs took, including what the samples leave out.
+    spent: Duration,
+    result: Vec<T>,
+    count: Option<usize>,
+    memory: Option<Memory>,
+    setups: Vec<Duration>,
+    probes: Vec<Duration>,
+}
+
+impl<'a, T: Element> Trial<'a, T> {
+    fn new(
+        job @ (method, a, b, flavor, switched, arrays): Job<'a, T>,
+        options: &'a BenchmarkOptions,
+    ) -> Self {
+        Trial {
+            method,
+            a,
+            b,
+            flavor,
+            switched,
+            arrays,
+            options,
+            name: job_name(job, options),
+            warmup: Duration::ZERO,
+            samples: Vec::with_capacity(options.repeats as usize),
+            spent: Duration::ZERO,
+            result: Vec::new(),
+            count: None,
+            memory: None,
+            setups: Vec::new(),
+            probes: Vec::new(),
+        }
+    }
+
+    fn intersection(&self) -> bool {
+        self.options.op == Op::Intersection
+    }
+
+    fn probes(&self) -> bool {
+        self.options.mode == Mode::Probe
+    }
+
+    fn phases(&self) -> bool {
+        self.probes()
+            || (self.options.phases
+                && self.intersection()
+                && !self.options.count_only
+                && !self.options.iterates()
+                && self.options.semantics == Semantics::MultisetBig
+                && self.arrays.is_empty())
+    }
+
+    /// Whether the method computes other semantics than
+    /// [`Semantics::MultisetBig`] in the timed runs.
+    fn honors_semantics(&self) -> bool {
+        self.intersection()
+            && self.arrays.is_empty()
+            && self.options.semantics != Semantics::MultisetBig
+    }
+
+    // black_box hides the inputs from the optimizer and makes it keep the
+    // outputs, so that no work is hoisted out of the timed region or dropped
+    // because its result is never read, like in the warmup runs
+    fn run(&self) -> (Vec<T>, Option<usize>) {
+        let (method, flavor, options) = (self.method, self.flavor, self.options);
+        let (a, b) = (black_box(self.a), black_box(self.b));
+        if self.probes() {
+            return self.phased().0;
+        }
+        if !self.arrays.is_empty() {
+            let result = flavor.run_many(method, black_box(self.arrays));
+            return black_box(if options.count_only {
+                (Vec::new(), Some(result.len()))
+            } else {
+                (result, None)
+            });
+        }
+        black_box(if options.count_only && self.honors_semantics() {
+            (
+                Vec::new(),
+                Some(flavor.count_as(method, options.semantics, a, b)),
+            )
+        } else if options.count_only {
+            (Vec::new(), Some(flavor.count(method, a, b)))
+        } else if options.iterates() {
+            let mut count = 0;
+            method.intersect_iter(a, b).for_each(|x| {
+                black_box(x);
+                count += 1;
+            });
+            (Vec::new(), Some(count))
+        } else if self.honors_semantics() {
+            (flavor.run_as(method, options.semantics, a, b), None)
+        } else if options.pre_sorted && self.intersection() {
+            (flavor.run_sorted(method, a, b), None)
+        } else {
+            (flavor.run(method, options.op, a, b), None)
+        })
+    }
+
+    /// Runs the method in two phases, returning how long each took.
+    fn phased(&self) -> ((Vec<T>, Option<usize>), Duration, Duration) {
+        let start = Instant::now();
+        match black_box(self.method.prepare(black_box(self.b))) {
+            Some(prepared) if self.probes() => {
+                let setup = start.elapsed();
+                let start = Instant::now();
+                let hits = black_box(self.flavor.hits(&*prepared, black_box(self.a)));
+                ((Vec::new(), Some(hits)), setup, start.elapsed())
+            }
+            Some(prepared) => {
+                let setup = start.elapsed();
+                let start = Instant::now();
+                let result = black_box(self.flavor.probe(&*prepared, black_box(self.a)));
+                ((result, None), setup, start.elapsed())
+            }
+            None => {
+                let result = self.run();
+                (result, Duration::ZERO, start.elapsed())
+            }
+        }
+    }
+
+    fn progress(&self, stage: &str) {
+        if let Some(progress) = &self.options.progress {
+            progress.update(&self.name, stage);
+        }
+    }
+
+    /// Runs the untimed warmup runs, timing them together.
+    fn warm_up(&mut self) {
+        if self.options.warmup > 0 {
+            self.progress("warming up");
+        }
+        let start = Instant::now();
+        for _ in 0..self.options.warmup {
+            if self.options.interrupted() {
+                break;
+            }
+            self.run();
+        }
+        self.warmup = start.elapsed();
+    }
+
+    /// Whether the method had all of its timed runs.
+    fn done(&self) -> bool {
+        let runs = self.samples.len() as u32;
+        match self.options.until_stable {
+            None => runs >= self.options.repeats,
+            Some(stability) => {
+                runs >= stability.max_runs
+                    || self.spent >= stability.budget
+                    || runs >= self.options.repeats.max(2) && self.stable(stability)
+            }
+        }
+    }
+
+    fn stable(&self, stability: Stability) -> bool {
+        relative_standard_error(&self.samples).is_some_and(|error| error < stability.threshold)
+    }
+
+    /// Times the next run.
+    fn measure(&mut self) {
+        let start = Instant::now();
+        self.run_timed();
+        self.spent += start.elapsed();
+    }
+
+    fn run_timed(&mut self) {
+        let repeats = self.options.repeats;
+        let i = self.samples.len() as u32;
+        match self.options.until_stable {
+            Some(_) => self.progress(&format!("run {} until stable", i + 1)),
+            None => self.progress(&format!("run {} of {}", i + 1, repeats)),
+        }
+        let start = Instant::now();
+        if self.phases() {
+            let (outcome, setup, probe) = self.phased();
+            (self.result, self.count) = outcome;
+            self.setups.push(setup);
+            self.probes.push(probe);
+            // answering queries is timed without building the structure
+            if self.probes() {
+                self.samples.push(probe);
+                return;
+            }
+        } else {
+            (self.result, self.count) = self.run();
+        }
+        self.samples.push(start.elapsed());
+    }
+
+    /// Counts what one more run allocates, after the timed runs so that
+    /// counting doesn't slow them down.
+    fn count_memory(&mut self) {
+        self.progress("counting allocations");
+        let memory = if self.phases() {
+            measure(|| self.phased()).1
+        } else {
+            measure(|| self.run()).1
+        };
+        self.memory = Some(memory);
+    }
+
+    /// Returns the product, or a skipped one if the benchmark was interrupted
+    /// before the first timed run.
+    fn finish(mut self) -> Product<T> {
+        if self.samples.is_empty() {
+            return Product {
+                switched: self.switched,
+                ..Product::skipped(self.name, "interrupted before it was timed".to_string())
+            };
+        }
+        if self.options.measure_memory && !self.options.interrupted() {
+            self.count_memory();
+        }
+        let phases = self.phases();
+        self.setups.sort();
+        self.probes.sort();
+        let options = self.options;
+        let converged = options.until_stable.map(|stability| self.stable(stability));
+        let product = Product::new(self.name, self.samples, self.warmup, self.result);
+        Product {
+            time: options.aggregate.of(&product.samples),
+            aggregate: options.aggregate,
+            count: self.count,
+            switched: self.switched,
+            memory: self.memory,
+            phases: phases.then(|| Phases {
+                setup: median(&self.setups),
+                probe: median(&self.probes),
+            }),
+            converged,
+            ..product
+        }
+    }
+}
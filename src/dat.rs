@@ -0,0 +1,98 @@
+//! Writing the times as a whitespace separated data file for gnuplot.
+
+use std::io::{self, Write};
+
+use crate::{Environment, Op, Product, Semantics, SweepPoint};
+
+/// The run configuration described in the header of a data file.
+#[derive(Clone, Copy)]
+pub struct DatOptions {
+    /// The seed the arrays were generated with.
+    pub seed: u64,
+    /// The operation every method computed.
+    pub op: Op,
+    /// How often an element occurs in the intersection.
+    pub semantics: Semantics,
+}
+
+/// Writes the header comment describing the columns and the run.
+fn write_header(out: &mut impl Write, options: &DatOptions, blocks: bool) -> io::Result<()> {
+    writeln!(out, "# compare-speed times, one line per method that ran")?;
+    writeln!(out, "# {}", Environment::current().description())?;
+    writeln!(
+        out,
+        "# {} with {}, seed {}",
+        options.op.description(),
+        options.semantics.description(),
+        options.seed
+    )?;
+    writeln!(
+        out,
+        "# columns: index, name, time in nanoseconds, size of the first array, size of the second array"
+    )?;
+    writeln!(
+        out,
+        "# names are quoted, with their double quotes replaced by single ones; \
+         skipped and timed out methods are left out"
+    )?;
+    if blocks {
+        writeln!(
+            out,
+            "# a block per input size, separated by two blank lines for gnuplot's `index`"
+        )?;
+    }
+    Ok(())
+}
+
+/// Writes a line per product that ran, numbered from 0.
+fn write_block<T>(
+    out: &mut impl Write,
+    products: &[Product<T>],
+    size_a: usize,
+    size_b: usize,
+) -> io::Result<()> {
+    let ran = products
+        .iter()
+        .filter(|p| p.skipped.is_none() && p.timed_out.is_none());
+    for (index, product) in ran.enumerate() {
+        writeln!(
+            out,
+            "{} \"{}\" {} {} {}",
+            index,
+            product.name.replace('"', "'"),
+            product.time.as_nanos(),
+            size_a,
+            size_b
+        )?;
+    }
+    Ok(())
+}
+
+/// Writes the times of a single run, after a header describing the columns
+/// and the run.
+pub fn write_dat<T>(
+    out: &mut impl Write,
+    products: &[Product<T>],
+    sizes: (usize, usize),
+    options: &DatOptions,
+) -> io::Result<()> {
+    write_header(out, options, false)?;
+    write_block(out, products, sizes.0, sizes.1)
+}
+
+/// Writes the times of a sweep as a block per size, so gnuplot can plot
+/// every size as its own series.
+pub fn write_dat_sweep<T>(
+    out: &mut impl Write,
+    points: &[SweepPoint<T>],
+    options: &DatOptions,
+) -> io::Result<()> {
+    write_header(out, options, true)?;
+    for (i, point) in points.iter().enumerate() {
+        if i > 0 {
+            writeln!(out, "\n")?;
+        }
+        write_block(out, &point.products, point.big, point.small)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,516 @@
+//! Generating or reading the arrays to intersect.
+
+use std::{
+    collections::HashSet,
+    io::{self, BufRead, Read, Write},
+    mem::size_of,
+};
+
+use clap::ValueEnum;
+use rand::{distributions::Alphanumeric, rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use rand_distr::{Distribution as _, Zipf};
+use rayon::{
+    iter::{
+        IndexedParallelIterator, IntoParallelRefIterator, IntoParallelRefMutIterator,
+        ParallelIterator,
+    },
+    slice::ParallelSliceMut,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::Element;
+
+const CHUNK_SIZE: usize = 4096;
+/// Identifies files written by [`write_dataset`].
+const MAGIC: &[u8; 8] = b"CMPSPD01";
+/// Number of distinct values a Zipf distribution draws from.
+const ZIPF_VALUES: u64 = 1 << 20;
+const CLUSTERS: usize = 8;
+const CLUSTER_WIDTH: usize = 1 << 16;
+/// Sizes of the arrays of [`Preset::Skewed`] if they aren't given.
+const SKEWED_SIZES: (usize, usize) = (10_000_000, 10);
+/// Number of distinct values [`Preset::Duplicates`] draws from.
+const DUPLICATE_VALUES: usize = 1_000;
+
+/// How the values of the generated arrays are distributed.
+#[derive(Clone, Copy, Debug)]
+pub enum Distribution {
+    /// Every `usize` is equally likely.
+    Uniform,
+    /// The ranks 1 to 2^20 with the given exponent, so that a few small
+    /// values make up most of the array.
+    Zipf(f64),
+    /// Values from a handful of narrow ranges placed by the given seed. Arrays
+    /// generated with the same seed share their ranges.
+    Clustered(u64),
+}
+
+impl Distribution {
+    /// Describes the distribution for the run header.
+    pub fn description(self) -> String {
+        match self {
+            Distribution::Uniform => "uniform".to_string(),
+            Distribution::Zipf(exponent) => format!("zipf (exponent {})", exponent),
+            Distribution::Clustered(_) => format!(
+                "clustered ({} ranges of {} values)",
+                CLUSTERS, CLUSTER_WIDTH
+            ),
+        }
+    }
+
+    /// Draws values below `max_value`, if given.
+    fn sampler(self, max_value: Option<usize>) -> impl Fn(&mut StdRng) -> usize + Sync {
+        let zipf = match self {
+            Distribution::Zipf(exponent) => {
+                let values = max_value.map_or(ZIPF_VALUES, |max| ZIPF_VALUES.min(max as u64));
+                Some(Zipf::new(values, exponent).unwrap())
+            }
+            _ => None,
+        };
+        let (end, width) = match max_value {
+            None => (usize::MAX - CLUSTER_WIDTH, CLUSTER_WIDTH),
+            Some(max) => {
+                let width = CLUSTER_WIDTH.min(max);
+                (max - width + 1, width)
+            }
+        };
+        let starts: Vec<usize> = match self {
+            Distribution::Clustered(seed) => {
+                let mut rng = StdRng::seed_from_u64(seed);
+                (0..CLUSTERS).map(|_| rng.gen_range(0..end)).collect()
+            }
+            _ => Vec::new(),
+        };
+        move |rng: &mut StdRng| match (self, max_value) {
+            (Distribution::Uniform, None) => rng.gen(),
+            (Distribution::Uniform, Some(max)) => rng.gen_range(0..max),
+            (Distribution::Zipf(_), None) => zipf.unwrap().sample(rng) as usize,
+            // Shift the ranks down so that 0 is the most common value.
+            (Distribution::Zipf(_), Some(_)) => zipf.unwrap().sample(rng) as usize - 1,
+            (Distribution::Clustered(_), _) => {
+                starts[rng.gen_range(0..CLUSTERS)] + rng.gen_range(0..width)
+            }
+        }
+    }
+}
+
+/// Generates `size` random values, or a random number of them if `size` is
+/// `None`. If `max_value` is given, every value is below it.
+///
+/// The values only depend on the state of `rng`, not on the number of threads.
+pub fn generate(
+    rng: &mut StdRng,
+    size: Option<usize>,
+    distribution: Distribution,
+    max_value: Option<usize>,
+) -> Vec<usize> {
+    let size = size.unwrap_or(rng.gen::<u16>() as usize);
+    let seed: u64 = rng.gen();
+    let sample = distribution.sampler(max_value);
+    let mut values = vec![0; size];
+    values
+        .par_chunks_mut(CHUNK_SIZE)
+        .enumerate()
+        .for_each(|(i, chunk)| {
+            let mut rng = StdRng::seed_from_u64(seed.wrapping_add(i as u64));
+            chunk.iter_mut().for_each(|x| *x = sample(&mut rng));
+        });
+    values
+}
+
+/// Generates `count` membership queries for `values`: half of them are drawn
+/// from `values`, unless it is empty, and the others are fresh values like
+/// [`generate`] makes, in shuffled order.
+///
+/// Like [`generate`], the queries only depend on the state of `rng`.
+pub fn queries(
+    rng: &mut StdRng,
+    values: &[usize],
+    count: usize,
+    distribution: Distribution,
+    max_value: Option<usize>,
+) -> Vec<usize> {
+    let mut queries = generate(rng, Some(count), distribution, max_value);
+    let seed: u64 = rng.gen();
+    if !values.is_empty() {
+        queries[..count / 2]
+            .par_chunks_mut(CHUNK_SIZE)
+            .enumerate()
+            .for_each(|(i, chunk)| {
+                let mut rng = StdRng::seed_from_u64(seed.wrapping_add(i as u64));
+                chunk
+                    .iter_mut()
+                    .for_each(|x| *x = values[rng.gen_range(0..values.len())]);
+            });
+    }
+    queries.shuffle(&mut StdRng::seed_from_u64(seed));
+    queries
+}
+
+/// Replaces about `fraction` of the elements of `small` with elements drawn
+/// from `big`, so that the arrays share that many elements.
+///
+/// Like [`generate`], the result only depends on the state of `rng`.
+pub fn overlap(rng: &mut StdRng, big: &[usize], small: &mut [usize], fraction: f64) {
+    let seed: u64 = rng.gen();
+    if big.is_empty() {
+        return;
+    }
+    small
+        .par_chunks_mut(CHUNK_SIZE)
+        .enumerate()
+        .for_each(|(i, chunk)| {
+            let mut rng = StdRng::seed_from_u64(seed.wrapping_add(i as u64));
+            chunk.iter_mut().for_each(|x| {
+                if rng.gen_bool(fraction) {
+                    *x = big[rng.gen_range(0..big.len())];
+                }
+            });
+        });
+}
+
+/// A shape of the generated arrays that is hard to get from random values.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Preset {
+    /// Independent random values, shaped by the distribution and overlap.
+    #[default]
+    Random,
+    /// Arrays without a value in common.
+    Disjoint,
+    /// The same values in both arrays.
+    Identical,
+    /// A second array whose values all occur in the first.
+    Subset,
+    /// A big first array and a tiny second one.
+    Skewed,
+    /// Arrays that repeat every value many times.
+    Duplicates,
+}
+
+impl Preset {
+    /// The name of the preset, as given to `--preset`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Preset::Random => "random",
+            Preset::Disjoint => "disjoint",
+            Preset::Identical => "identical",
+            Preset::Subset => "subset",
+            Preset::Skewed => "skewed",
+            Preset::Duplicates => "duplicates",
+        }
+    }
+
+    /// Describes the shape for the run header.
+    pub fn description(self) -> String {
+        let shape = match self {
+            Preset::Random => return self.name().to_string(),
+            Preset::Disjoint => "even values in the first array, odd ones in the second",
+            Preset::Identical => "the second array is a shuffled copy of the first",
+            Preset::Subset => "the second array is drawn from the first",
+            Preset::Skewed => "half of the tiny second array is drawn from the big first one",
+            Preset::Duplicates => {
+                return format!(
+                    "{}: both arrays draw from {} values",
+                    self.name(),
+                    DUPLICATE_VALUES
+                )
+            }
+        };
+        format!("{}: {}", self.name(), shape)
+    }
+
+    /// Generates both arrays of the sizes given, or of random ones like
+    /// [`generate`] picks, from uniform values.
+    ///
+    /// Like [`generate`], the arrays only depend on the state of `rng`.
+    pub fn generate(
+        self,
+        rng: &mut StdRng,
+        sizes: (Option<usize>, Option<usize>),
+    ) -> (Vec<usize>, Vec<usize>) {
+        match self {
+            Preset::Random => (
+                generate(rng, sizes.0, Distribution::Uniform, None),
+                generate(rng, sizes.1, Distribution::Uniform, None),
+            ),
+            Preset::Disjoint => disjoint(rng, sizes),
+            Preset::Identical => identical(rng, sizes.0),
+            Preset::Subset => subset(rng, sizes),
+            Preset::Skewed => skewed(rng, sizes),
+            Preset::Duplicates => duplicates(rng, sizes),
+        }
+    }
+}
+
+/// Generates arrays without a value in common, by keeping the even values
+/// in the first and the odd ones in the second.
+pub fn disjoint(
+    rng: &mut StdRng,
+    sizes: (Option<usize>, Option<usize>),
+) -> (Vec<usize>, Vec<usize>) {
+    let mut a = generate(rng, sizes.0, Distribution::Uniform, None);
+    let mut b = generate(rng, sizes.1, Distribution::Uniform, None);
+    a.par_iter_mut().for_each(|x| *x &= !1);
+    b.par_iter_mut().for_each(|x| *x |= 1);
+    (a, b)
+}
+
+/// Generates an array and a shuffled copy of it.
+pub fn identical(rng: &mut StdRng, size: Option<usize>) -> (Vec<usize>, Vec<usize>) {
+    let a = generate(rng, size, Distribution::Uniform, None);
+    let mut b = a.clone();
+    b.shuffle(&mut StdRng::seed_from_u64(rng.gen()));
+    (a, b)
+}
+
+/// Generates an array and a second one of elements drawn from it, at most
+/// as many as the first has if the size isn't given and none if the first is
+/// empty.
+pub fn subset(rng: &mut StdRng, sizes: (Option<usize>, Option<usize>)) -> (Vec<usize>, Vec<usize>) {
+    let a = generate(rng, sizes.0, Distribution::Uniform, None);
+    let size = match a.len() {
+        0 => 0,
+        len => sizes.1.unwrap_or_else(|| rng.gen_range(0..=len)),
+    };
+    let mut b = generate(rng, Some(size), Distribution::Uniform, None);
+    overlap(rng, &a, &mut b, 1.0);
+    (a, b)
+}
+
+/// Generates a big array and a tiny one, 10 million and 10 elements if the
+/// sizes aren't given, half of the tiny one drawn from the big one.
+pub fn skewed(rng: &mut StdRng, sizes: (Option<usize>, Option<usize>)) -> (Vec<usize>, Vec<usize>) {
+    let a = generate(
+        rng,
+        Some(sizes.0.unwrap_or(SKEWED_SIZES.0)),
+        Distribution::Uniform,
+        None,
+    );
+    let mut b = generate(
+        rng,
+        Some(sizes.1.unwrap_or(SKEWED_SIZES.1)),
+        Distribution::Uniform,
+        None,
+    );
+    let half = b.len() / 2;
+    let seed: u64 = rng.gen();
+    if !a.is_empty() {
+        let mut rng = StdRng::seed_from_u64(seed);
+        b[..half]
+            .iter_mut()
+            .for_each(|x| *x = a[rng.gen_range(0..a.len())]);
+    }
+    (a, b)
+}
+
+/// Generates arrays of values below 1000, so that both hold every value
+/// many times once they have a few thousand elements.
+pub fn duplicates(
+    rng: &mut StdRng,
+    sizes: (Option<usize>, Option<usize>),
+) -> (Vec<usize>, Vec<usize>) {
+    let max_value = Some(DUPLICATE_VALUES);
+    (
+        generate(rng, sizes.0, Distribution::Uniform, max_value),
+        generate(rng, sizes.1, Distribution::Uniform, max_value),
+    )
+}
+
+/// The number of elements of `small` that occur in `big`.
+pub fn shared<T: Element>(big: &[T], small: &[T]) -> usize {
+    let big: HashSet<&T> = big.iter().collect();
+    small.par_iter().filter(|x| big.contains(x)).count()
+}
+
+/// The number of distinct values that occur in both arrays.
+pub fn distinct_shared<T: Element>(a: &[T], b: &[T]) -> usize {
+    let a: HashSet<&T> = a.iter().collect();
+    let b: HashSet<&T> = b.iter().collect();
+    a.intersection(&b).count()
+}
+
+/// The number of distinct values in an array.
+pub fn distinct<T: Element>(array: &[T]) -> usize {
+    array.iter().collect::<HashSet<_>>().len()
+}
+
+/// How similar two arrays are when both are read as sets.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+pub struct Similarity {
+    /// The number of distinct values in both arrays.
+    pub shared: usize,
+    /// The number of distinct values in either array.
+    pub union: usize,
+    /// `shared / union`, or zero when both arrays are empty.
+    pub jaccard: f64,
+    /// `shared` divided by the distinct values of the array with fewer of
+    /// them, or zero when that array is empty.
+    pub containment: f64,
+}
+
+impl Similarity {
+    /// Computes the metrics from the number of distinct values in each array
+    /// and an intersection of them, which may hold duplicates.
+    pub fn new<T: Element>(distinct: (usize, usize), intersection: &[T]) -> Self {
+        Similarity::from_shared(distinct, self::distinct(intersection))
+    }
+
+    /// Computes the metrics from the number of distinct values in each array
+    /// and in both of them.
+    pub fn from_shared(distinct: (usize, usize), shared: usize) -> Self {
+        let union = distinct.0 + distinct.1 - shared;
+        let ratio = |of: usize| match of {
+            0 => 0.0,
+            of => shared as f64 / of as f64,
+        };
+        Similarity {
+            shared,
+            union,
+            jaccard: ratio(union),
+            containment: ratio(distinct.0.min(distinct.1)),
+        }
+    }
+}
+
+/// An element type the generated and read `usize` values can be converted to.
+pub trait FromValue: Element + 'static {
+    /// What else a generated element depends on, like the length of a string.
+    type Options: Copy + Send + Sync;
+
+    /// Converts a generated value so that equal values stay equal, keeping
+    /// the bits that fit.
+    fn from_generated(value: usize, options: Self::Options) -> Self;
+    /// Converts a value read from an input file, or returns `None` if it
+    /// doesn't fit.
+    fn from_input(value: usize) -> Option<Self>;
+    /// Describes the elements for the report, like `u32 values of 4 bytes`.
+    fn describe(options: Self::Options) -> String;
+}
+
+macro_rules! from_value {
+    ($($t:ty),*) => {$(
+        impl FromValue for $t {
+            type Options = ();
+
+            fn from_generated(value: usize, _: ()) -> Self {
+                value as $t
+            }
+
+            fn from_input(value: usize) -> Option<Self> {
+                value.try_into().ok()
+            }
+
+            fn describe(_: ()) -> String {
+                format!("{} values of {} bytes", stringify!($t), size_of::<$t>())
+            }
+        }
+    )*};
+}
+
+from_value!(u32, u64, usize, i64);
+
+/// Generated values become random alphanumeric strings of the given length,
+/// read values their decimal digits.
+impl FromValue for String {
+    type Options = usize;
+
+    fn from_generated(value: usize, length: usize) -> Self {
+        StdRng::seed_from_u64(value as u64)
+            .sample_iter(Alphanumeric)
+            .take(length)
+            .map(char::from)
+            .collect()
+    }
+
+    fn from_input(value: usize) -> Option<Self> {
+        Some(value.to_string())
+    }
+
+    fn describe(length: usize) -> String {
+        format!("strings of {} characters", length)
+    }
+}
+
+/// Reads whitespace separated values, reporting the line of the first value
+/// that isn't a valid `usize`.
+pub fn read_text(reader: impl BufRead) -> Result<Vec<usize>, String> {
+    let mut values = Vec::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line.map_err(|e| e.to_string())?;
+        for value in line.split_whitespace() {
+            let value = value
+                .parse()
+                .map_err(|e| format!("line {}: invalid value '{}': {}", i + 1, value, e))?;
+            values.push(value);
+        }
+    }
+    Ok(values)
+}
+
+/// Reads consecutive little-endian `u64` values.
+pub fn read_binary(mut reader: impl Read) -> Result<Vec<usize>, String> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes).map_err(|e| e.to_string())?;
+    from_le_bytes(&bytes)
+}
+
+fn from_le_bytes(bytes: &[u8]) -> Result<Vec<usize>, String> {
+    if !bytes.len().is_multiple_of(8) {
+        return Err(format!(
+            "{} bytes is not a whole number of 8 byte values",
+            bytes.len()
+        ));
+    }
+    bytes
+        .chunks_exact(8)
+        .map(|chunk| {
+            let value = u64::from_le_bytes(chunk.try_into().unwrap());
+            usize::try_from(value).map_err(|_| format!("{} doesn't fit in a usize", value))
+        })
+        .collect()
+}
+
+/// Writes both arrays and the seed they were generated with, so that
+/// [`read_dataset`] can restore them exactly.
+///
+/// The file starts with a magic number followed by the seed and the lengths
+/// of the arrays, then holds the values of both arrays. Every number is a
+/// little-endian `u64`.
+pub fn write_dataset(mut out: impl Write, seed: u64, a: &[usize], b: &[usize]) -> io::Result<()> {
+    out.write_all(MAGIC)?;
+    for x in [seed, a.len() as u64, b.len() as u64] {
+        out.write_all(&x.to_le_bytes())?;
+    }
+    for x in a.iter().chain(b) {
+        out.write_all(&(*x as u64).to_le_bytes())?;
+    }
+    out.flush()
+}
+
+/// Reads a file written by [`write_dataset`], returning the seed and both
+/// arrays.
+pub fn read_dataset(mut reader: impl Read) -> Result<(u64, Vec<usize>, Vec<usize>), String> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes).map_err(|e| e.to_string())?;
+    let header = MAGIC.len() + 3 * 8;
+    if bytes.len() < header || &bytes[..MAGIC.len()] != MAGIC {
+        return Err("not a dataset written by --save-data".to_string());
+    }
+    let number = |i: usize| {
+        let start = MAGIC.len() + i * 8;
+        u64::from_le_bytes(bytes[start..start + 8].try_into().unwrap())
+    };
+    let (seed, len_a, len_b) = (number(0), number(1), number(2));
+    let mut values = from_le_bytes(&bytes[header..])?;
+    if len_a.checked_add(len_b) != Some(values.len() as u64) {
+        return Err(format!(
+            "the header announces {} and {} values but the file holds {}",
+            len_a,
+            len_b,
+            values.len()
+        ));
+    }
+    let b = values.split_off(len_a as usize);
+    Ok((seed, values, b))
+}
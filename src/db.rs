@@ -0,0 +1,215 @@
+//! Storing the times of every run in a SQLite database and summarizing them.
+
+use std::{path::Path, time::Duration};
+
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::{HistoryRecord, HistoryStats};
+
+/// The statements that bring the schema from the version of their index to
+/// the next one. Released statements must never change, new ones go last.
+const MIGRATIONS: &[&str] = &["
+    CREATE TABLE runs (
+        id INTEGER PRIMARY KEY,
+        timestamp_ms INTEGER NOT NULL,
+        hostname TEXT NOT NULL,
+        seed INTEGER NOT NULL,
+        size_a INTEGER NOT NULL,
+        size_b INTEGER NOT NULL,
+        preset TEXT NOT NULL,
+        environment TEXT,
+        scenario TEXT
+    );
+    CREATE TABLE results (
+        run_id INTEGER NOT NULL REFERENCES runs (id),
+        position INTEGER NOT NULL,
+        name TEXT NOT NULL,
+        time_ns INTEGER NOT NULL,
+        runs INTEGER NOT NULL,
+        mean_ns INTEGER NOT NULL,
+        std_dev_ns INTEGER NOT NULL,
+        min_ns INTEGER NOT NULL,
+        max_ns INTEGER NOT NULL,
+        result_len INTEGER NOT NULL,
+        PRIMARY KEY (run_id, position)
+    );
+    CREATE INDEX results_by_name ON results (name);
+"];
+
+/// The version of the schema this build creates and reads.
+pub const DB_SCHEMA_VERSION: usize = MIGRATIONS.len();
+
+/// A database of runs, with a table of the runs and one of the times of
+/// their products.
+///
+/// SQLite only stores signed integers, so seeds and nanoseconds above
+/// `i64::MAX` are stored wrapped around and read back as they were.
+pub struct Database {
+    connection: Connection,
+}
+
+impl Database {
+    /// Opens the database at `path`, creating it if it doesn't exist, and
+    /// migrates it to [`DB_SCHEMA_VERSION`].
+    ///
+    /// Fails for databases of a newer schema.
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let connection = Connection::open(path).map_err(|e| e.to_string())?;
+        let mut database = Database { connection };
+        database.migrate()?;
+        Ok(database)
+    }
+
+    /// The version of the schema of the database.
+    pub fn version(&self) -> Result<usize, String> {
+        self.read_version().map_err(|e| e.to_string())
+    }
+
+    fn read_version(&self) -> rusqlite::Result<usize> {
+        self.connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS schema_version (version INTEGER NOT NULL)",
+        )?;
+        let version: Option<i64> = self
+            .connection
+            .query_row("SELECT version FROM schema_version", [], |row| row.get(0))
+            .optional()?;
+        Ok(version.unwrap_or(0) as usize)
+    }
+
+    /// Runs the migrations the database hasn't seen yet in one transaction.
+    fn migrate(&mut self) -> Result<(), String> {
+        let version = self.read_version().map_err(|e| e.to_string())?;
+        if version > DB_SCHEMA_VERSION {
+            return Err(format!(
+                "schema version {} is newer than the supported version {}",
+                version, DB_SCHEMA_VERSION
+            ));
+        }
+        if version == DB_SCHEMA_VERSION {
+            return Ok(());
+        }
+        let transaction = self.connection.transaction().map_err(|e| e.to_string())?;
+        for migration in &MIGRATIONS[version..] {
+            transaction
+                .execute_batch(migration)
+                .map_err(|e| e.to_string())?;
+        }
+        transaction
+            .execute_batch("DELETE FROM schema_version")
+            .and_then(|_| {
+                transaction.execute(
+                    "INSERT INTO schema_version (version) VALUES (?1)",
+                    [DB_SCHEMA_VERSION as i64],
+                )
+            })
+            .and_then(|_| transaction.commit())
+            .map_err(|e| e.to_string())
+    }
+
+    /// Inserts the run and the times of its products in one transaction,
+    /// returning the id of the run.
+    pub fn insert(&mut self, record: &HistoryRecord) -> Result<i64, String> {
+        self.try_insert(record).map_err(|e| e.to_string())
+    }
+
+    fn try_insert(&mut self, record: &HistoryRecord) -> Result<i64, Box<dyn std::error::Error>> {
+        let run = &record.run;
+        let environment = run
+            .environment
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()?;
+        let scenario = run
+            .scenario
+            .as_ref()
+            .map(serde_json::to_string)
+            .transpose()?;
+        let transaction = self.connection.transaction()?;
+        transaction.execute(
+            "INSERT INTO runs (timestamp_ms, hostname, seed, size_a, size_b, preset, environment, \
+             scenario) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                record.timestamp_ms as i64,
+                record.hostname,
+                run.seed as i64,
+                run.size_a as i64,
+                run.size_b as i64,
+                run.preset.name(),
+                environment,
+                scenario,
+            ],
+        )?;
+        let id = transaction.last_insert_rowid();
+        for (position, time) in run.times.iter().enumerate() {
+            transaction.execute(
+                "INSERT INTO results (run_id, position, name, time_ns, runs, mean_ns, \
+                 std_dev_ns, min_ns, max_ns, result_len) \
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                params![
+                    id,
+                    position as i64,
+                    time.name,
+                    time.time_ns as i64,
+                    time.runs as i64,
+                    time.mean_ns as i64,
+                    time.std_dev_ns as i64,
+                    time.min_ns as i64,
+                    time.max_ns as i64,
+                    time.result_len as i64,
+                ],
+            )?;
+        }
+        transaction.commit()?;
+        Ok(id)
+    }
+
+    /// The shortest, median and longest time of every product, in the order
+    /// the products were first recorded, over the [`Database::runs`] between
+    /// `min` and `max`.
+    pub fn summary(
+        &self,
+        min: Option<usize>,
+        max: Option<usize>,
+    ) -> Result<Vec<HistoryStats>, String> {
+        let mut statement = self
+            .connection
+            .prepare(
+                "SELECT results.name, results.time_ns FROM results \
+                 JOIN runs ON runs.id = results.run_id \
+                 WHERE max(runs.size_a, runs.size_b) BETWEEN ?1 AND ?2 \
+                 ORDER BY runs.id, results.position",
+            )
+            .map_err(|e| e.to_string())?;
+        let times: Vec<(String, Duration)> = statement
+            .query_map(bounds(min, max), |row| {
+                let time: i64 = row.get(1)?;
+                Ok((row.get(0)?, Duration::from_nanos(time as u64)))
+            })
+            .and_then(|rows| rows.collect())
+            .map_err(|e| e.to_string())?;
+        Ok(HistoryStats::from_times(
+            times.iter().map(|(name, time)| (name.as_str(), *time)),
+        ))
+    }
+
+    /// The number of runs whose bigger array has at least `min` and at most
+    /// `max` elements.
+    pub fn runs(&self, min: Option<usize>, max: Option<usize>) -> Result<usize, String> {
+        self.connection
+            .query_row(
+                "SELECT count(*) FROM runs WHERE max(size_a, size_b) BETWEEN ?1 AND ?2",
+                bounds(min, max),
+                |row| row.get::<_, i64>(0),
+            )
+            .map(|count| count as usize)
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// The bounds of a size filter, all sizes if neither is given.
+fn bounds(min: Option<usize>, max: Option<usize>) -> [i64; 2] {
+    [
+        min.unwrap_or(0) as i64,
+        max.map_or(i64::MAX, |max| max as i64),
+    ]
+}
@@ -0,0 +1,225 @@
+//! Comparing the times of two saved runs.
+
+use std::{
+    io::{self, Read, Write},
+    time::Duration,
+};
+
+use prettytable::{Cell, Row, Table};
+use serde::Deserialize;
+
+use crate::{
+    record::{check_schema, ProductRecord},
+    Environment, Preset, TimeUnit,
+};
+
+/// A run read back from a JSON report or a baseline.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct SavedRun {
+    /// The schema the file was written with.
+    #[serde(default)]
+    pub schema_version: u32,
+    /// The seed the arrays were generated with.
+    pub seed: u64,
+    /// The number of elements in the first array.
+    pub size_a: usize,
+    /// The number of elements in the second array.
+    pub size_b: usize,
+    /// The products that ran, called `times` in baselines.
+    #[serde(alias = "times")]
+    pub products: Vec<ProductRecord>,
+    /// Where the run happened, if the file recorded it.
+    #[serde(default)]
+    pub environment: Option<Environment>,
+    /// The shape the arrays were generated in.
+    #[serde(default)]
+    pub preset: Preset,
+}
+
+impl SavedRun {
+    /// Reads a report written by `--json` or a baseline, failing for a newer
+    /// schema.
+    pub fn read(reader: impl Read) -> Result<Self, String> {
+        let run: Self = serde_json::from_reader(reader).map_err(|e| e.to_string())?;
+        check_schema(run.schema_version)?;
+        Ok(run)
+    }
+}
+
+/// The times of a product in both runs.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiffRow {
+    /// The name of the product.
+    pub name: String,
+    /// The aggregated time in the old run, `None` if it timed out.
+    pub old: Option<Duration>,
+    /// The aggregated time in the new run, `None` if it timed out.
+    pub new: Option<Duration>,
+}
+
+impl DiffRow {
+    /// The change in percent of the old time, positive if the product got
+    /// slower, or `None` if either run timed out or the old one took no time.
+    pub fn percent(&self) -> Option<f64> {
+        match (self.old, self.new) {
+            (Some(old), Some(new)) if !old.is_zero() => {
+                Some((new.as_nanos() as f64 / old.as_nanos() as f64 - 1.0) * 100.0)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// How the products and the input of two runs differ.
+pub struct RunDiff {
+    /// The products that ran in both, in the order of the new run.
+    pub rows: Vec<DiffRow>,
+    /// The names and times of the products only the old run has.
+    pub only_old: Vec<(String, Option<Duration>)>,
+    /// The names and times of the products only the new run has.
+    pub only_new: Vec<(String, Option<Duration>)>,
+    /// What the runs were measured on differently, like `seed: 1 in the old
+    /// run, 2 in the new one`.
+    pub differences: Vec<String>,
+}
+
+impl RunDiff {
+    /// Pairs the products of both runs by name, leaving out the skipped ones.
+    pub fn new(old: &SavedRun, new: &SavedRun) -> Self {
+        let ran = |run: &SavedRun| -> Vec<ProductRecord> {
+            run.products
+                .iter()
+                .filter(|record| record.skipped.is_none())
+                .cloned()
+                .collect()
+        };
+        let (old_products, new_products) = (ran(old), ran(new));
+        let time = |record: &ProductRecord| {
+            record
+                .timed_out_ns
+                .is_none()
+                .then(|| Duration::from_nanos(record.time_ns))
+        };
+        let rows = new_products
+            .iter()
+            .filter_map(|record| {
+                let previous = old_products.iter().find(|old| old.name == record.name)?;
+                Some(DiffRow {
+                    name: record.name.clone(),
+                    old: time(previous),
+                    new: time(record),
+                })
+            })
+            .collect();
+        let missing = |from: &[ProductRecord], to: &[ProductRecord]| {
+            from.iter()
+                .filter(|record| !to.iter().any(|other| other.name == record.name))
+                .map(|record| (record.name.clone(), time(record)))
+                .collect()
+        };
+        let mut differences: Vec<String> = [
+            ("seed", old.seed.to_string(), new.seed.to_string()),
+            (
+                "sizes",
+                format!("{} and {}", old.size_a, old.size_b),
+                format!("{} and {}", new.size_a, new.size_b),
+            ),
+            (
+                "preset",
+                old.preset.name().to_string(),
+                new.preset.name().to_string(),
+            ),
+        ]
+        .into_iter()
+        .filter(|(_, old, new)| old != new)
+        .map(|(name, old, new)| format!("{}: {} in the old run, {} in the new one", name, old, new))
+        .collect();
+        if let (Some(old), Some(new)) = (&old.environment, &new.environment) {
+            differences.extend(new.differences_between(old, "in the old run", "in the new one"));
+        }
+        RunDiff {
+            rows,
+            only_old: missing(&old_products, &new_products),
+            only_new: missing(&new_products, &old_products),
+            differences,
+        }
+    }
+}
+
+/// Prints how the runs differ, then the old and new time of every product
+/// with the difference and the change, followed by the products only one run
+/// has, colored yellow when `color` is set, in which case the table always
+/// goes to stdout.
+pub fn print_diff(
+    out: &mut impl Write,
+    diff: &RunDiff,
+    unit: TimeUnit,
+    color: bool,
+) -> io::Result<()> {
+    if !diff.differences.is_empty() {
+        writeln!(
+            out,
+            "note: the runs were measured differently, their times may not compare:"
+        )?;
+        for difference in &diff.differences {
+            writeln!(out, "  {}", difference)?;
+        }
+        writeln!(out)?;
+    }
+    let format = |time: Option<Duration>| time.map_or("timed out".to_string(), |t| unit.format(t));
+    let mut table = Table::new();
+    table.add_row(Row::new(
+        ["Name", "Old time", "New time", "Difference", "Change"]
+            .iter()
+            .map(|cell| Cell::new(cell))
+            .collect(),
+    ));
+    for row in &diff.rows {
+        let difference = match (row.old, row.new) {
+            (Some(old), Some(new)) if new >= old => format!("+{}", unit.format(new - old)),
+            (Some(old), Some(new)) => format!("-{}", unit.format(old - new)),
+            _ => "n/a".to_string(),
+        };
+        let change = row
+            .percent()
+            .map_or("n/a".to_string(), |percent| format!("{:+.2}%", percent));
+        table.add_row(Row::new(
+            [
+                row.name.clone(),
+                format(row.old),
+                format(row.new),
+                difference,
+                change,
+            ]
+            .iter()
+            .map(|cell| Cell::new(cell))
+            .collect(),
+        ));
+    }
+    for (name, time) in &diff.only_old {
+        let cells = [name.clone(), format(*time), "-".to_string()];
+        table.add_row(only_in_one(cells, "only in the old run", color));
+    }
+    for (name, time) in &diff.only_new {
+        let cells = [name.clone(), "-".to_string(), format(*time)];
+        table.add_row(only_in_one(cells, "only in the new run", color));
+    }
+    if color {
+        out.flush()?;
+        table.print_tty(true)?;
+    } else {
+        table.print(out)?;
+    }
+    Ok(())
+}
+
+/// A row for a product only one run has, which says so as its change.
+fn only_in_one(cells: [String; 3], note: &str, color: bool) -> Row {
+    let style = if color { "Fy" } else { "" };
+    cells
+        .iter()
+        .map(String::as_str)
+        .chain(["n/a", note])
+        .map(|cell| Cell::new(cell).style_spec(style))
+        .collect()
+}
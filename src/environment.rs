@@ -0,0 +1,98 @@
+//! Describing the machine and build that produced the results.
+
+use std::thread::available_parallelism;
+
+use rayon::current_num_threads;
+use serde::{Deserialize, Serialize};
+
+/// The machine and build a run happened on, so that results can be told
+/// apart from the ones of another environment.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct Environment {
+    /// The number of logical CPUs.
+    pub cpus: usize,
+    /// The number of threads in the pool the parallel flavors run on.
+    pub threads: usize,
+    /// The target triple the binary was built for.
+    pub target: String,
+    /// `release` for optimized builds, `debug` otherwise.
+    pub profile: String,
+    /// The version of this crate.
+    pub version: String,
+    /// The compiler the binary was built with, like `rustc 1.80.0 (051478957 2024-07-21)`.
+    pub rustc: String,
+}
+
+impl Environment {
+    /// The environment of this process, with the threads of the current pool.
+    pub fn current() -> Self {
+        Environment {
+            cpus: available_parallelism().map_or(1, |cpus| cpus.get()),
+            threads: current_num_threads(),
+            target: env!("COMPARE_SPEED_TARGET").to_string(),
+            profile: if cfg!(debug_assertions) {
+                "debug"
+            } else {
+                "release"
+            }
+            .to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            rustc: env!("COMPARE_SPEED_RUSTC").to_string(),
+        }
+    }
+
+    /// Whether the binary was built without optimizations.
+    pub fn is_debug(&self) -> bool {
+        self.profile == "debug"
+    }
+
+    /// Describes how this environment differs from `baseline`, one entry per
+    /// difference, like `threads: 8 in the baseline, 16 now`.
+    pub fn differences(&self, baseline: &Environment) -> Vec<String> {
+        self.differences_between(baseline, "in the baseline", "now")
+    }
+
+    /// Describes how this environment differs from `other`, with the values
+    /// of `other` marked `then` and the own ones `now`.
+    pub fn differences_between(&self, other: &Environment, then: &str, now: &str) -> Vec<String> {
+        let baseline = other;
+        let fields: [(&str, String, String); 6] = [
+            (
+                "logical CPUs",
+                baseline.cpus.to_string(),
+                self.cpus.to_string(),
+            ),
+            (
+                "threads",
+                baseline.threads.to_string(),
+                self.threads.to_string(),
+            ),
+            ("target", baseline.target.clone(), self.target.clone()),
+            ("build", baseline.profile.clone(), self.profile.clone()),
+            ("version", baseline.version.clone(), self.version.clone()),
+            ("compiler", baseline.rustc.clone(), self.rustc.clone()),
+        ];
+        fields
+            .into_iter()
+            .filter(|(_, baseline, current)| baseline != current)
+            .map(|(name, baseline, current)| {
+                format!("{}: {} {}, {} {}", name, baseline, then, current, now)
+            })
+            .collect()
+    }
+
+    /// Describes the build and the machine for the report header, like
+    /// `compare-speed 0.1.0, a release build for x86_64-unknown-linux-gnu by
+    /// rustc 1.80.0 (051478957 2024-07-21), on 16 logical CPUs`.
+    pub fn description(&self) -> String {
+        format!(
+            "compare-speed {}, a {} build for {} by {}, on {} logical CPU{}",
+            self.version,
+            self.profile,
+            self.target,
+            self.rustc,
+            self.cpus,
+            if self.cpus == 1 { "" } else { "s" }
+        )
+    }
+}
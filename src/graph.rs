@@ -0,0 +1,413 @@
+//! Rendering the results as a bar graph, and a sweep as a chart.
+
+use std::{
+    env,
+    io::{self, Write},
+    time::Duration,
+};
+
+use clap::ValueEnum;
+use crossterm::{style::Stylize, terminal::size};
+
+use crate::{
+    format_compact,
+    report::{highlight, time_range, Highlight},
+    sweep::names,
+    Product, SweepPoint, TimeUnit,
+};
+
+/// How durations are mapped to the length of a bar.
+#[derive(Clone, Copy, ValueEnum)]
+pub enum GraphScale {
+    /// Bars are proportional to the time taken.
+    Linear,
+    /// Bars are proportional to the logarithm of the time taken.
+    Log,
+}
+
+impl GraphScale {
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            GraphScale::Linear => "linear",
+            GraphScale::Log => "log",
+        }
+    }
+
+    /// The position of a duration on the scale, before normalizing to the graph width.
+    fn value(self, duration: Duration) -> f64 {
+        match self {
+            GraphScale::Linear => duration.as_nanos() as f64,
+            // durations below the timer resolution are drawn as if they took 1ns
+            GraphScale::Log => (duration.as_nanos().max(1) as f64).ln(),
+        }
+    }
+}
+
+/// The width of the terminal, falling back to `COLUMNS` or 80 columns when
+/// stdout is not a terminal or reports no width.
+pub fn terminal_width() -> usize {
+    size()
+        .ok()
+        .map(|(width, _)| width as usize)
+        .filter(|width| *width > 0)
+        .or_else(|| env::var("COLUMNS").ok()?.parse().ok())
+        .unwrap_or(80)
+}
+
+/// How the graph is drawn.
+#[derive(Clone, Copy)]
+pub struct GraphOptions {
+    /// Total width of a line, including the name column.
+    pub width: usize,
+    /// How durations are mapped to bar lengths.
+    pub scale: GraphScale,
+    /// Draw whole `*` characters instead of Unicode eighth blocks.
+    pub ascii: bool,
+    /// Separate the bars of different methods and flavors by an empty line,
+    /// for products whose orders are grouped with
+    /// [`group_orders`](crate::group_orders).
+    pub pairs: bool,
+    /// The unit of the duration written after every bar, or `None` to draw
+    /// the bars alone.
+    pub labels: Option<TimeUnit>,
+    /// Draw an axis with a tick at every power of ten under the bars of a
+    /// log graph.
+    pub axis: bool,
+}
+
+const EIGHTHS: [char; 8] = [' ', '▏', '▎', '▍', '▌', '▋', '▊', '▉'];
+
+/// Draws a bar covering `fraction` of `width` characters.
+pub(crate) fn bar(fraction: f64, width: usize, ascii: bool) -> String {
+    if ascii {
+        return "*".repeat((fraction * width as f64).round() as usize);
+    }
+    let eighths = (fraction * width as f64 * 8.0).round() as usize;
+    let mut bar = "█".repeat(eighths / 8);
+    if !eighths.is_multiple_of(8) {
+        bar.push(EIGHTHS[eighths % 8]);
+    }
+    bar
+}
+
+/// The fraction of the graph width the bar of every product covers, from 0
+/// for the fastest to 1 for the slowest.
+pub(crate) fn fractions<T>(products: &[Product<T>], scale: GraphScale) -> Vec<f64> {
+    let value = |product: &Product<T>| scale.value(product.time);
+    let (fastest, slowest) = time_range(products);
+    let min = scale.value(fastest);
+    let range = scale.value(slowest) - min;
+    products
+        .iter()
+        .map(|product| {
+            if range > 0.0 {
+                (value(product) - min) / range
+            } else {
+                1.0
+            }
+        })
+        .collect()
+}
+
+/// Ends the bar of a product that timed out.
+const TIMED_OUT: char = '>';
+
+/// The label of a tick at a power of ten nanoseconds, like `10µs`.
+fn decade_label(nanos: u64) -> String {
+    match nanos {
+        n if n < 1_000 => format!("{}ns", n),
+        n if n < 1_000_000 => format!("{}µs", n / 1_000),
+        n if n < 1_000_000_000 => format!("{}ms", n / 1_000_000),
+        n => format!("{}s", n / 1_000_000_000),
+    }
+}
+
+/// Places every label at the start of its column, moved left where it
+/// would end past `width`, or returns `None` if two labels would touch.
+fn place_labels(labels: &[(usize, String)], width: usize) -> Option<String> {
+    let mut line = String::new();
+    let mut used = 0;
+    for (column, label) in labels {
+        let len = label.chars().count();
+        let start = (*column).min(width.checked_sub(len)?);
+        if used > 0 && start <= used {
+            return None;
+        }
+        line.push_str(&" ".repeat(start - used));
+        line.push_str(label);
+        used = start + len;
+    }
+    Some(line)
+}
+
+/// Draws an axis under log-scaled bars of `width` columns that start after
+/// `indent` columns, with a tick and a label at every power of ten between
+/// the fastest and the slowest time.
+///
+/// If the labels of the ticks don't fit, only the fastest and the slowest
+/// time are labeled at the ends of the axis.
+fn write_axis<T>(
+    out: &mut impl Write,
+    products: &[Product<T>],
+    indent: usize,
+    width: usize,
+    ascii: bool,
+) -> io::Result<()> {
+    if width == 0 {
+        return Ok(());
+    }
+    let (fastest, slowest) = time_range(products);
+    let min = GraphScale::Log.value(fastest);
+    let range = GraphScale::Log.value(slowest) - min;
+    // the same position a bar of this time would end at
+    let column = |time: Duration| match range > 0.0 {
+        true => {
+            let fraction = (GraphScale::Log.value(time) - min) / range;
+            ((fraction * width as f64).round() as usize).min(width - 1)
+        }
+        false => width - 1,
+    };
+    let ticks: Vec<(usize, String)> = (0..20)
+        .map(|exponent| 10u64.pow(exponent))
+        .map(Duration::from_nanos)
+        .filter(|&decade| decade >= fastest.max(Duration::from_nanos(1)) && decade <= slowest)
+        .map(|decade| (column(decade), decade_label(decade.as_nanos() as u64)))
+        .collect();
+    let (line, tick) = if ascii { ('-', '+') } else { ('─', '┬') };
+    let mut axis = vec![line; width];
+    let labels = match place_labels(&ticks, width).filter(|_| !ticks.is_empty()) {
+        Some(labels) => {
+            ticks.iter().for_each(|&(column, _)| axis[column] = tick);
+            Some(labels)
+        }
+        None => {
+            axis[0] = tick;
+            axis[width - 1] = tick;
+            let extremes = [
+                (0, format_compact(fastest)),
+                (width - 1, format_compact(slowest)),
+            ];
+            place_labels(&extremes, width)
+        }
+    };
+    let indent = " ".repeat(indent);
+    writeln!(out, "{}{}", indent, axis.into_iter().collect::<String>())?;
+    if let Some(labels) = labels {
+        writeln!(out, "{}{}", indent, labels)?;
+    }
+    writeln!(out, "log scale, ticks at powers of ten")
+}
+
+/// Draws a bar per product, followed by its duration unless the labels are
+/// off, coloring the fastest and slowest ones when `color` is set.
+///
+/// Products that timed out are drawn at full width, ending in `>`. The bars
+/// are shortened so that the longest label still fits the width, and the
+/// labels are left out if the names leave no room for them. A log graph
+/// ends in an axis if [`GraphOptions::axis`] is set.
+///
+/// `products` must not be empty.
+pub fn print_graph<T>(
+    out: &mut impl Write,
+    products: &[Product<T>],
+    graph: GraphOptions,
+    color: bool,
+) -> io::Result<()> {
+    let max_name_len = products.iter().map(|p| p.name.len()).max().unwrap();
+    let mut labels: Vec<String> = products
+        .iter()
+        .map(|product| match (graph.labels, product.timed_out) {
+            (None, _) => String::new(),
+            (Some(unit), Some(limit)) => format!(" {} (timed out)", unit.format(limit)),
+            (Some(unit), None) => format!(" {}", unit.format(product.time)),
+        })
+        .collect();
+    let mut max_label_len = labels.iter().map(|l| l.chars().count()).max().unwrap();
+    // labels that don't fit next to the names are left out instead of wrapping
+    if max_name_len + 2 + max_label_len > graph.width {
+        labels.iter_mut().for_each(String::clear);
+        max_label_len = 0;
+    }
+    let width = graph.width.saturating_sub(max_name_len + 2 + max_label_len);
+    let fractions = fractions(products, graph.scale);
+
+    writeln!(out, "\ntimes as a {} graph: ", graph.scale.name())?;
+    products
+        .iter()
+        .zip(fractions)
+        .zip(labels)
+        .enumerate()
+        .try_for_each(|(i, ((product, fraction), label))| -> io::Result<()> {
+            let pair = |i: usize| products[i].pair_name();
+            if graph.pairs && i > 0 && pair(i) != pair(i - 1) {
+                writeln!(out)?;
+            }
+            let drawn = match product.timed_out {
+                Some(_) => {
+                    let mut drawn = bar(1.0, width.saturating_sub(1), graph.ascii);
+                    drawn.push(TIMED_OUT);
+                    drawn
+                }
+                None => bar(fraction, width, graph.ascii),
+            };
+            let line = format!("{:<x$}: {}{}", product.name, drawn, label, x = max_name_len);
+            match highlight(products, product) {
+                Some(Highlight::Fastest) if color => writeln!(out, "{}", line.green()),
+                Some(Highlight::Slowest) if color => writeln!(out, "{}", line.red()),
+                _ => writeln!(out, "{}", line),
+            }
+        })?;
+    if graph.axis && matches!(graph.scale, GraphScale::Log) {
+        write_axis(out, products, max_name_len + 2, width, graph.ascii)?;
+    }
+    Ok(())
+}
+
+/// Number of rows the chart area of [`print_chart`] spans.
+const CHART_HEIGHT: usize = 16;
+/// Marks the products on the chart, in the order of the legend.
+const SYMBOLS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+/// Marks a cell several products fall into.
+const COLLISION: char = '+';
+
+/// The symbol and name of a product, with the size and time of every point
+/// it ran at.
+type Series<'a> = (char, &'a str, Vec<(usize, Duration)>);
+
+/// Draws the time of every product that ran against the size of the bigger
+/// array, both on a log scale, followed by a legend of the symbols.
+///
+/// The measurements of a product are connected by a line of its symbol.
+pub fn print_chart<T>(
+    out: &mut impl Write,
+    points: &[SweepPoint<T>],
+    width: usize,
+) -> io::Result<()> {
+    let series: Vec<Series> = names(points)
+        .into_iter()
+        .map(|name| {
+            let times = points
+                .iter()
+                .filter_map(|point| {
+                    let product = point.products.iter().find(|p| p.name == name)?;
+                    product
+                        .skipped
+                        .is_none()
+                        .then_some((point.big, product.time))
+                })
+                .collect();
+            (name, times)
+        })
+        .filter(|(_, times): &(&str, Vec<_>)| !times.is_empty())
+        .zip(SYMBOLS.iter().cycle())
+        .map(|((name, times), &symbol)| (symbol as char, name, times))
+        .collect();
+    let samples = || series.iter().flat_map(|(_, _, times)| times);
+    let (Some(shortest), Some(longest)) =
+        (samples().map(|s| s.1).min(), samples().map(|s| s.1).max())
+    else {
+        return writeln!(out, "\nno method ran, so there is nothing to chart");
+    };
+    let smallest = samples().map(|s| s.0).min().unwrap();
+    let biggest = samples().map(|s| s.0).max().unwrap();
+
+    let (top, bottom) = (format_compact(longest), format_compact(shortest));
+    let label_width = top.len().max(bottom.len());
+    let plot_width = width.saturating_sub(label_width + 2).max(10);
+    let log_size = |size: usize| (size.max(1) as f64).ln();
+    let log_time = |time: Duration| GraphScale::Log.value(time);
+    let position = |value: f64, min: f64, max: f64, cells: usize| {
+        if max > min {
+            ((value - min) / (max - min) * (cells - 1) as f64).round() as usize
+        } else {
+            cells / 2
+        }
+    };
+
+    let cell = |size: f64, time: f64| {
+        let column = position(size, log_size(smallest), log_size(biggest), plot_width);
+        let row = position(time, log_time(shortest), log_time(longest), CHART_HEIGHT);
+        (CHART_HEIGHT - 1 - row, column)
+    };
+
+    // Every cell holds a symbol and whether a product was measured there, so
+    // that measurements are drawn over the lines connecting them.
+    let mut grid = vec![vec![(' ', false); plot_width]; CHART_HEIGHT];
+    let mut collided = false;
+    for (symbol, _, times) in &series {
+        for pair in times.windows(2) {
+            let (from, to) = (log_size(pair[0].0), log_size(pair[1].0));
+            let (start, end) = (cell(from, 0.0).1, cell(to, 0.0).1);
+            for column in start + 1..end {
+                let x = log_size(smallest)
+                    + (log_size(biggest) - log_size(smallest)) * column as f64
+                        / (plot_width - 1) as f64;
+                let y = log_time(pair[0].1)
+                    + (log_time(pair[1].1) - log_time(pair[0].1)) * (x - from) / (to - from);
+                let (row, column) = cell(x, y);
+                if grid[row][column].0 == ' ' {
+                    grid[row][column] = (*symbol, false);
+                }
+            }
+        }
+    }
+    for (symbol, _, times) in &series {
+        for &(size, time) in times {
+            let (row, column) = cell(log_size(size), log_time(time));
+            let (current, measured) = &mut grid[row][column];
+            if *measured && current != symbol {
+                *current = COLLISION;
+                collided = true;
+            } else {
+                *current = *symbol;
+            }
+            *measured = true;
+        }
+    }
+
+    writeln!(out, "\ntimes against the bigger size on log-log axes:")?;
+    for (i, row) in grid.iter().enumerate() {
+        let label = match i {
+            0 => top.as_str(),
+            i if i == CHART_HEIGHT - 1 => bottom.as_str(),
+            _ => "",
+        };
+        let row: String = row.iter().map(|(symbol, _)| symbol).collect();
+        writeln!(out, "{:>w$} |{}", label, row.trim_end(), w = label_width)?;
+    }
+    writeln!(
+        out,
+        "{:>w$} +{}",
+        "",
+        "-".repeat(plot_width),
+        w = label_width
+    )?;
+    let (left, right) = (smallest.to_string(), biggest.to_string());
+    writeln!(
+        out,
+        "{:>w$}  {}{:>r$}",
+        "",
+        left,
+        if biggest > smallest {
+            right.as_str()
+        } else {
+            ""
+        },
+        w = label_width,
+        r = plot_width.saturating_sub(left.len())
+    )?;
+    if biggest == smallest {
+        writeln!(
+            out,
+            "only one size ran, so the chart can't show how the times grow"
+        )?;
+    }
+    writeln!(out)?;
+    for (symbol, name, _) in &series {
+        writeln!(out, "{}: {}", symbol, name)?;
+    }
+    if collided {
+        writeln!(out, "{}: several methods", COLLISION)?;
+    }
+    Ok(())
+}
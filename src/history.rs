@@ -0,0 +1,169 @@
+//! Appending the times of every run to a log and summarizing the log.
+
+use std::{
+    env,
+    fmt::Debug,
+    fs,
+    io::{self, BufRead, Write},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use prettytable::{Cell, Row, Table};
+use serde::{Deserialize, Serialize};
+
+use crate::{benchmark::median, format_compact, record::check_schema, Baseline, Product};
+
+/// One line of the history log, describing a run.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct HistoryRecord {
+    /// When the run finished, in milliseconds since the Unix epoch.
+    pub timestamp_ms: u128,
+    /// The name of the machine the run happened on.
+    pub hostname: String,
+    /// The input and the times of the products that finished.
+    #[serde(flatten)]
+    pub run: Baseline,
+}
+
+/// The name of this machine, or `unknown` if it can't be found out.
+pub fn hostname() -> String {
+    env::var("HOSTNAME")
+        .or_else(|_| env::var("COMPUTERNAME"))
+        .ok()
+        .or_else(|| fs::read_to_string("/etc/hostname").ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+impl HistoryRecord {
+    /// Records the times of the products that ran and finished at `timestamp`.
+    pub fn new<T: Debug>(
+        timestamp: SystemTime,
+        hostname: String,
+        seed: u64,
+        sizes: (usize, usize),
+        products: &[Product<T>],
+    ) -> Self {
+        HistoryRecord {
+            timestamp_ms: timestamp
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis(),
+            hostname,
+            run: Baseline::new(seed, sizes, products),
+        }
+    }
+
+    /// Writes the record as a single line of JSON.
+    pub fn append(&self, mut out: impl Write) -> Result<(), String> {
+        let line = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        writeln!(out, "{}", line).map_err(|e| e.to_string())
+    }
+}
+
+/// Reads the records of a history log, skipping lines that aren't records
+/// or have a newer schema.
+///
+/// Returns the records and a warning for every skipped line, so that a line
+/// cut off by an interrupted run doesn't hide the others.
+pub fn read_history(reader: impl BufRead) -> io::Result<(Vec<HistoryRecord>, Vec<String>)> {
+    let mut records = Vec::new();
+    let mut warnings = Vec::new();
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record = serde_json::from_str::<HistoryRecord>(&line)
+            .map_err(|e| e.to_string())
+            .and_then(|record| check_schema(record.run.schema_version).map(|_| record));
+        match record {
+            Ok(record) => records.push(record),
+            Err(e) => warnings.push(format!("skipped line {}: {}", number + 1, e)),
+        }
+    }
+    Ok((records, warnings))
+}
+
+/// The times of one product across the runs of a history log.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HistoryStats {
+    /// The name of the product.
+    pub name: String,
+    /// The number of runs the product finished in.
+    pub runs: usize,
+    /// The shortest time of the product.
+    pub min: Duration,
+    /// The median time of the product.
+    pub median: Duration,
+    /// The longest time of the product.
+    pub max: Duration,
+}
+
+impl HistoryStats {
+    /// Collects the times of every product, in the order the products first
+    /// appear in the log.
+    pub fn collect(records: &[HistoryRecord]) -> Vec<Self> {
+        Self::from_times(
+            records
+                .iter()
+                .flat_map(|record| &record.run.times)
+                .map(|time| (time.name.as_str(), Duration::from_nanos(time.time_ns))),
+        )
+    }
+
+    /// Collects the `(name, time)` pairs of every product, in the order the
+    /// products first appear.
+    pub(crate) fn from_times<'a>(
+        pairs: impl IntoIterator<Item = (&'a str, Duration)>,
+    ) -> Vec<Self> {
+        let mut times: Vec<(&str, Vec<Duration>)> = Vec::new();
+        for (name, duration) in pairs {
+            match times.iter_mut().find(|(other, _)| *other == name) {
+                Some((_, durations)) => durations.push(duration),
+                None => times.push((name, vec![duration])),
+            }
+        }
+        times
+            .into_iter()
+            .map(|(name, mut durations)| {
+                durations.sort();
+                HistoryStats {
+                    name: name.to_string(),
+                    runs: durations.len(),
+                    min: durations[0],
+                    median: median(&durations),
+                    max: *durations.last().unwrap(),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Prints the minimum, median and maximum time of every product.
+pub fn print_history(out: &mut impl Write, stats: &[HistoryStats]) -> io::Result<()> {
+    let mut table = Table::new();
+    table.add_row(Row::new(
+        ["Name", "Runs", "Min", "Median", "Max"]
+            .iter()
+            .map(|cell| Cell::new(cell))
+            .collect(),
+    ));
+    for stat in stats {
+        table.add_row(Row::new(
+            [
+                stat.name.clone(),
+                stat.runs.to_string(),
+                format_compact(stat.min),
+                format_compact(stat.median),
+                format_compact(stat.max),
+            ]
+            .iter()
+            .map(|cell| Cell::new(cell))
+            .collect(),
+        ));
+    }
+    table.print(out)?;
+    Ok(())
+}
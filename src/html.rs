@@ -0,0 +1,123 @@
+//! Rendering the results as a self-contained HTML page.
+
+use std::io::{self, Write};
+
+use crate::{
+    format_compact,
+    graph::fractions,
+    report::{highlight, resolve, table_rows, Highlight},
+    GraphScale, Product, TableOptions,
+};
+
+/// What the HTML report shows besides the products.
+#[derive(Clone)]
+pub struct HtmlOptions {
+    /// The seed the arrays were generated with.
+    pub seed: u64,
+    /// The number of elements in the first and the second array.
+    pub sizes: (usize, usize),
+    /// How the table is built.
+    pub table: TableOptions,
+    /// How the times are mapped to the length of the bars.
+    pub scale: GraphScale,
+    /// Whether every method computed the same values.
+    pub all_equal: bool,
+}
+
+const STYLE: &str = "body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; }
+th, td { border: 1px solid #ccc; padding: 0.3em 0.6em; text-align: left; }
+.chart { display: grid; grid-template-columns: max-content 1fr; gap: 0.3em 1em; \
+max-width: 60em; }
+.track { display: flex; align-items: center; gap: 0.5em; }
+.bar { height: 1em; background: #4a78c2; }
+.fastest .bar { background: #3a9a4a; }
+.slowest .bar { background: #c2473a; }";
+
+/// Replaces the characters that have a meaning in HTML.
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Writes a page with the input, the table, a bar per product and whether
+/// the results are equal, without referring to any other file.
+///
+/// The bars have the lengths [`print_graph`](crate::print_graph) draws with
+/// the same scale.
+pub fn write_html<T>(
+    out: &mut impl Write,
+    products: &[Product<T>],
+    skipped: &[Product<T>],
+    options: &HtmlOptions,
+) -> io::Result<()> {
+    writeln!(out, "<!DOCTYPE html>")?;
+    writeln!(out, "<html>\n<head>\n<meta charset=\"utf-8\">")?;
+    writeln!(out, "<title>Intersection benchmark</title>")?;
+    writeln!(out, "<style>\n{}\n</style>\n</head>\n<body>", STYLE)?;
+    writeln!(out, "<h1>Intersection benchmark</h1>")?;
+    writeln!(
+        out,
+        "<p>The arrays have the sizes {} and {} (seed {}).</p>",
+        options.sizes.0, options.sizes.1, options.seed
+    )?;
+    if products.is_empty() {
+        writeln!(out, "<p>No method ran.</p>")?;
+        writeln!(out, "<ul>")?;
+        for product in skipped {
+            writeln!(
+                out,
+                "<li>{} skipped: {}</li>",
+                escape(&product.name),
+                escape(product.skipped.as_deref().unwrap_or_default())
+            )?;
+        }
+        writeln!(out, "</ul>")?;
+    } else {
+        let reference = resolve(&options.table.reference, products)?;
+        let rows = table_rows(products, skipped, reference, &options.table, format_compact);
+        writeln!(out, "<table>")?;
+        for (i, row) in rows.iter().enumerate() {
+            let tag = if i == 0 { "th" } else { "td" };
+            write!(out, "<tr>")?;
+            for cell in row {
+                write!(out, "<{}>{}</{}>", tag, escape(cell), tag)?;
+            }
+            writeln!(out, "</tr>")?;
+        }
+        writeln!(out, "</table>")?;
+
+        let time = |duration| options.table.time_unit.format(duration);
+        writeln!(out, "<h2>Times as a {} graph</h2>", options.scale.name())?;
+        writeln!(out, "<div class=\"chart\">")?;
+        for (product, fraction) in products.iter().zip(fractions(products, options.scale)) {
+            let (fraction, time) = match product.timed_out {
+                Some(limit) => (1.0, format!("&gt; {} (timed out)", time(limit))),
+                None => (fraction, time(product.time)),
+            };
+            let class = match highlight(products, product) {
+                Some(Highlight::Fastest) => " fastest",
+                Some(Highlight::Slowest) => " slowest",
+                None => "",
+            };
+            writeln!(
+                out,
+                "<div>{}</div><div class=\"track{}\"><div class=\"bar\" \
+                 style=\"width: {:.2}%\"></div>{}</div>",
+                escape(&product.name),
+                class,
+                fraction * 100.0,
+                time
+            )?;
+        }
+        writeln!(out, "</div>")?;
+    }
+    writeln!(
+        out,
+        "<p>All values are equal: {}</p>",
+        if options.all_equal { "yes" } else { "no" }
+    )?;
+    writeln!(out, "</body>\n</html>")
+}
@@ -0,0 +1,78 @@
+//! Compares the speed of different approaches to intersecting two arrays.
+//!
+//! Implement [`Intersect`] for your own method, pass it to [`run_benchmark`]
+//! together with the built-in ones, and render the returned products with
+//! [`print_table`] and [`print_graph`].
+
+mod baseline;
+mod benchmark;
+mod dat;
+mod data;
+#[cfg(feature = "sqlite")]
+mod db;
+mod diff;
+mod environment;
+mod graph;
+mod history;
+mod html;
+mod memory;
+mod methods;
+mod ops;
+mod output;
+mod progress;
+mod record;
+mod registry;
+mod report;
+mod scenario;
+mod stats;
+mod svg;
+mod sweep;
+mod tui;
+mod verify;
+
+pub use baseline::{Baseline, BaselineComparison, Delta};
+pub use benchmark::{
+    plan, run_benchmark, run_benchmark_many, run_benchmark_observed, run_benchmark_with,
+    run_benchmark_with_timeout, Aggregate, Api, BenchmarkOptions, Flavor, Mode, Orders, Phases,
+    Product, Stability,
+};
+pub use dat::{write_dat, write_dat_sweep, DatOptions};
+pub use data::{
+    disjoint, distinct, distinct_shared, duplicates, generate, identical, overlap, queries,
+    read_binary, read_dataset, read_text, shared, skewed, subset, write_dataset, Distribution,
+    FromValue, Preset, Similarity,
+};
+#[cfg(feature = "sqlite")]
+pub use db::{Database, DB_SCHEMA_VERSION};
+pub use diff::{print_diff, DiffRow, RunDiff, SavedRun};
+pub use environment::Environment;
+pub use graph::{print_chart, print_graph, terminal_width, GraphOptions, GraphScale};
+pub use history::{hostname, print_history, read_history, HistoryRecord, HistoryStats};
+pub use html::{write_html, HtmlOptions};
+pub use memory::{CountingAllocator, Memory};
+#[cfg(feature = "roaring")]
+pub use methods::Roaring;
+pub use methods::{
+    Auto, BTree, BTreeBoth, Binary, BinaryStable, Bitset, BloomHash, DirectIndex, Element,
+    Galloping, Hash, HashA, HashFx, Intersect, Merge, PartitionedHash, Prepared, Retain, Simd,
+    Squared, SquaredBreak, StdIntersection,
+};
+pub use ops::{Op, Semantics};
+pub use output::{Output, Verbosity};
+pub use progress::Progress;
+pub use record::{ProductRecord, RESULT_SAMPLE_LEN, SCHEMA_VERSION};
+pub use registry::MethodRegistry;
+pub use report::{
+    choose_layout, format_compact, group_orders, print_markdown, print_samples, print_table,
+    print_times, throughput, total_time, write_csv, Column, Comparison, Layout, Reference, Report,
+    SortOrder, Summary, TableOptions, TimeUnit,
+};
+pub use scenario::Scenario;
+pub use stats::{mann_whitney, relative_standard_error, Significance, SIGNIFICANCE_LEVEL};
+pub use svg::{write_svg, SvgOptions};
+pub use sweep::{
+    estimate_complexity, print_complexity, print_sweep, print_thread_sweep, Complexity, SweepPoint,
+    SweepReport, ThreadPoint,
+};
+pub use tui::run_tui;
+pub use verify::{normalize, Multiplicity, Verification};
@@ -1,221 +1,1956 @@
 use std::{
-    cmp::{max_by_key, min_by_key},
-    collections::{BTreeSet, HashSet},
-    fmt::Debug,
-    iter::repeat,
-    time::{Duration, SystemTime},
+    any::type_name,
+    env,
+    ffi::OsString,
+    fmt::Display,
+    fs::{self, File, OpenOptions},
+    io::{self, BufReader, BufWriter, IsTerminal, Write},
+    iter::successors,
+    path::{Path, PathBuf},
+    process::exit,
+    sync::{atomic::AtomicBool, Arc},
+    thread::available_parallelism,
+    time::{Duration, Instant, SystemTime},
 };
 
-use crossterm::terminal::size;
-use prettytable::{row, Table};
-use rand::random;
-use rayon::iter::{IntoParallelIterator, IntoParallelRefIterator, ParallelIterator};
+use clap::{
+    error::ErrorKind, parser::ValueSource, ArgAction, CommandFactory, FromArgMatches, Parser,
+    ValueEnum,
+};
+#[cfg(feature = "sqlite")]
+use compare_speed::Database;
+#[cfg(feature = "roaring")]
+use compare_speed::Roaring;
+use compare_speed::{
+    distinct, distinct_shared, estimate_complexity, generate, group_orders, hostname, overlap,
+    plan, print_chart, print_complexity, print_diff, print_graph, print_history, print_markdown,
+    print_samples, print_sweep, print_table, print_thread_sweep, print_times, queries, read_binary,
+    read_dataset, read_history, read_text, run_benchmark_many, run_benchmark_with,
+    run_benchmark_with_timeout, run_tui, shared, terminal_width, write_csv, write_dat,
+    write_dat_sweep, write_dataset, write_html, write_svg, Aggregate, Api, Auto, BTree, BTreeBoth,
+    Baseline, BaselineComparison, BenchmarkOptions, Binary, BinaryStable, Bitset, BloomHash,
+    Column, CountingAllocator, DatOptions, DirectIndex, Distribution, Element, Environment, Flavor,
+    FromValue, Galloping, GraphOptions, GraphScale, Hash, HashA, HashFx, HistoryRecord,
+    HistoryStats, HtmlOptions, Intersect, Merge, MethodRegistry, Mode, Op, Orders, Output,
+    PartitionedHash, Preset, Product, Progress, Reference, Report, Retain, RunDiff, SavedRun,
+    Scenario, Semantics, Simd, Similarity, SortOrder, Squared, SquaredBreak, Stability,
+    StdIntersection, Summary, SvgOptions, SweepPoint, SweepReport, TableOptions, ThreadPoint,
+    TimeUnit, Verbosity, Verification,
+};
+use rand::{random, rngs::StdRng, SeedableRng};
+use rayon::{
+    current_num_threads,
+    iter::{IntoParallelIterator, ParallelIterator},
+    slice::ParallelSliceMut,
+    ThreadPoolBuilder,
+};
+use signal_hook::{consts::SIGINT, flag};
 
-#[derive(Debug)]
-struct Squared;
-#[derive(Debug)]
-struct SquaredBreak;
-#[derive(Debug)]
-struct BTree;
-#[derive(Debug)]
-struct Binary;
-#[derive(Debug)]
-struct Hash;
-
-struct Product {
-    name: String,
-    time: Duration,
-    result: Vec<usize>,
-}
-
-impl Product {
-    fn new(name: String, time: Duration, result: Vec<usize>) -> Self {
-        Product { name, time, result }
-    }
-}
-
-trait Intersect: Debug + Send + Sync {
-    fn intersect(&self, big: &[usize], small: &[usize]) -> Vec<usize>;
-}
-
-impl Intersect for Squared {
-    fn intersect(&self, big: &[usize], small: &[usize]) -> Vec<usize> {
-        big.par_iter()
-            .flat_map_iter(|i| small.iter().zip(repeat(i)))
-            .filter(|(i, j)| *i == *j)
-            .map(|(i, _)| *i)
-            .collect()
-    }
-}
-
-impl Intersect for SquaredBreak {
-    fn intersect(&self, big: &[usize], small: &[usize]) -> Vec<usize> {
-        big.par_iter()
-            .filter(|i| small.par_iter().find_any(|j| j == i).is_some())
-            .copied()
-            .collect()
-    }
-}
-
-impl Intersect for BTree {
-    fn intersect(&self, big: &[usize], small: &[usize]) -> Vec<usize> {
-        let small = BTreeSet::from_iter(small);
-        big.par_iter()
-            .filter(|i| small.contains(i))
-            .copied()
-            .collect()
-    }
-}
-
-impl Intersect for Binary {
-    fn intersect(&self, big: &[usize], small: &[usize]) -> Vec<usize> {
-        let mut small = small.to_vec();
-        small.sort();
-        big.par_iter()
-            .filter(|i| small.binary_search(i).is_ok())
-            .copied()
-            .collect()
-    }
-}
-
-impl Intersect for Hash {
-    fn intersect(&self, big: &[usize], small: &[usize]) -> Vec<usize> {
-        let small: HashSet<usize> = small.iter().copied().collect();
-        big.par_iter()
-            .filter(|i| small.contains(i))
-            .copied()
-            .collect()
-    }
-}
-
-fn test_method(method: &dyn Intersect, a: &[usize], b: &[usize], appendage: &str) -> Product {
-    let name = format!("{:?}{}", method, appendage);
-    let start = SystemTime::now();
-    let result = method.intersect(a, b);
-    let time = SystemTime::now().duration_since(start).unwrap();
-    Product::new(name, time, result)
-}
-
-fn print_table(products: &[Product]) {
-    let mut table = Table::new();
-    table.add_row(row![
-        "Name",
-        "Time taken",
-        "times faster than previous",
-        "Absolute time difference",
-        "percent of previous time",
-        "Compared to"
-    ]);
-    table.add_row(row![
-        products[0].name,
-        format!("{:?}", products[0].time),
-        "-",
-        "-",
-        "-",
-        "-"
-    ]);
-
-    products.windows(2).for_each(|values| {
-        table.add_row(row![
-            values[1].name,
-            format!("{:?}", values[1].time),
-            format!(
-                "{:.2}x",
-                values[0].time.as_nanos() as f64 / values[1].time.as_nanos() as f64
-            ),
-            format!("{:?}", values[0].time - values[1].time),
-            format!(
-                "{:.2}%",
-                values[1].time.as_nanos() as f64 / values[0].time.as_nanos() as f64 * 100.0
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Compares the speed of different approaches to intersecting two arrays.
+#[derive(Parser)]
+struct Args {
+    /// Read the settings of this scenario from the --config file, overridden
+    /// by the flags on the command line
+    #[arg(long)]
+    scenario: Option<String>,
+    /// TOML file with a table per scenario under `scenarios`, whose keys are
+    /// flags like size_a
+    #[arg(long, default_value = DEFAULT_CONFIG, requires = "scenario")]
+    config: PathBuf,
+    /// The scenario the settings were read from.
+    #[arg(skip)]
+    resolved: Option<Scenario>,
+    /// Number of elements in the first array (random if omitted)
+    #[arg(long)]
+    size_a: Option<usize>,
+    /// Number of elements in the second array (random if omitted)
+    #[arg(long)]
+    size_b: Option<usize>,
+    /// Number of generated arrays every method intersects at once, the ones
+    /// after the first two with random sizes
+    #[arg(
+        long,
+        default_value_t = 2,
+        value_parser = clap::value_parser!(u32).range(2..),
+        conflicts_with_all = [
+            "input_a", "load_data", "save_data", "sweep", "thread_sweep", "overlap", "timeout",
+            "tui", "op", "semantics", "api", "orders", "group_orders", "phases",
+        ]
+    )]
+    arrays: u32,
+    /// What every method is timed on: intersecting the arrays, or building a
+    /// lookup structure from the smaller array once and answering --queries
+    /// membership queries with it
+    #[arg(long, value_enum, default_value_t = Mode::Intersect)]
+    mode: Mode,
+    /// Number of membership queries in probe mode, half of them for values of
+    /// the smaller array and half of them fresh random values
+    #[arg(long, default_value_t = 1_000_000, value_parser = positive)]
+    queries: usize,
+    /// Seed for the random number generator (random if omitted)
+    #[arg(long)]
+    seed: Option<u64>,
+    /// Comma separated list of methods to run (all if omitted)
+    #[arg(long, value_delimiter = ',')]
+    methods: Vec<String>,
+    /// Number of timed runs per method
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    repeats: u32,
+    /// How the timed runs of every method are combined into its time
+    #[arg(long, value_enum, default_value_t = Aggregate::Median)]
+    aggregate: Aggregate,
+    /// Keep timing every method after its repeats until its times are
+    /// stable, it had --max-runs runs or its runs took --stable-budget
+    #[arg(long)]
+    until_stable: bool,
+    /// Relative standard error of the times of a method, in percent, below
+    /// which they count as stable
+    #[arg(long, default_value_t = 2.0, value_parser = percent, requires = "until_stable")]
+    stable_threshold: f64,
+    /// Most timed runs of a method with --until-stable
+    #[arg(
+        long,
+        default_value_t = 1000,
+        value_parser = clap::value_parser!(u32).range(2..),
+        requires = "until_stable"
+    )]
+    max_runs: u32,
+    /// Seconds the timed runs of a method may take together with
+    /// --until-stable, the run that crosses it still finishes
+    #[arg(long, default_value = "10", value_parser = seconds, requires = "until_stable")]
+    stable_budget: Duration,
+    /// Number of untimed runs per method before measuring
+    #[arg(long, default_value_t = 1)]
+    warmup: u32,
+    /// Memory budget in MiB for lookup tables indexed by value
+    #[arg(long, default_value_t = 256)]
+    memory_budget: usize,
+    /// Comma separated false positive rates to benchmark BloomHash with
+    #[arg(long, value_delimiter = ',', default_value = "0.01", value_parser = rate)]
+    bloom_fp_rates: Vec<f64>,
+    /// Number of buckets PartitionedHash splits the arrays into, one per
+    /// thread by default
+    #[arg(long, value_parser = clap::value_parser!(u16).range(1..))]
+    partitions: Option<u16>,
+    /// Comma separated list of flavors to run every method in
+    #[arg(
+        long,
+        value_enum,
+        value_delimiter = ',',
+        default_value = "parallel,sequential"
+    )]
+    flavors: Vec<Flavor>,
+    /// Which orders of the arrays to run every method in: with the bigger
+    /// array first, with the smaller one first, or both
+    #[arg(long, value_enum, default_value_t = Orders::Both)]
+    orders: Orders,
+    /// Show the two orders of every method next to each other, with how many
+    /// times faster the faster order is
+    #[arg(long)]
+    group_orders: bool,
+    /// Only print a `name<TAB>nanos` line per method
+    #[arg(
+        short,
+        long,
+        conflicts_with_all = [
+            "verbose",
+            "format",
+            "json",
+            "output",
+            "tui",
+            "sweep",
+            "thread_sweep"
+        ]
+    )]
+    quiet: bool,
+    /// Print more detail: the samples of every method, and the table and graph
+    /// of every size in a sweep
+    #[arg(short, long, action = ArgAction::Count)]
+    verbose: u8,
+    /// Format of the report written to stdout
+    #[arg(long, value_enum, default_value_t = Format::Table)]
+    format: Format,
+    /// Also write the results as CSV to this file
+    #[arg(long)]
+    csv: Option<PathBuf>,
+    /// Write a JSON report to this file, or to stdout if no file is given
+    #[arg(long, num_args = 0..=1, default_missing_value = "-")]
+    json: Option<PathBuf>,
+    /// Also write the results as a self-contained HTML page to this file
+    #[arg(long, conflicts_with_all = ["sweep", "thread_sweep"])]
+    html: Option<PathBuf>,
+    /// Also write the times as an SVG bar chart to this file
+    #[arg(long, conflicts_with_all = ["sweep", "thread_sweep"])]
+    svg: Option<PathBuf>,
+    /// Also write the times as a whitespace separated data file for gnuplot,
+    /// with a block per size in a sweep
+    #[arg(long, conflicts_with = "thread_sweep")]
+    dat: Option<PathBuf>,
+    /// Write the table, graph and equality summary to this file instead of stdout
+    #[arg(long)]
+    output: Option<PathBuf>,
+    /// Append to the --output file instead of overwriting it
+    #[arg(long, requires = "output")]
+    append: bool,
+    /// What the ratio columns of the table compare every method to: previous,
+    /// fastest, slowest, first or the name of a method like "Hash seq"
+    #[arg(long, value_parser = reference, default_value = "previous")]
+    baseline: Reference,
+    /// Comma separated columns of the table in the order they are shown (all
+    /// if omitted)
+    #[arg(long, value_enum, value_delimiter = ',')]
+    columns: Vec<Column>,
+    /// Order of the methods in the table, the graph and the other reports;
+    /// in name order the ratios compare to the fastest method instead of the
+    /// previous row
+    #[arg(long, value_enum, default_value_t = SortOrder::TimeDesc)]
+    sort: SortOrder,
+    /// Add a row with the sum of all times to the table instead of the summary
+    /// below it
+    #[arg(long)]
+    total_row: bool,
+    /// Unit of the times in the table, the summary and the chart labels;
+    /// machine-readable reports always hold nanoseconds
+    #[arg(long, value_enum, default_value_t = TimeUnit::Auto)]
+    time_unit: TimeUnit,
+    /// Scale of the bars in the graph
+    #[arg(long, value_enum, default_value_t = GraphScale::Log)]
+    graph_scale: GraphScale,
+    /// Draw the bars of the graph without the durations after them and the
+    /// axis under them
+    #[arg(long)]
+    bare_graph: bool,
+    /// Draw the graph with plain ASCII characters
+    #[arg(long)]
+    ascii: bool,
+    /// Width of the table and the graph in columns (the terminal width if
+    /// omitted)
+    #[arg(long)]
+    width: Option<usize>,
+    /// Show every column of the table, even if it is wider than the terminal
+    #[arg(long)]
+    wide: bool,
+    /// Don't highlight the fastest and slowest methods
+    #[arg(long)]
+    no_color: bool,
+    /// How often the intersection contains duplicate elements: every distinct
+    /// element once, once per occurrence in the first array, or once per pair
+    /// of equal elements
+    #[arg(long, value_enum, default_value_t = Semantics::MultisetBig)]
+    semantics: Semantics,
+    /// Number of example elements shown for methods that disagree
+    #[arg(long, default_value_t = 5)]
+    examples: usize,
+    /// Set operation every method computes
+    #[arg(long, value_enum, default_value_t = Op::Intersection)]
+    op: Op,
+    /// Only count the elements of the intersection instead of collecting them
+    #[arg(long)]
+    count_only: bool,
+    /// How the intersection is taken from every method; iter drains an
+    /// iterator into a counter on a single thread
+    #[arg(long, value_enum, default_value_t = Api::Vec)]
+    api: Api,
+    /// Generate arrays of this shape from the seed instead of independent
+    /// random ones; skewed defaults to 10 million and 10 elements
+    #[arg(
+        long,
+        value_enum,
+        conflicts_with_all = ["overlap", "distribution", "max_value", "sweep"]
+    )]
+    preset: Option<Preset>,
+    /// Percentage of the smaller array to draw from the bigger one
+    #[arg(long, value_parser = percent)]
+    overlap: Option<f64>,
+    /// Distribution the values of the arrays are drawn from
+    #[arg(long, value_enum, default_value_t = DistributionKind::Uniform)]
+    distribution: DistributionKind,
+    /// Exponent of the zipf distribution, higher values mean more duplicates
+    #[arg(long, default_value_t = 1.0, value_parser = non_negative)]
+    zipf_exponent: f64,
+    /// Type of the elements of the arrays; generated values keep the bits that
+    /// fit, read values must fit
+    #[arg(long, value_enum, default_value_t = ElementType::Usize)]
+    element_type: ElementType,
+    /// Number of characters of the strings generated for --element-type string
+    #[arg(long, default_value_t = 16, value_parser = positive)]
+    string_length: usize,
+    /// Only generate values below this one, to get duplicates and natural overlap
+    #[arg(long, value_parser = positive)]
+    max_value: Option<usize>,
+    /// Sort both arrays before benchmarking, so that only the lookups are timed
+    #[arg(long)]
+    pre_sorted: bool,
+    /// Read the first array from this file instead of generating it
+    #[arg(
+        long,
+        requires = "input_b",
+        conflicts_with_all = ["size_a", "size_b", "preset", "overlap", "distribution", "max_value"]
+    )]
+    input_a: Option<PathBuf>,
+    /// Read the second array from this file instead of generating it
+    #[arg(long, requires = "input_a")]
+    input_b: Option<PathBuf>,
+    /// Read the input files as little-endian u64 values instead of text
+    #[arg(long, requires = "input_a")]
+    binary_input: bool,
+    /// Write both arrays and the seed to this file before benchmarking
+    #[arg(long)]
+    save_data: Option<PathBuf>,
+    /// Benchmark the arrays of a file written by --save-data
+    #[arg(
+        long,
+        conflicts_with_all = [
+            "input_a",
+            "seed",
+            "size_a",
+            "size_b",
+            "preset",
+            "overlap",
+            "distribution",
+            "max_value"
+        ]
+    )]
+    load_data: Option<PathBuf>,
+    /// Number of threads parallel methods and data generation run on (all
+    /// cores if omitted)
+    #[arg(long, value_parser = clap::value_parser!(u16).range(1..=4096))]
+    threads: Option<u16>,
+    /// Benchmark the parallel flavor in thread pools of 1, 2, 4, ... threads up
+    /// to the number of logical cores
+    #[arg(
+        long,
+        conflicts_with_all = ["threads", "sweep", "csv", "json", "output", "format"]
+    )]
+    thread_sweep: bool,
+    /// Count the bytes every method allocates, running the methods one at a time
+    #[arg(long)]
+    measure_memory: bool,
+    /// Run the methods one at a time in rounds of one timed run each, in an
+    /// order shuffled with the seed every round
+    #[arg(long, conflicts_with = "timeout")]
+    interleave: bool,
+    /// Run the methods at the same time instead of one after another, which
+    /// finishes sooner but makes them compete for the cores
+    #[arg(long, conflicts_with_all = ["interleave", "timeout", "measure_memory"])]
+    concurrent_methods: bool,
+    /// Time building the lookup structure of every method apart from probing it
+    #[arg(long, conflicts_with = "count_only")]
+    phases: bool,
+    /// Stop waiting for a method after this many seconds and mark it as timed
+    /// out, leaving it running in the background
+    #[arg(
+        long,
+        value_parser = seconds,
+        conflicts_with_all = ["sweep", "thread_sweep"]
+    )]
+    timeout: Option<Duration>,
+    /// Write the median time of every method, the sizes and the seed to this
+    /// JSON file
+    #[arg(long, conflicts_with_all = ["sweep", "thread_sweep"])]
+    save_baseline: Option<PathBuf>,
+    /// Compare the times against a file written by --save-baseline
+    #[arg(long, conflicts_with_all = ["sweep", "thread_sweep"])]
+    compare_baseline: Option<PathBuf>,
+    /// Percentage a method may get slower than in the baseline before it
+    /// counts as a regression
+    #[arg(long, default_value_t = 10.0, value_parser = non_negative)]
+    regression_threshold: f64,
+    /// Exit with an error if a method regressed against the baseline
+    #[arg(long, requires = "compare_baseline")]
+    fail_on_regression: bool,
+    /// Exit with an error if the results disagree or a method timed out,
+    /// describing the failures on stderr. Without it the exit status is 0
+    /// even if the results disagree
+    #[arg(long, conflicts_with = "thread_sweep")]
+    check: bool,
+    /// Add a method that finds nothing, to test how failures are reported
+    #[arg(long, hide = true)]
+    broken_method: bool,
+    /// Show the table and graph live on the alternate screen while the methods
+    /// run, then wait for a key press before printing the report
+    #[arg(long, conflicts_with_all = ["sweep", "thread_sweep", "timeout"])]
+    tui: bool,
+    /// Append the input and the times of the run as a line of JSON to this file
+    #[arg(long, conflicts_with_all = ["sweep", "thread_sweep"])]
+    history: Option<PathBuf>,
+    /// Print the minimum, median and maximum time of every method in the
+    /// --history file instead of benchmarking
+    #[arg(long, requires = "history")]
+    show_history: bool,
+    /// Print the old and new time of every method in two files written by
+    /// --json or --save-baseline instead of benchmarking
+    #[arg(long, num_args = 2, value_names = ["OLD", "NEW"])]
+    diff: Vec<PathBuf>,
+    /// Insert the input and the times of the run into this SQLite database
+    /// (needs a build with --features sqlite)
+    #[arg(
+        long,
+        conflicts_with_all = ["sweep", "thread_sweep"],
+        hide = cfg!(not(feature = "sqlite"))
+    )]
+    db: Option<PathBuf>,
+    /// Print the minimum, median and maximum time of every method in the --db
+    /// database instead of benchmarking
+    #[arg(long, requires = "db", hide = cfg!(not(feature = "sqlite")))]
+    db_summary: bool,
+    /// Only summarize the runs whose bigger array has at least this many
+    /// elements
+    #[arg(long, requires = "db_summary", hide = cfg!(not(feature = "sqlite")))]
+    db_min_size: Option<usize>,
+    /// Only summarize the runs whose bigger array has at most this many
+    /// elements
+    #[arg(long, requires = "db_summary", hide = cfg!(not(feature = "sqlite")))]
+    db_max_size: Option<usize>,
+    /// Print what would be benchmarked with the other options, how often and
+    /// with which seed, without generating any data
+    #[arg(long, conflicts_with_all = ["tui", "quiet"])]
+    dry_run: bool,
+    /// Print the name, description and expected complexity of every method
+    /// for --element-type instead of benchmarking
+    #[arg(long)]
+    list_methods: bool,
+    /// Comma separated sizes of the bigger array to benchmark one after another
+    #[arg(
+        long,
+        value_delimiter = ',',
+        conflicts_with_all = [
+            "size_a",
+            "size_b",
+            "input_a",
+            "load_data",
+            "save_data",
+            "csv",
+            "output",
+            "format"
+        ]
+    )]
+    sweep: Vec<usize>,
+    /// How many times bigger the bigger array is than the smaller one in a sweep
+    #[arg(long, default_value_t = 2.0, value_parser = at_least_one)]
+    sweep_ratio: f64,
+    /// Skip Squared and SquaredBreak when the bigger array has more elements
+    /// than this (100000 in a sweep)
+    #[arg(long)]
+    quadratic_limit: Option<usize>,
+}
+
+impl Args {
+    /// How `--baseline`, `--total-row` and `--columns` ask for the table of
+    /// arrays with this many elements together.
+    fn table(&self, inputs: usize) -> TableOptions {
+        TableOptions {
+            // chained comparisons between rows in name order mean nothing
+            reference: match (&self.baseline, self.sort) {
+                (Reference::Previous, SortOrder::Name) => Reference::Fastest,
+                (reference, _) => reference.clone(),
+            },
+            total: self.total_row,
+            columns: self.columns.clone(),
+            group_orders: self.group_orders,
+            elements: Some(inputs),
+            queries: self.mode == Mode::Probe,
+            time_unit: self.time_unit,
+            width: (!self.wide).then(|| self.width.unwrap_or_else(terminal_width)),
+        }
+    }
+
+    /// The run configuration in the header of the `--dat` file.
+    fn dat_options(&self, seed: u64) -> DatOptions {
+        DatOptions {
+            seed,
+            op: self.op,
+            semantics: self.semantics,
+        }
+    }
+
+    /// Prints the messages `--quiet` and `--verbose` ask for.
+    fn printer(&self) -> Output {
+        Output::new(if self.quiet {
+            Verbosity::Quiet
+        } else if self.verbose > 0 {
+            Verbosity::Verbose
+        } else {
+            Verbosity::Normal
+        })
+    }
+}
+
+/// The default of `--quadratic-limit` in a sweep, where the bigger sizes would
+/// otherwise take hours.
+const DEFAULT_SWEEP_QUADRATIC_LIMIT: usize = 100_000;
+
+/// The exit status of a run stopped with Ctrl+C, the one shells use for it.
+const INTERRUPTED: i32 = 130;
+
+/// The scenario file read if `--config` isn't given.
+const DEFAULT_CONFIG: &str = "compare_times.toml";
+
+/// Flags a scenario can't set.
+const NOT_IN_SCENARIOS: [&str; 4] = ["scenario", "config", "help", "version"];
+
+/// Parses the command line, after the settings of the `--scenario` it names
+/// that none of its flags override.
+fn parse_args() -> Args {
+    let matches = Args::command().get_matches();
+    let args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    let Some(name) = &args.scenario else {
+        return args;
+    };
+    let command = Args::command();
+    let flags: Vec<(String, String)> = command
+        .get_arguments()
+        .filter(|arg| !arg.is_hide_set() && !NOT_IN_SCENARIOS.contains(&arg.get_id().as_str()))
+        .filter_map(|arg| Some((arg.get_id().to_string(), arg.get_long()?.to_string())))
+        .collect();
+    let known: Vec<String> = flags.iter().map(|(id, _)| id.clone()).collect();
+    let path = &args.config;
+    let mut scenario = read(
+        path,
+        fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| Scenario::parse(&text, &path.display().to_string(), name, &known)),
+    );
+    scenario.overridden = scenario
+        .settings
+        .keys()
+        .filter(|key| matches.value_source(key) == Some(ValueSource::CommandLine))
+        .cloned()
+        .collect();
+    let mut argv: Vec<OsString> = env::args_os().take(1).collect();
+    for (key, values) in &scenario.settings {
+        if scenario.overridden.contains(key) {
+            continue;
+        }
+        let (_, long) = flags.iter().find(|(id, _)| id == key).unwrap();
+        match values.is_empty() {
+            true => argv.push(format!("--{}", long).into()),
+            false => argv.extend(
+                values
+                    .iter()
+                    .flat_map(|value| [format!("--{}", long), value.clone()])
+                    .map(OsString::from),
             ),
-            values[0].name
-        ]);
-    });
-    let first = &products[0];
-    let last = products.last().unwrap();
-    table.add_row(row![
-        "Total",
-        format!(
-            "{:?}",
-            products
+        }
+    }
+    argv.extend(env::args_os().skip(1));
+    let mut args = Args::try_parse_from(argv).unwrap_or_else(|e| e.exit());
+    args.resolved = Some(scenario);
+    args
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum DistributionKind {
+    Uniform,
+    Zipf,
+    Clustered,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum ElementType {
+    U32,
+    U64,
+    Usize,
+    I64,
+    String,
+}
+
+fn non_negative(value: &str) -> Result<f64, String> {
+    let number: f64 = value.parse().map_err(|e| format!("{}", e))?;
+    if number >= 0.0 {
+        Ok(number)
+    } else {
+        Err("must not be negative".to_string())
+    }
+}
+
+fn at_least_one(value: &str) -> Result<f64, String> {
+    let number: f64 = value.parse().map_err(|e| format!("{}", e))?;
+    if number >= 1.0 {
+        Ok(number)
+    } else {
+        Err("must be at least 1".to_string())
+    }
+}
+
+fn rate(value: &str) -> Result<f64, String> {
+    let rate: f64 = value.parse().map_err(|e| format!("{}", e))?;
+    if rate > 0.0 && rate < 1.0 {
+        Ok(rate)
+    } else {
+        Err("must be between 0 and 1, exclusive".to_string())
+    }
+}
+
+fn positive(value: &str) -> Result<usize, String> {
+    match value.parse().map_err(|e| format!("{}", e))? {
+        0 => Err("must be at least 1".to_string()),
+        number => Ok(number),
+    }
+}
+
+fn seconds(value: &str) -> Result<Duration, String> {
+    let seconds: f64 = value.parse().map_err(|e| format!("{}", e))?;
+    Duration::try_from_secs_f64(seconds)
+        .ok()
+        .filter(|duration| !duration.is_zero())
+        .ok_or_else(|| "must be a positive number of seconds".to_string())
+}
+
+fn percent(value: &str) -> Result<f64, String> {
+    let percent: f64 = value.parse().map_err(|e| format!("{}", e))?;
+    if (0.0..=100.0).contains(&percent) {
+        Ok(percent)
+    } else {
+        Err("must be between 0 and 100".to_string())
+    }
+}
+
+fn reference(value: &str) -> Result<Reference, String> {
+    Ok(match value {
+        "previous" => Reference::Previous,
+        "fastest" => Reference::Fastest,
+        "slowest" => Reference::Slowest,
+        "first" => Reference::First,
+        name => Reference::Method(name.to_string()),
+    })
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum Format {
+    Table,
+    Markdown,
+    Csv,
+}
+
+/// Everything the report shows about the products of a run.
+#[derive(Clone, Copy)]
+struct Results<'a, T> {
+    /// Whether Ctrl+C stopped the benchmark before every method ran.
+    interrupted: bool,
+    /// Describes the elements, like `u32 values of 4 bytes`.
+    elements: &'a str,
+    /// The number of elements in all arrays.
+    inputs: usize,
+    products: &'a [Product<T>],
+    skipped: &'a [Product<T>],
+    verification: &'a Verification<'a, T>,
+    similarity: Option<&'a Similarity>,
+    baseline: Option<&'a BaselineComparison>,
+    summary: Option<&'a Summary>,
+}
+
+/// Writes the results table, the graph and the equality summary.
+fn write_report<T: Element>(
+    out: &mut impl Write,
+    args: &Args,
+    results: Results<T>,
+    color: bool,
+) -> io::Result<()> {
+    let Results {
+        interrupted,
+        elements,
+        inputs,
+        products,
+        skipped,
+        verification,
+        similarity,
+        baseline,
+        summary,
+    } = results;
+    if interrupted {
+        writeln!(
+            out,
+            "(interrupted) only the methods measured before Ctrl+C are shown\n"
+        )?;
+    }
+    if products.is_empty() {
+        return skipped.iter().try_for_each(|product| {
+            writeln!(
+                out,
+                "{} skipped: {}",
+                product.name,
+                product.skipped.as_deref().unwrap_or_default()
+            )
+        });
+    }
+    writeln!(out, "the elements are {}", elements)?;
+    if args.pre_sorted {
+        writeln!(out, "pre-sorted input, sorting the arrays is not timed\n")?;
+    }
+    let graph = GraphOptions {
+        width: args.width.unwrap_or_else(terminal_width),
+        scale: args.graph_scale,
+        ascii: args.ascii,
+        pairs: args.group_orders,
+        labels: (!args.bare_graph).then_some(args.time_unit),
+        axis: !args.bare_graph,
+    };
+    let table = args.table(inputs);
+    if args.format == Format::Markdown {
+        print_markdown(out, products, skipped, &table)?;
+        if let Some(summary) = summary {
+            summary.write(out, args.time_unit)?;
+        }
+        writeln!(out, "\n```text")?;
+        print_graph(out, products, graph, false)?;
+        writeln!(out, "```")?;
+    } else {
+        print_table(out, products, skipped, &table, color)?;
+        if let Some(summary) = summary {
+            summary.write(out, args.time_unit)?;
+        }
+        print_graph(out, products, graph, color)?;
+    }
+    if args.printer().shows(Verbosity::Verbose) {
+        print_samples(out, products)?;
+    }
+    verification.write(out)?;
+    if let Some(similarity) = similarity {
+        writeln!(
+            out,
+            "jaccard similarity {:.4}: {} of the {} distinct values are in both arrays",
+            similarity.jaccard, similarity.shared, similarity.union
+        )?;
+        writeln!(
+            out,
+            "containment {:.4}: the share of the distinct values of the smaller array that are \
+             in the other",
+            similarity.containment
+        )?;
+    }
+    if products.iter().any(|product| product.timed_out.is_some()) {
+        writeln!(
+            out,
+            "note: methods that timed out keep running in the background, which can slow down \
+             the ones after them"
+        )?;
+    }
+    match baseline {
+        Some(baseline) => {
+            writeln!(out)?;
+            baseline.write(out)
+        }
+        None => Ok(()),
+    }
+}
+
+/// How similar the two arrays are, from the result of the first product
+/// that finished, so it's computed once instead of for every method.
+fn similarity<T: Element>(
+    args: &Args,
+    products: &[Product<T>],
+    distinct: (usize, usize),
+) -> Option<Similarity> {
+    if args.op != Op::Intersection {
+        return None;
+    }
+    let reference = products.iter().find(|p| p.timed_out.is_none())?;
+    match reference.count {
+        // only a set counts every shared value once
+        Some(count) => {
+            (args.semantics == Semantics::Set).then(|| Similarity::from_shared(distinct, count))
+        }
+        None => Some(Similarity::new(distinct, &reference.result)),
+    }
+}
+
+/// The summary shown below the table, unless `--total-row` asks for the row.
+fn summarize<T>(args: &Args, products: &[Product<T>], wall_time: Duration) -> Option<Summary> {
+    if args.total_row {
+        None
+    } else {
+        Summary::new(products, wall_time)
+    }
+}
+
+fn create_file(path: &Path) -> File {
+    File::create(path).unwrap_or_else(|e| fail(path, e))
+}
+
+/// Exits with an error if `values` couldn't be read from `path`.
+fn read<T>(path: &Path, values: Result<T, String>) -> T {
+    values.unwrap_or_else(|e| {
+        eprintln!("error: could not read {}: {}", path.display(), e);
+        exit(1)
+    })
+}
+
+/// Whether `--tui` was given and the terminal can show it.
+fn use_tui(args: &Args) -> bool {
+    if !args.tui {
+        return false;
+    }
+    let terminal = io::stdin().is_terminal() && io::stdout().is_terminal();
+    if !terminal {
+        eprintln!("warning: --tui needs a terminal, showing the plain report instead");
+    }
+    terminal
+}
+
+/// Reads the values of an input file.
+fn load(path: &Path, binary: bool) -> Vec<usize> {
+    let values = File::open(path)
+        .map_err(|e| e.to_string())
+        .and_then(|file| {
+            let reader = BufReader::new(file);
+            if binary {
+                read_binary(reader)
+            } else {
+                read_text(reader)
+            }
+        });
+    read(path, values)
+}
+
+/// Converts the values to the element type, exiting with an error if a value
+/// read from `path` doesn't fit.
+fn convert<T: FromValue>(values: Vec<usize>, path: Option<&Path>, element: T::Options) -> Vec<T> {
+    match path {
+        None => values
+            .into_par_iter()
+            .map(|value| T::from_generated(value, element))
+            .collect(),
+        Some(path) => {
+            let converted = values
+                .into_iter()
+                .map(|value| {
+                    T::from_input(value)
+                        .ok_or_else(|| format!("{} doesn't fit in a {}", value, type_name::<T>()))
+                })
+                .collect();
+            read(path, converted)
+        }
+    }
+}
+
+/// Draws `percent` percent of the smaller array from the bigger one.
+fn overlap_arrays(rng: &mut StdRng, a: &mut Vec<usize>, b: &mut Vec<usize>, percent: f64) {
+    let (big, small) = if b.len() < a.len() { (a, b) } else { (b, a) };
+    overlap(rng, big, small, percent / 100.0);
+}
+
+/// Benchmarks freshly generated arrays at every size of `--sweep`, then
+/// prints the combined table and exits.
+fn sweep<T: FromValue>(
+    args: &Args,
+    methods: &[Box<dyn Intersect<T>>],
+    element: T::Options,
+    options: &BenchmarkOptions,
+    rng: &mut StdRng,
+    distribution: Distribution,
+    seed: u64,
+) -> ! {
+    let human = args.json.as_deref() != Some(Path::new("-"));
+    let printer = args.printer();
+    if human {
+        printer.normal(format_args!(
+            "sweeping the sizes {} (seed {})",
+            args.sweep
                 .iter()
-                .map(|x| x.time)
-                .fold(Duration::ZERO, |a, b| a + b)
+                .map(|size| size.to_string())
+                .collect::<Vec<_>>()
+                .join(", "),
+            seed
+        ));
+        printer.normal(format_args!(
+            "the values follow a {} distribution",
+            distribution.description()
+        ));
+        printer.normal(format_args!("the elements are {}", T::describe(element)));
+        print_environment(args);
+        printer.normal(scheduling(args));
+        printer.normal(format_args!("{}\n", computing(args)));
+    }
+    let mut points = Vec::new();
+    let mut equal = true;
+    for &size in &args.sweep {
+        if options.interrupted() {
+            break;
+        }
+        let small_size = (size as f64 / args.sweep_ratio).round() as usize;
+        let mut a = generate(rng, Some(size), distribution, args.max_value);
+        let mut b = generate(rng, Some(small_size), distribution, args.max_value);
+        if let Some(percent) = args.overlap {
+            overlap_arrays(rng, &mut a, &mut b, percent);
+        }
+        let (mut a, mut b): (Vec<T>, Vec<T>) =
+            (convert(a, None, element), convert(b, None, element));
+        if args.pre_sorted {
+            a.par_sort_unstable();
+            b.par_sort_unstable();
+        }
+        let start = Instant::now();
+        let (ran, skipped): (Vec<_>, Vec<_>) = run_benchmark_with(methods, &a, &b, options)
+            .into_iter()
+            .partition(|p| p.skipped.is_none());
+        let summary = summarize(args, &ran, start.elapsed());
+        let verification = Verification::new(&ran, args.op, args.semantics, args.examples);
+        if human && printer.shows(Verbosity::Verbose) {
+            printer.verbose(format_args!(
+                "the arrays have the sizes {} and {}\n",
+                a.len(),
+                b.len()
+            ));
+            let results = Results {
+                interrupted: false,
+                elements: &T::describe(element),
+                inputs: a.len() + b.len(),
+                products: &ran,
+                skipped: &skipped,
+                verification: &verification,
+                similarity: None,
+                baseline: None,
+                summary: summary.as_ref(),
+            };
+            print_report(args, None, results);
+            printer.verbose("");
+        } else if human && !verification.passed() {
+            printer.normal(format_args!(
+                "the arrays with the sizes {} and {}:",
+                a.len(),
+                b.len()
+            ));
+            verification
+                .write(&mut io::stdout())
+                .expect("failed to write to stdout");
+        }
+        if options.interrupted() && ran.is_empty() {
+            break;
+        }
+        equal &= verification.passed();
+        points.push(SweepPoint {
+            big: a.len(),
+            small: b.len(),
+            products: ran.into_iter().chain(skipped).collect(),
+        });
+    }
+    let complexity = estimate_complexity(&points);
+    if human {
+        if options.interrupted() {
+            printer.normal("(interrupted) only the sizes measured before Ctrl+C are shown\n");
+        }
+        let mut out = io::stdout();
+        print_sweep(&mut out, &points)
+            .and_then(|_| print_chart(&mut out, &points, args.width.unwrap_or_else(terminal_width)))
+            .and_then(|_| writeln!(out))
+            .and_then(|_| print_complexity(&mut out, &complexity))
+            .expect("failed to write to stdout");
+        printer.normal(format_args!("\nall values are equal: {}", equal));
+    }
+    if let Some(path) = &args.dat {
+        let mut out = BufWriter::new(create_file(path));
+        write_dat_sweep(&mut out, &points, &args.dat_options(seed))
+            .and_then(|_| out.flush())
+            .unwrap_or_else(|e| fail(path, e));
+    }
+    if let Some(path) = &args.json {
+        let report = SweepReport::new(seed, &points, &complexity, equal)
+            .with_scenario(args.resolved.clone());
+        if path == Path::new("-") {
+            serde_json::to_writer_pretty(io::stdout(), &report).expect("failed to write to stdout");
+            println!();
+        } else {
+            serde_json::to_writer_pretty(create_file(path), &report)
+                .unwrap_or_else(|e| fail(path, e));
+        }
+    }
+    if args.check && !options.interrupted() {
+        // the disagreements at every size were already reported above
+        check::<T>(
+            equal,
+            None,
+            timed_out(points.iter().flat_map(|p| &p.products)),
+        );
+    }
+    exit(if options.interrupted() {
+        INTERRUPTED
+    } else {
+        0
+    })
+}
+
+/// What the run computes, for the header.
+fn computing(args: &Args) -> String {
+    match args.op {
+        _ if args.mode == Mode::Probe => format!(
+            "answering {} membership queries with a structure built from the smaller array, \
+             half of them for its values",
+            args.queries
         ),
-        format!(
-            "{:.2}x",
-            first.time.as_nanos() as f64 / last.time.as_nanos() as f64
+        _ if args.arrays > 2 => format!(
+            "computing the intersection of all {} arrays, with {}",
+            args.arrays,
+            Semantics::Set.description()
         ),
-        format!("{:?}", first.time - last.time),
-        format!(
-            "{:.2}%",
-            last.time.as_nanos() as f64 / first.time.as_nanos() as f64 * 100.0
+        Op::Intersection if args.api == Api::Iter => format!(
+            "computing the intersection, with {}, by draining an iterator on a single thread",
+            args.semantics.description()
         ),
-        "-"
-    ]);
-    table.printstd();
+        Op::Intersection => format!(
+            "computing the intersection, with {}",
+            args.semantics.description()
+        ),
+        op => format!("computing the {}", op.description()),
+    }
 }
 
-fn print_graph(products: &[Product]) {
-    let max_name_len = products.iter().map(|p| p.name.len()).max().unwrap();
-    let width = (size().unwrap().0 as usize - max_name_len - 2) as f64;
-    let min = (products.last().unwrap().time.as_nanos() as f64).ln();
-    let base = width / ((products[0].time.as_nanos() as f64).ln() - min);
+/// How the methods take turns and how often they run, for the header.
+fn scheduling(args: &Args) -> String {
+    let scheduling = if args.interleave {
+        "the methods run one at a time, interleaving their timed runs in shuffled rounds"
+    } else if args.concurrent_methods {
+        "the methods run concurrently, each with all of its runs at once"
+    } else {
+        "the methods run one at a time, each with all of its runs at once"
+    };
+    if !args.until_stable {
+        return scheduling.to_string();
+    }
+    format!(
+        "{}\nevery method is timed until the relative standard error of its times is below \
+         {}%, for at most {} runs or {:?}",
+        scheduling, args.stable_threshold, args.max_runs, args.stable_budget
+    )
+}
 
-    println!("\ntimes as a log graph: ");
-    products.iter().for_each(|product| {
-        println!(
-            "{:<x$}: {}",
-            product.name,
-            "*".repeat((((product.time.as_nanos() as f64).ln() - min) * base).round() as usize),
-            x = max_name_len
-        )
+/// Prints the scenario the settings came from, the build, the number of
+/// CPUs and the threads the parallel flavors run on.
+fn print_environment(args: &Args) {
+    let printer = args.printer();
+    if let Some(scenario) = &args.resolved {
+        printer.normal(scenario.description());
+    }
+    printer.normal(Environment::current().description());
+    match current_num_threads() {
+        1 => printer.normal("running on 1 thread"),
+        threads => printer.normal(format_args!("running on {} threads", threads)),
+    }
+}
+
+/// The sizes of the thread pools of `--thread-sweep`: the powers of two below
+/// the number of logical cores, and the number itself.
+fn pools() -> Vec<usize> {
+    let cores = available_parallelism().map_or(1, |cores| cores.get());
+    let mut threads: Vec<usize> = successors(Some(1), |n| Some(n * 2))
+        .take_while(|n| *n < cores)
+        .collect();
+    threads.push(cores);
+    threads
+}
+
+/// Benchmarks the parallel flavor of every method in thread pools of growing
+/// size, then prints the speedups and exits.
+fn thread_sweep<T: Element>(
+    methods: &[Box<dyn Intersect<T>>],
+    options: &BenchmarkOptions,
+    a: &[T],
+    b: &[T],
+    printer: Output,
+) -> ! {
+    let threads = pools();
+    let options = BenchmarkOptions {
+        flavors: vec![Flavor::Parallel],
+        ..options.clone()
+    };
+    printer.normal(format_args!(
+        "running the parallel flavor on {} threads\n",
+        threads
+            .iter()
+            .map(|n| n.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    ));
+    let points: Vec<ThreadPoint<T>> = threads
+        .into_iter()
+        .take_while(|_| !options.interrupted())
+        .map(|threads| {
+            let pool = ThreadPoolBuilder::new()
+                .num_threads(threads)
+                .build()
+                .expect("failed to build a thread pool");
+            ThreadPoint {
+                threads,
+                products: pool.install(|| run_benchmark_with(methods, a, b, &options)),
+            }
+        })
+        .collect();
+    if options.interrupted() {
+        printer.normal("(interrupted) only the pools measured before Ctrl+C are shown\n");
+    }
+    print_thread_sweep(&mut io::stdout(), &points).expect("failed to write to stdout");
+    if methods.iter().any(|method| method.name() == "SquaredBreak") {
+        printer.normal(
+            "note: SquaredBreak stops at whichever match a thread finds first, so the work it \
+             does and its scaling are noisy",
+        );
+    }
+    exit(if options.interrupted() {
+        INTERRUPTED
+    } else {
+        0
+    })
+}
+
+/// Prints the times of every method in the history log, then exits.
+fn show_history(args: &Args, path: &Path) -> ! {
+    let history = File::open(path)
+        .and_then(|file| read_history(BufReader::new(file)))
+        .map_err(|e| e.to_string());
+    let (records, warnings) = read(path, history);
+    for warning in warnings {
+        eprintln!("warning: {}: {}", path.display(), warning);
+    }
+    print_runs(args, records.len(), path);
+    print_history(&mut io::stdout(), &HistoryStats::collect(&records))
+        .expect("failed to write to stdout");
+    exit(0)
+}
+
+/// Prints how many runs the history in `path` holds, unless `--quiet` asks
+/// for the times alone.
+fn print_runs(args: &Args, runs: usize, path: &Path) {
+    let runs = match runs {
+        1 => "1 run".to_string(),
+        runs => format!("{} runs", runs),
+    };
+    args.printer()
+        .normal(format_args!("{} in {}\n", runs, path.display()));
+}
+
+/// Opens the `--db` database, exiting with an error if it can't be read.
+#[cfg(feature = "sqlite")]
+fn open_db(path: &Path) -> Database {
+    read(path, Database::open(path))
+}
+
+/// Prints the times of every method in the database, then exits.
+#[cfg(feature = "sqlite")]
+fn db_summary(args: &Args, path: &Path) -> ! {
+    let database = open_db(path);
+    let (min, max) = (args.db_min_size, args.db_max_size);
+    let runs = read(path, database.runs(min, max));
+    let stats = read(path, database.summary(min, max));
+    print_runs(args, runs, path);
+    print_history(&mut io::stdout(), &stats).expect("failed to write to stdout");
+    exit(0)
+}
+
+/// Prints how the times of the `[old, new]` runs differ, then exits.
+fn diff(args: &Args, paths: &[PathBuf]) -> ! {
+    let [old, new] = [&paths[0], &paths[1]].map(|path| {
+        let run = File::open(path)
+            .map_err(|e| e.to_string())
+            .and_then(|file| SavedRun::read(BufReader::new(file)));
+        read(path, run)
     });
+    let color = !args.no_color && io::stdout().is_terminal();
+    print_diff(
+        &mut io::stdout(),
+        &RunDiff::new(&old, &new),
+        args.time_unit,
+        color,
+    )
+    .expect("failed to write to stdout");
+    exit(0)
+}
+
+/// Prints the methods, sizes and number of runs the options ask for, then
+/// exits.
+///
+/// The plan is all `--dry-run` prints, so it isn't routed through
+/// [`Output`], and `--dry-run` can't be combined with `--quiet`.
+fn dry_run<T: Element>(
+    args: &Args,
+    methods: &[Box<dyn Intersect<T>>],
+    options: &BenchmarkOptions,
+    seed: u64,
+) -> ! {
+    let join = |values: &[usize]| {
+        values
+            .iter()
+            .map(|value| value.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    println!("dry run, no data is generated and no method runs\n");
+    println!("seed {}", seed);
+    let (options, pools) = if args.thread_sweep {
+        let pools = pools();
+        println!("{}", Environment::current().description());
+        println!("running the parallel flavor on {} threads", join(&pools));
+        let options = BenchmarkOptions {
+            flavors: vec![Flavor::Parallel],
+            ..options.clone()
+        };
+        (options, pools.len())
+    } else {
+        print_environment(args);
+        (options.clone(), 1)
+    };
+    println!("{}", scheduling(args));
+    println!("{}", computing(args));
+    let size = |size: Option<usize>| match size {
+        Some(size) => size.to_string(),
+        None => format!("a random size below {}", u16::MAX as usize + 1),
+    };
+    let sizes = if !args.sweep.is_empty() {
+        println!(
+            "sweeping the sizes {}, with a smaller array {} times smaller",
+            join(&args.sweep),
+            args.sweep_ratio
+        );
+        args.sweep.len()
+    } else {
+        if let (Some(a), Some(b)) = (&args.input_a, &args.input_b) {
+            println!(
+                "the arrays are read from {} and {}",
+                a.display(),
+                b.display()
+            );
+        } else if let Some(path) = &args.load_data {
+            println!("the arrays are loaded from {}", path.display());
+        } else {
+            println!(
+                "the arrays have the sizes {} and {}",
+                size(args.size_a),
+                size(args.size_b)
+            );
+        }
+        if args.arrays > 2 {
+            println!("and {} more arrays of random sizes", args.arrays - 2);
+        }
+        1
+    };
+    if let Some(limit) = options.quadratic_limit {
+        println!("quadratic methods are skipped above {} elements", limit);
+    }
+    let (products, unsupported) = plan(methods, args.arrays as usize, &options);
+    println!("\n{} products:", products.len());
+    for product in &products {
+        println!("  {}", product);
+    }
+    if !unsupported.is_empty() {
+        println!(
+            "not supporting the {}: {}",
+            options.op.name(),
+            unsupported.join(", ")
+        );
+    }
+    let scope = match (sizes, pools) {
+        (1, 1) => String::new(),
+        (1, pools) => format!(" in {} thread pools", pools),
+        (sizes, _) => format!(" over {} sizes", sizes),
+    };
+    match options.until_stable {
+        Some(stability) => println!(
+            "\nevery product has {} warmup runs and is timed until stable, for at most {} \
+             runs in total{}",
+            options.warmup,
+            products.len() * (options.warmup + stability.max_runs) as usize * sizes * pools,
+            scope
+        ),
+        None => println!(
+            "\nevery product has {} warmup and {} timed runs, for {} runs in total{}",
+            options.warmup,
+            options.repeats,
+            products.len() * (options.warmup + options.repeats) as usize * sizes * pools,
+            scope
+        ),
+    }
+    exit(0)
+}
+
+/// Prints every registered method with its description and complexity, then
+/// exits.
+///
+/// The methods are printed even with `--quiet`, which only leaves out the
+/// notes below them.
+fn list_methods<T>(args: &Args, registry: &MethodRegistry<T>) -> ! {
+    let printer = args.printer();
+    let width = registry.iter().map(|m| m.name().len()).max().unwrap_or(0);
+    for method in registry.iter() {
+        println!(
+            "{:width$} — {} — {}",
+            method.name(),
+            method.description(),
+            method.complexity()
+        );
+    }
+    #[cfg(not(feature = "roaring"))]
+    if matches!(args.element_type, ElementType::Usize) {
+        println!(
+            "{:width$} — not compiled in, build with --features roaring",
+            "Roaring"
+        );
+    }
+    if !matches!(args.element_type, ElementType::Usize) {
+        printer.normal("\nSimd, Bitset, BloomHash and Roaring only intersect --element-type usize");
+    }
+    printer.normal(
+        "\nn is the length of the bigger array, m the length of the smaller one and max its \
+         biggest value",
+    );
+    exit(0)
+}
+
+fn fail(path: &Path, error: impl Display) -> ! {
+    eprintln!("error: could not write {}: {}", path.display(), error);
+    exit(1)
 }
 
 fn main() {
-    let methods: [Box<dyn Intersect>; 5] = [
-        Box::new(Squared {}),
-        Box::new(SquaredBreak {}),
-        Box::new(BTree {}),
-        Box::new(Binary {}),
-        Box::new(Hash {}),
-    ];
-    let start = SystemTime::now();
-    let a: Vec<usize> = (0..random::<u16>())
-        .into_par_iter()
-        .map(|_| random())
-        .collect();
-    let b: Vec<usize> = (0..random::<u16>())
-        .into_par_iter()
-        .map(|_| random())
+    let args = parse_args();
+    if args.count_only && args.op != Op::Intersection {
+        Args::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                format!(
+                    "--count-only can't be used with --op {}",
+                    args.op.to_possible_value().unwrap().get_name()
+                ),
+            )
+            .exit();
+    }
+    if args.preset == Some(Preset::Identical) && args.size_b.is_some() {
+        Args::command()
+            .error(
+                ErrorKind::ArgumentConflict,
+                "--size-b can't be used with --preset identical, whose arrays have the size of --size-a",
+            )
+            .exit();
+    }
+    if args.api == Api::Iter {
+        let conflict = if args.op != Op::Intersection {
+            Some("--op")
+        } else if args.count_only {
+            Some("--count-only")
+        } else if args.semantics != Semantics::MultisetBig {
+            Some("--semantics other than multiset-big")
+        } else if args.phases {
+            Some("--phases")
+        } else if args.thread_sweep {
+            Some("--thread-sweep")
+        } else if !args.flavors.iter().any(|f| matches!(f, Flavor::Sequential)) {
+            Some("--flavors without the sequential flavor")
+        } else {
+            None
+        };
+        if let Some(conflict) = conflict {
+            Args::command()
+                .error(
+                    ErrorKind::ArgumentConflict,
+                    format!("--api iter can't be used with {}", conflict),
+                )
+                .exit();
+        }
+    }
+    if args.mode == Mode::Probe {
+        let conflict = if args.op != Op::Intersection {
+            Some("--op")
+        } else if args.count_only {
+            Some("--count-only")
+        } else if args.api == Api::Iter {
+            Some("--api iter")
+        } else if args.phases {
+            Some("--phases")
+        } else if args.arrays > 2 {
+            Some("--arrays")
+        } else if args.group_orders {
+            Some("--group-orders")
+        } else if !args.sweep.is_empty() {
+            Some("--sweep")
+        } else if args.thread_sweep {
+            Some("--thread-sweep")
+        } else {
+            None
+        };
+        if let Some(conflict) = conflict {
+            Args::command()
+                .error(
+                    ErrorKind::ArgumentConflict,
+                    format!("--mode probe can't be used with {}", conflict),
+                )
+                .exit();
+        }
+    }
+    if let Some(threads) = args.threads {
+        ThreadPoolBuilder::new()
+            .num_threads(threads.into())
+            .build_global()
+            .unwrap_or_else(|e| Args::command().error(ErrorKind::InvalidValue, e).exit());
+    }
+    if !args.diff.is_empty() {
+        diff(&args, &args.diff);
+    }
+    #[cfg(not(feature = "sqlite"))]
+    if args.db.is_some() {
+        Args::command()
+            .error(
+                ErrorKind::InvalidValue,
+                "--db needs a build with --features sqlite",
+            )
+            .exit();
+    }
+    if Environment::current().is_debug() {
+        eprintln!(
+            "warning: this is a debug build, its times say little about optimized code; \
+             build with --release"
+        );
+    }
+    if args.show_history {
+        show_history(&args, args.history.as_deref().unwrap());
+    }
+    #[cfg(feature = "sqlite")]
+    if args.db_summary {
+        db_summary(&args, args.db.as_deref().unwrap());
+    }
+    let partitions = args.partitions.map(usize::from);
+    match args.element_type {
+        ElementType::U32 => run::<u32>(args, generic_methods(partitions), ()),
+        ElementType::U64 => run::<u64>(args, generic_methods(partitions), ()),
+        ElementType::Usize => {
+            let mut methods = generic_methods(partitions);
+            methods.push(Box::new(Simd));
+            methods.push(Box::new(Bitset::new(args.memory_budget)));
+            methods.push(Box::new(DirectIndex::new(args.memory_budget)));
+            #[cfg(feature = "roaring")]
+            methods.push(Box::new(Roaring));
+            methods.extend(
+                args.bloom_fp_rates
+                    .iter()
+                    .map(|&rate| Box::new(BloomHash::new(rate)) as Box<dyn Intersect>),
+            );
+            run(args, methods, ())
+        }
+        ElementType::I64 => run::<i64>(args, generic_methods(partitions), ()),
+        ElementType::String => {
+            let length = args.string_length;
+            run::<String>(args, generic_methods(partitions), length)
+        }
+    }
+}
+
+/// The methods that intersect arrays of any element type, with
+/// PartitionedHash splitting the arrays into `partitions` buckets.
+fn generic_methods<T: Element>(partitions: Option<usize>) -> Vec<Box<dyn Intersect<T>>> {
+    vec![
+        Box::new(Squared),
+        Box::new(SquaredBreak),
+        Box::new(BTree),
+        Box::new(BTreeBoth),
+        Box::new(Binary),
+        Box::new(BinaryStable),
+        Box::new(Hash),
+        Box::new(HashFx),
+        Box::new(HashA),
+        Box::new(PartitionedHash::new(partitions)),
+        Box::new(Merge),
+        Box::new(Galloping),
+        Box::new(Auto::default()),
+        Box::new(StdIntersection),
+        Box::new(Retain),
+    ]
+}
+
+/// Benchmarks `methods` on arrays of `T`, generated with `element`, and
+/// prints the report.
+fn run<T: FromValue>(args: Args, mut methods: Vec<Box<dyn Intersect<T>>>, element: T::Options) {
+    if args.broken_method {
+        methods.push(Box::new(Broken));
+    }
+    let mut registry = MethodRegistry::new();
+    methods
+        .into_iter()
+        .try_for_each(|method| registry.register(method))
+        .unwrap_or_else(|e| Args::command().error(ErrorKind::InvalidValue, e).exit());
+    if args.list_methods {
+        list_methods(&args, &registry);
+    }
+    let registry = registry
+        .select(&args.methods)
+        .unwrap_or_else(|e| Args::command().error(ErrorKind::InvalidValue, e).exit());
+    let started_at = SystemTime::now();
+    let start = Instant::now();
+    let baseline = args.compare_baseline.as_ref().map(|path| {
+        let baseline = File::open(path)
+            .map_err(|e| e.to_string())
+            .and_then(|file| Baseline::read(BufReader::new(file)));
+        read(path, baseline)
+    });
+    let saved = args.load_data.as_ref().map(|path| {
+        let dataset = File::open(path)
+            .map_err(|e| e.to_string())
+            .and_then(|file| read_dataset(BufReader::new(file)));
+        read(path, dataset)
+    });
+    let seed = match &saved {
+        Some((seed, _, _)) => *seed,
+        None => args.seed.unwrap_or_else(random),
+    };
+    let mut rng = StdRng::seed_from_u64(seed);
+    let preset = args.preset.unwrap_or_default();
+    let distribution = match args.distribution {
+        DistributionKind::Uniform => Distribution::Uniform,
+        DistributionKind::Zipf => Distribution::Zipf(args.zipf_exponent),
+        DistributionKind::Clustered => Distribution::Clustered(seed),
+    };
+    let tui = use_tui(&args);
+    let interrupted = Arc::new(AtomicBool::new(false));
+    // the first Ctrl+C lets the running method finish and reports what was
+    // measured, a second one exits right away
+    flag::register_conditional_shutdown(SIGINT, INTERRUPTED, Arc::clone(&interrupted))
+        .and_then(|_| flag::register(SIGINT, Arc::clone(&interrupted)))
+        .expect("failed to handle Ctrl+C");
+    let options = BenchmarkOptions {
+        flavors: args.flavors.clone(),
+        orders: args.orders,
+        mode: args.mode,
+        repeats: args.repeats,
+        until_stable: args.until_stable.then_some(Stability {
+            threshold: args.stable_threshold / 100.0,
+            max_runs: args.max_runs,
+            budget: args.stable_budget,
+        }),
+        warmup: args.warmup,
+        op: args.op,
+        count_only: args.count_only,
+        semantics: args.semantics,
+        api: args.api,
+        pre_sorted: args.pre_sorted,
+        measure_memory: args.measure_memory,
+        phases: args.phases,
+        aggregate: args.aggregate,
+        concurrent_methods: args.concurrent_methods,
+        interleave: args.interleave.then_some(seed),
+        // the status line would end up in piped output, over the live screen or
+        // between the lines of --quiet
+        progress: (io::stdout().is_terminal()
+            && !tui
+            && !args.quiet
+            && args.format != Format::Csv
+            && args.json.as_deref() != Some(Path::new("-")))
+        .then(|| Arc::new(Progress::new())),
+        interrupted: Some(interrupted),
+        quadratic_limit: args
+            .quadratic_limit
+            .or_else(|| (!args.sweep.is_empty()).then_some(DEFAULT_SWEEP_QUADRATIC_LIMIT)),
+    };
+    if args.dry_run {
+        dry_run(&args, registry.methods(), &options, seed);
+    }
+    if !args.sweep.is_empty() {
+        sweep(
+            &args,
+            registry.methods(),
+            element,
+            &options,
+            &mut rng,
+            distribution,
+            seed,
+        );
+    }
+    let output = args.output.as_ref().map(|path| {
+        let file = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .append(args.append)
+            .truncate(!args.append)
+            .open(path)
+            .unwrap_or_else(|e| fail(path, e));
+        (path, file)
+    });
+    let (mut a, mut b) = match (saved, &args.input_a, &args.input_b) {
+        (Some((_, a, b)), _, _) => (a, b),
+        (None, Some(a), Some(b)) => (load(a, args.binary_input), load(b, args.binary_input)),
+        _ => match preset {
+            Preset::Random => (
+                generate(&mut rng, args.size_a, distribution, args.max_value),
+                generate(&mut rng, args.size_b, distribution, args.max_value),
+            ),
+            preset => preset.generate(&mut rng, (args.size_a, args.size_b)),
+        },
+    };
+    if let Some(percent) = args.overlap {
+        overlap_arrays(&mut rng, &mut a, &mut b, percent);
+    }
+    let rest: Vec<Vec<usize>> = (2..args.arrays)
+        .map(|_| generate(&mut rng, None, distribution, args.max_value))
         .collect();
-    println!(
-        "generating test data took {:?}",
-        SystemTime::now().duration_since(start).unwrap()
+    let queries = (args.mode == Mode::Probe).then(|| {
+        let small = if b.len() < a.len() { &b } else { &a };
+        queries(&mut rng, small, args.queries, distribution, args.max_value)
+    });
+    let elapsed = start.elapsed();
+    if let Some(path) = &args.save_data {
+        write_dataset(BufWriter::new(create_file(path)), seed, &a, &b)
+            .unwrap_or_else(|e| fail(path, e));
+    }
+    let (mut a, mut b): (Vec<T>, Vec<T>) = (
+        convert(a, args.input_a.as_deref(), element),
+        convert(b, args.input_b.as_deref(), element),
     );
-    println!("the arrays have the sizes {} and {}\n", a.len(), b.len());
-
-    let big = max_by_key(&a, &b, |x| x.len());
-    let small = min_by_key(&a, &b, |x| x.len());
-
-    let mut products: Vec<_> = methods
-        .par_iter()
-        .flat_map(|method| {
-            [
-                (method, big, small, ""),
-                (method, small, big, " switched order"),
-            ]
-        })
-        .map(|(method, a, b, appendage)| test_method(&**method, a, b, appendage))
+    let queries: Option<Vec<T>> = queries.map(|values| convert(values, None, element));
+    let mut rest: Vec<Vec<T>> = rest
+        .into_iter()
+        .map(|values| convert(values, None, element))
         .collect();
+    if args.pre_sorted {
+        a.par_sort_unstable();
+        b.par_sort_unstable();
+        rest.iter_mut()
+            .for_each(|values| values.par_sort_unstable());
+    }
+    let human = args.format != Format::Csv && args.json.as_deref() != Some(Path::new("-"));
+    let printer = args.printer();
+    if human {
+        if args.input_a.is_some() {
+            printer.normal(format_args!("loading test data took {:?}", elapsed));
+        } else if args.load_data.is_some() {
+            printer.normal(format_args!(
+                "loading test data took {:?} (seed {})",
+                elapsed, seed
+            ));
+        } else {
+            printer.normal(format_args!(
+                "generating test data took {:?} (seed {})",
+                elapsed, seed
+            ));
+            match preset {
+                Preset::Random => printer.normal(format_args!(
+                    "the values follow a {} distribution",
+                    distribution.description()
+                )),
+                preset => printer.normal(format_args!(
+                    "the arrays follow the preset {}",
+                    preset.description()
+                )),
+            }
+            if let Some(max) = args.max_value {
+                printer.normal(format_args!("every value is below {}", max));
+            }
+        }
+        if rest.is_empty() {
+            printer.normal(format_args!(
+                "the arrays have the sizes {} and {}",
+                a.len(),
+                b.len()
+            ));
+            let percent = |part: usize, whole: usize| match whole {
+                0 => 0.0,
+                whole => part as f64 * 100.0 / whole as f64,
+            };
+            let (in_a, in_b) = (shared(&b, &a), shared(&a, &b));
+            printer.normal(format_args!(
+                "the arrays share {} distinct values, held by {} of the {} elements of the first \
+                 array ({:.1}%) and {} of the {} elements of the second ({:.1}%)",
+                distinct_shared(&a, &b),
+                in_a,
+                a.len(),
+                percent(in_a, a.len()),
+                in_b,
+                b.len(),
+                percent(in_b, b.len())
+            ));
+        } else {
+            let sizes: Vec<String> = [&a, &b]
+                .into_iter()
+                .chain(&rest)
+                .map(|values| values.len().to_string())
+                .collect();
+            printer.normal(format_args!(
+                "the {} arrays have the sizes {}",
+                args.arrays,
+                sizes.join(", ")
+            ));
+        }
+        print_environment(&args);
+        printer.normal(scheduling(&args));
+        printer.normal(format_args!("{}\n", computing(&args)));
+        if a.is_empty() || b.is_empty() {
+            printer.normal(
+                "note: one of the arrays is empty, so every intersection is trivially empty\n",
+            );
+        }
+    }
 
-    products.sort_by(|a, b| b.time.cmp(&a.time));
-    print_table(&products);
-    print_graph(&products);
+    if args.thread_sweep {
+        thread_sweep(registry.methods(), &options, &a, &b, printer);
+    }
+    let sizes = (a.len(), b.len());
+    let inputs = match &queries {
+        Some(queries) => queries.len(),
+        None => a.len() + b.len() + rest.iter().map(Vec::len).sum::<usize>(),
+    };
+    let distinct = (rest.is_empty() && queries.is_none()).then(|| (distinct(&a), distinct(&b)));
+    // the methods take the queries first and build their structures from the
+    // smaller array
+    let (a, b) = match queries {
+        Some(queries) if b.len() < a.len() => (queries, b),
+        Some(queries) => (queries, a),
+        None => (a, b),
+    };
+    let start = Instant::now();
+    let products = match args.timeout {
+        // abandoned methods keep using the methods and arrays, so they are leaked
+        Some(timeout) => {
+            let methods = Box::leak(Box::new(registry)).methods();
+            run_benchmark_with_timeout(methods, a.leak(), b.leak(), &options, timeout)
+        }
+        None if tui => {
+            let graph = GraphOptions {
+                width: terminal_width(),
+                scale: args.graph_scale,
+                ascii: args.ascii,
+                // the live products aren't grouped yet
+                pairs: false,
+                labels: (!args.bare_graph).then_some(args.time_unit),
+                axis: !args.bare_graph,
+            };
+            run_tui(registry.methods(), &a, &b, &options, graph)
+                .expect("failed to draw to the terminal")
+        }
+        None if !rest.is_empty() => {
+            let arrays: Vec<Vec<T>> = [a.clone(), b.clone()].into_iter().chain(rest).collect();
+            run_benchmark_many(registry.methods(), &arrays, &options)
+        }
+        None => run_benchmark_with(registry.methods(), &a, &b, &options),
+    };
+    let (mut products, skipped): (Vec<_>, Vec<_>) = products
+        .into_iter()
+        .partition(|product| product.skipped.is_none());
+    args.sort.sort(&mut products);
+    if args.group_orders {
+        group_orders(&mut products);
+    }
+    let summary = summarize(&args, &products, start.elapsed());
+    if !products.is_empty() {
+        if let Err(e) = args.baseline.resolve(&products) {
+            Args::command().error(ErrorKind::InvalidValue, e).exit();
+        }
+    }
+    let comparison = baseline.map(|baseline| {
+        baseline.compare(
+            &mut products,
+            seed,
+            sizes,
+            preset,
+            args.regression_threshold,
+        )
+    });
+    if let Some(path) = &args.save_baseline {
+        let baseline = Baseline {
+            preset,
+            scenario: args.resolved.clone(),
+            ..Baseline::new(seed, sizes, &products)
+        };
+        baseline
+            .write(create_file(path))
+            .unwrap_or_else(|e| fail(path, e));
+    }
+    let mut record = HistoryRecord::new(SystemTime::now(), hostname(), seed, sizes, &products);
+    record.run.preset = preset;
+    record.run.scenario = args.resolved.clone();
+    if let Some(path) = &args.history {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .unwrap_or_else(|e| fail(path, e));
+        record.append(file).unwrap_or_else(|e| fail(path, e));
+    }
+    #[cfg(feature = "sqlite")]
+    if let Some(path) = &args.db {
+        open_db(path)
+            .insert(&record)
+            .unwrap_or_else(|e| fail(path, e));
+    }
+    // the intersection of more than two arrays holds every distinct element once
+    let semantics = match args.arrays {
+        2 => args.semantics,
+        _ => Semantics::Set,
+    };
+    let verification = Verification::new(&products, args.op, semantics, args.examples);
+    let equal = verification.passed();
+    let similarity = distinct.and_then(|distinct| similarity(&args, &products, distinct));
 
-    let equal = products
-        .windows(2)
-        .all(|values| values[0].result == values[1].result);
-    println!("\nall values are equal: {}", equal);
+    if let Some(path) = &args.csv {
+        write_csv(create_file(path), &products, &skipped, sizes.0, sizes.1)
+            .unwrap_or_else(|e| fail(path, e));
+    }
+    if args.format == Format::Csv {
+        write_csv(io::stdout(), &products, &skipped, sizes.0, sizes.1)
+            .expect("failed to write to stdout");
+    }
+    if let Some(path) = &args.html {
+        let options = HtmlOptions {
+            seed,
+            sizes,
+            table: args.table(inputs),
+            scale: args.graph_scale,
+            all_equal: equal,
+        };
+        let mut out = BufWriter::new(create_file(path));
+        write_html(&mut out, &products, &skipped, &options)
+            .and_then(|_| out.flush())
+            .unwrap_or_else(|e| fail(path, e));
+    }
+    if let Some(path) = &args.svg {
+        let options = SvgOptions {
+            seed,
+            sizes,
+            scale: args.graph_scale,
+            time_unit: args.time_unit,
+        };
+        let mut out = BufWriter::new(create_file(path));
+        write_svg(&mut out, &products, &options)
+            .and_then(|_| out.flush())
+            .unwrap_or_else(|e| fail(path, e));
+    }
+    if let Some(path) = &args.dat {
+        let mut out = BufWriter::new(create_file(path));
+        write_dat(&mut out, &products, sizes, &args.dat_options(seed))
+            .and_then(|_| out.flush())
+            .unwrap_or_else(|e| fail(path, e));
+    }
+    if let Some(path) = &args.json {
+        let report = Report::new(
+            started_at,
+            seed,
+            sizes,
+            &products,
+            &skipped,
+            &verification,
+            similarity,
+        )
+        .with_scenario(args.resolved.clone());
+        if path == Path::new("-") {
+            serde_json::to_writer_pretty(io::stdout(), &report).expect("failed to write to stdout");
+            println!();
+        } else {
+            serde_json::to_writer_pretty(create_file(path), &report)
+                .unwrap_or_else(|e| fail(path, e));
+        }
+    }
+    if args.quiet {
+        print_times(&mut io::stdout(), &products).expect("failed to write to stdout");
+    } else if human {
+        let results = Results {
+            interrupted: options.interrupted(),
+            elements: &T::describe(element),
+            inputs,
+            products: &products,
+            skipped: &skipped,
+            verification: &verification,
+            similarity: similarity.as_ref(),
+            baseline: comparison.as_ref(),
+            summary: summary.as_ref(),
+        };
+        print_report(&args, output, results);
+    }
+    if options.interrupted() {
+        exit(INTERRUPTED);
+    }
+    if args.check {
+        check(equal, Some(&verification), timed_out(&products));
+    }
+    let regressed = comparison.as_ref().is_some_and(|c| c.regressed());
+    if args.fail_on_regression && regressed {
+        exit(1);
+    }
+}
+
+/// The names of the products that timed out.
+fn timed_out<'a, T: 'a>(products: impl IntoIterator<Item = &'a Product<T>>) -> Vec<&'a str> {
+    products
+        .into_iter()
+        .filter(|p| p.timed_out.is_some())
+        .map(|p| p.name.as_str())
+        .collect()
+}
+
+/// Exits with an error for `--check` if the results aren't `equal` or a
+/// product timed out, describing the failures on stderr with `verification`
+/// unless they were already described.
+fn check<T: Element>(equal: bool, verification: Option<&Verification<T>>, timed_out: Vec<&str>) {
+    if equal && timed_out.is_empty() {
+        return;
+    }
+    let mut err = io::stderr();
+    if let Some(verification) = verification.filter(|_| !equal) {
+        verification
+            .write(&mut err)
+            .expect("failed to write to stderr");
+    }
+    if !timed_out.is_empty() {
+        eprintln!("timed out: {}", timed_out.join(", "));
+    }
+    eprintln!("error: the check failed");
+    exit(1)
+}
+
+/// A method that finds nothing, added by the hidden `--broken-method` flag so
+/// that the tests can check how wrong results are reported.
+struct Broken;
+
+impl<T: Element> Intersect<T> for Broken {
+    fn name(&self) -> String {
+        "Broken".to_string()
+    }
+
+    fn description(&self) -> &str {
+        "returns nothing, to test how wrong results are reported"
+    }
+
+    fn intersect(&self, _big: &[T], _small: &[T]) -> Vec<T> {
+        Vec::new()
+    }
+
+    fn intersect_seq(&self, _big: &[T], _small: &[T]) -> Vec<T> {
+        Vec::new()
+    }
+}
+
+fn print_report<T: Element>(args: &Args, output: Option<(&PathBuf, File)>, results: Results<T>) {
+    let Results {
+        products,
+        verification,
+        ..
+    } = results;
+    let printer = args.printer();
+    if args.warmup > 0 {
+        printer.normal(format_args!(
+            "warmup took {:?}\n",
+            products.iter().map(|p| p.warmup).sum::<Duration>()
+        ));
+    }
+    match output {
+        Some((path, file)) => {
+            let mut out = BufWriter::new(file);
+            write_report(&mut out, args, results, false)
+                .and_then(|_| out.flush())
+                .unwrap_or_else(|e| fail(path, e));
+            printer.normal(format_args!("results written to {}", path.display()));
+            if let Some(fastest) = products.iter().min_by_key(|p| p.time) {
+                printer.normal(format_args!(
+                    "fastest method: {} ({:?})",
+                    fastest.name, fastest.time
+                ));
+            }
+            printer.normal(format_args!(
+                "all values are equal: {}",
+                verification.passed()
+            ));
+        }
+        None => write_report(
+            &mut io::stdout(),
+            args,
+            results,
+            !args.no_color && io::stdout().is_terminal(),
+        )
+        .expect("failed to write to stdout"),
+    }
 }
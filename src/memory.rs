@@ -0,0 +1,91 @@
+//! Counting the memory methods allocate.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicBool, AtomicIsize, AtomicUsize, Ordering::Relaxed},
+};
+
+static COUNTING: AtomicBool = AtomicBool::new(false);
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static LIVE: AtomicIsize = AtomicIsize::new(0);
+static PEAK: AtomicIsize = AtomicIsize::new(0);
+
+/// A global allocator that forwards to [`System`] and counts the bytes
+/// allocated while [`BenchmarkOptions::measure_memory`](crate::BenchmarkOptions::measure_memory)
+/// measures a method.
+///
+/// Install it with `#[global_allocator]` for the memory columns to be filled.
+/// Outside of a measurement it only checks a flag.
+pub struct CountingAllocator;
+
+impl CountingAllocator {
+    fn grow(size: usize) {
+        ALLOCATED.fetch_add(size, Relaxed);
+        let live = LIVE.fetch_add(size as isize, Relaxed) + size as isize;
+        PEAK.fetch_max(live, Relaxed);
+    }
+
+    fn shrink(size: usize) {
+        LIVE.fetch_sub(size as isize, Relaxed);
+    }
+}
+
+// SAFETY: every call is forwarded to the system allocator unchanged.
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() && COUNTING.load(Relaxed) {
+            Self::grow(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc_zeroed(layout);
+        if !ptr.is_null() && COUNTING.load(Relaxed) {
+            Self::grow(layout.size());
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        if COUNTING.load(Relaxed) {
+            Self::shrink(layout.size());
+        }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new = System.realloc(ptr, layout, new_size);
+        if !new.is_null() && COUNTING.load(Relaxed) {
+            Self::shrink(layout.size());
+            Self::grow(new_size);
+        }
+        new
+    }
+}
+
+/// The memory one run of a method allocated.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Memory {
+    /// The sum of the sizes of all allocations.
+    pub allocated: usize,
+    /// The most bytes that were allocated and not yet freed at once.
+    pub peak: usize,
+}
+
+/// Runs `f` and counts what it allocates, which is only accurate when nothing
+/// else allocates at the same time.
+pub(crate) fn measure<R>(f: impl FnOnce() -> R) -> (R, Memory) {
+    ALLOCATED.store(0, Relaxed);
+    LIVE.store(0, Relaxed);
+    PEAK.store(0, Relaxed);
+    COUNTING.store(true, Relaxed);
+    let result = f();
+    COUNTING.store(false, Relaxed);
+    let memory = Memory {
+        allocated: ALLOCATED.load(Relaxed),
+        peak: PEAK.load(Relaxed).max(0) as usize,
+    };
+    (result, memory)
+}
@@ -0,0 +1,3098 @@
+//! The intersection strategies being compared.
+
+use std::{
+    any::type_name,
+    borrow::Cow,
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
+    f64::consts::LN_2,
+    fmt::Debug,
+    hash::{BuildHasher, BuildHasherDefault, DefaultHasher, RandomState},
+    hint::black_box,
+    iter::{from_fn, repeat_n},
+    mem::replace,
+    ops::Bound::{Excluded, Included, Unbounded},
+    sync::atomic::{AtomicBool, AtomicU64, Ordering::Relaxed},
+};
+
+use ahash::AHashSet;
+use rayon::{
+    current_num_threads,
+    iter::{IntoParallelIterator, IntoParallelRefIterator, ParallelIterator},
+    slice::{ParallelSlice, ParallelSliceMut},
+};
+#[cfg(feature = "roaring")]
+use roaring::RoaringTreemap;
+use rustc_hash::{FxBuildHasher, FxHashSet};
+
+use crate::{Op, Semantics};
+
+const LANES: usize = 4;
+
+type FixedState = BuildHasherDefault<DefaultHasher>;
+
+/// Compares every element of one array with every element of the other.
+#[derive(Debug)]
+pub struct Squared;
+/// Like [`Squared`], but stops looking as soon as a match is found.
+#[derive(Debug)]
+pub struct SquaredBreak;
+/// Looks elements up in a `BTreeSet` built from the smaller array.
+#[derive(Debug)]
+pub struct BTree;
+/// Builds `BTreeSet`s from both arrays and leapfrogs through them with `range`.
+#[derive(Debug)]
+pub struct BTreeBoth;
+/// Binary searches the smaller array, sorting a copy first unless it is already sorted.
+#[derive(Debug)]
+pub struct Binary;
+/// Like [`Binary`], but sorts the copy with the stable `sort` instead of
+/// `sort_unstable`.
+#[derive(Debug)]
+pub struct BinaryStable;
+/// Looks elements up in a `HashSet` built from the smaller array.
+#[derive(Debug)]
+pub struct Hash;
+/// Like [`Hash`], with the Fx hasher of `rustc-hash` instead of SipHash.
+#[derive(Debug)]
+pub struct HashFx;
+/// Like [`Hash`], with the hasher of `ahash` instead of SipHash.
+#[derive(Debug)]
+pub struct HashA;
+/// Splits both arrays into buckets by hash and intersects the pairs of
+/// buckets independently with a `HashSet` each.
+#[derive(Debug)]
+pub struct PartitionedHash {
+    partitions: Option<usize>,
+}
+/// Sorts both arrays and walks them side by side.
+#[derive(Debug)]
+pub struct Merge;
+/// Sorts both arrays and gallops through the bigger one with exponential search.
+#[derive(Debug)]
+pub struct Galloping;
+/// Picks [`Binary`] or [`Hash`] from the lengths of the arrays it gets, never
+/// a quadratic method.
+#[derive(Debug)]
+pub struct Auto {
+    ratio: usize,
+    tiny: usize,
+}
+/// Collects both arrays into `HashSet`s and calls `HashSet::intersection`.
+#[derive(Debug)]
+pub struct StdIntersection;
+/// Copies the bigger array and calls `Vec::retain` with a `HashSet` of the smaller one.
+#[derive(Debug)]
+pub struct Retain;
+/// Sorts both arrays and compares them in blocks the compiler can vectorize.
+#[derive(Debug)]
+pub struct Simd;
+/// Looks elements up in a compressed Roaring bitmap.
+#[cfg(feature = "roaring")]
+#[derive(Debug)]
+pub struct Roaring;
+/// Looks elements up in a bitmap indexed by value, if it fits the memory budget.
+#[derive(Debug)]
+pub struct Bitset {
+    budget: usize,
+}
+/// Looks elements up in an array of booleans indexed by value, if it fits the
+/// memory budget.
+#[derive(Debug)]
+pub struct DirectIndex {
+    budget: usize,
+}
+/// Filters with a Bloom filter before looking elements up in a `HashSet`.
+#[derive(Debug)]
+pub struct BloomHash {
+    false_positive_rate: f64,
+}
+
+/// The bounds an element needs to be intersected by every generic method.
+pub trait Element: Ord + std::hash::Hash + Clone + Debug + Send + Sync {}
+
+impl<T: Ord + std::hash::Hash + Clone + Debug + Send + Sync> Element for T {}
+
+/// A lookup structure built from the smaller array, see [`Intersect::prepare`].
+pub trait Prepared<T = usize>: Send + Sync {
+    /// Returns every element of `big` the structure contains, using all
+    /// available threads.
+    fn probe(&self, big: &[T]) -> Vec<T>;
+
+    /// Does the same as `probe` on a single thread.
+    fn probe_seq(&self, big: &[T]) -> Vec<T>;
+
+    /// Whether the structure contains `value`.
+    fn contains(&self, value: &T) -> bool;
+}
+
+/// A structure built from the smaller array, with the test for whether it
+/// contains an element.
+struct Lookup<S, T> {
+    set: S,
+    contains: fn(&S, &T) -> bool,
+}
+
+impl<S: Send + Sync, T: Element> Prepared<T> for Lookup<S, T> {
+    fn probe(&self, big: &[T]) -> Vec<T> {
+        big.par_iter()
+            .filter(|x| (self.contains)(&self.set, x))
+            .cloned()
+            .collect()
+    }
+
+    fn probe_seq(&self, big: &[T]) -> Vec<T> {
+        big.iter()
+            .filter(|x| (self.contains)(&self.set, x))
+            .cloned()
+            .collect()
+    }
+
+    fn contains(&self, value: &T) -> bool {
+        (self.contains)(&self.set, value)
+    }
+}
+
+/// Boxes a [`Lookup`] as the result of [`Intersect::prepare`].
+fn lookup<'a, S: Send + Sync + 'a, T: Element + 'a>(
+    set: S,
+    contains: fn(&S, &T) -> bool,
+) -> Option<Box<dyn Prepared<T> + 'a>> {
+    Some(Box::new(Lookup { set, contains }))
+}
+
+/// The arrays from the smallest to the biggest.
+fn by_size<T>(arrays: &[Vec<T>]) -> Vec<&[T]> {
+    let mut arrays: Vec<&[T]> = arrays.iter().map(Vec::as_slice).collect();
+    arrays.sort_by_key(|array| array.len());
+    arrays
+}
+
+/// Intersects the distinct elements of the smallest array with every other
+/// array in order of size, stopping once none are left.
+fn progressively<T: Element>(
+    arrays: &[Vec<T>],
+    intersect: impl Fn(&[T], &[T]) -> Vec<T>,
+) -> Vec<T> {
+    let arrays = by_size(arrays);
+    let Some((smallest, others)) = arrays.split_first() else {
+        return Vec::new();
+    };
+    let mut candidates = smallest.to_vec();
+    candidates.sort_unstable();
+    candidates.dedup();
+    for other in others {
+        if candidates.is_empty() {
+            break;
+        }
+        candidates = intersect(&candidates, other);
+    }
+    candidates
+}
+
+/// A way of intersecting two arrays of `T`.
+///
+/// Every element of `big` that also occurs in `small` is returned, in any order.
+/// Methods can also support the other set operations in [`Op`].
+pub trait Intersect<T = usize>: Send + Sync {
+    /// The name shown in the results, the type name without its path by default.
+    fn name(&self) -> String {
+        let name = type_name::<Self>();
+        let name = name.split('<').next().unwrap_or(name);
+        name.rsplit("::").next().unwrap_or(name).to_string()
+    }
+
+    /// The name shown for a run on these arrays, [`name`](Intersect::name) by
+    /// default.
+    fn name_for(&self, _big: &[T], _small: &[T]) -> String {
+        self.name()
+    }
+
+    /// How the method intersects, in one line for `--list-methods`.
+    fn description(&self) -> &str {
+        "no description"
+    }
+
+    /// The expected time in the length `n` of `big` and `m` of `small`, like
+    /// `O((n + m) log m)`.
+    fn complexity(&self) -> &str {
+        "unknown"
+    }
+
+    /// Intersects the arrays using all available threads.
+    fn intersect(&self, big: &[T], small: &[T]) -> Vec<T>;
+
+    /// Does the same as `intersect` on a single thread.
+    fn intersect_seq(&self, big: &[T], small: &[T]) -> Vec<T>;
+
+    /// Does the same as `intersect` on arrays that are both sorted, so that
+    /// methods relying on sorted input can skip sorting them.
+    fn intersect_sorted(&self, big: &[T], small: &[T]) -> Vec<T> {
+        self.intersect(big, small)
+    }
+
+    /// Does the same as `intersect_sorted` on a single thread.
+    fn intersect_sorted_seq(&self, big: &[T], small: &[T]) -> Vec<T> {
+        self.intersect_seq(big, small)
+    }
+
+    /// Counts the elements `intersect` returns, without collecting them if
+    /// the method can avoid it.
+    fn intersect_count(&self, big: &[T], small: &[T]) -> usize {
+        // only the length is used, so black_box keeps the optimizer from
+        // dropping the work that computes the elements
+        black_box(self.intersect(big, small)).len()
+    }
+
+    /// Does the same as `intersect_count` on a single thread.
+    fn intersect_count_seq(&self, big: &[T], small: &[T]) -> usize {
+        black_box(self.intersect_seq(big, small)).len()
+    }
+
+    /// Yields the elements `intersect_seq` returns one at a time, without
+    /// collecting them if the method can avoid it.
+    fn intersect_iter<'a>(&self, big: &'a [T], small: &'a [T]) -> Box<dyn Iterator<Item = T> + 'a> {
+        Box::new(self.intersect_seq(big, small).into_iter())
+    }
+
+    /// Intersects all of `arrays` using all available threads, returning
+    /// every distinct element that occurs in each of them, in any order.
+    ///
+    /// By default the distinct elements of the smallest array are intersected
+    /// with the other arrays one after another, from small to big.
+    fn intersect_many(&self, arrays: &[Vec<T>]) -> Vec<T>
+    where
+        T: Element,
+    {
+        progressively(arrays, |candidates, other| {
+            self.intersect(candidates, other)
+        })
+    }
+
+    /// Does the same as `intersect_many` on a single thread.
+    fn intersect_many_seq(&self, arrays: &[Vec<T>]) -> Vec<T>
+    where
+        T: Element,
+    {
+        progressively(arrays, |candidates, other| {
+            self.intersect_seq(candidates, other)
+        })
+    }
+
+    /// Builds the structure `intersect` looks the elements of `big` up in, so
+    /// that building and probing it can be timed apart.
+    ///
+    /// Methods that don't build one from `small` return `None`, their whole
+    /// time counts as probing.
+    fn prepare<'a>(&self, _small: &'a [T]) -> Option<Box<dyn Prepared<T> + 'a>> {
+        None
+    }
+
+    /// Returns why the method can't run on the given input, if it can't.
+    fn skip_reason(&self, _big: &[T], _small: &[T]) -> Option<String> {
+        None
+    }
+
+    /// Whether `intersect` returns an element of `big` as often as it occurs
+    /// there. Methods that return every distinct element once only run with
+    /// [`Semantics::MultisetBig`] on a `big` without duplicates.
+    fn keeps_duplicates(&self) -> bool {
+        true
+    }
+
+    /// Whether [`intersect_as`](Intersect::intersect_as) computes the
+    /// intersection with `semantics` itself, by default only for the
+    /// semantics `intersect` returns anyway.
+    ///
+    /// The benchmark skips methods that don't honor the chosen semantics.
+    fn honors(&self, semantics: Semantics) -> bool {
+        match semantics {
+            Semantics::Set => !self.keeps_duplicates(),
+            Semantics::MultisetBig => self.keeps_duplicates(),
+            Semantics::Pairs => false,
+        }
+    }
+
+    /// Intersects the arrays with `semantics` using all available threads,
+    /// if the method honors them.
+    fn intersect_as(&self, semantics: Semantics, big: &[T], small: &[T]) -> Vec<T> {
+        assert!(
+            self.honors(semantics),
+            "{} does not honor the {} semantics",
+            self.name(),
+            semantics.name()
+        );
+        self.intersect(big, small)
+    }
+
+    /// Does the same as `intersect_as` on a single thread.
+    fn intersect_as_seq(&self, semantics: Semantics, big: &[T], small: &[T]) -> Vec<T> {
+        assert!(
+            self.honors(semantics),
+            "{} does not honor the {} semantics",
+            self.name(),
+            semantics.name()
+        );
+        self.intersect_seq(big, small)
+    }
+
+    /// Counts the elements `intersect_as` returns, without collecting them if
+    /// the method can avoid it.
+    fn intersect_count_as(&self, semantics: Semantics, big: &[T], small: &[T]) -> usize {
+        black_box(self.intersect_as(semantics, big, small)).len()
+    }
+
+    /// Does the same as `intersect_count_as` on a single thread.
+    fn intersect_count_as_seq(&self, semantics: Semantics, big: &[T], small: &[T]) -> usize {
+        black_box(self.intersect_as_seq(semantics, big, small)).len()
+    }
+
+    /// Whether the time grows with the product of the array lengths, so that
+    /// [`BenchmarkOptions::quadratic_limit`](crate::BenchmarkOptions::quadratic_limit)
+    /// applies to the method.
+    fn is_quadratic(&self) -> bool {
+        false
+    }
+
+    /// Whether the method can compute `op`, only intersection by default.
+    fn supports(&self, op: Op) -> bool {
+        op == Op::Intersection
+    }
+
+    /// Computes `op` using all available threads, if the method supports it.
+    fn apply(&self, op: Op, big: &[T], small: &[T]) -> Vec<T> {
+        match op {
+            Op::Intersection => self.intersect(big, small),
+            _ => panic!("{} does not support the {}", self.name(), op.name()),
+        }
+    }
+
+    /// Does the same as `apply` on a single thread.
+    fn apply_seq(&self, op: Op, big: &[T], small: &[T]) -> Vec<T> {
+        match op {
+            Op::Intersection => self.intersect_seq(big, small),
+            _ => panic!("{} does not support the {}", self.name(), op.name()),
+        }
+    }
+}
+
+/// What a method makes of the elements of an intersection: the elements
+/// themselves, or how many there are.
+trait Gather<T>: Sized {
+    /// Takes every element of `big` as often as `copies` says, in parallel if
+    /// requested.
+    fn gather(big: &[T], copies: impl Fn(&T) -> usize + Send + Sync, parallel: bool) -> Self;
+}
+
+impl<T: Element> Gather<T> for Vec<T> {
+    fn gather(big: &[T], copies: impl Fn(&T) -> usize + Send + Sync, parallel: bool) -> Self {
+        let repeated = |x: &T| repeat_n(x.clone(), copies(x));
+        if parallel {
+            big.par_iter().flat_map_iter(repeated).collect()
+        } else {
+            big.iter().flat_map(repeated).collect()
+        }
+    }
+}
+
+impl<T: Element> Gather<T> for usize {
+    fn gather(big: &[T], copies: impl Fn(&T) -> usize + Send + Sync, parallel: bool) -> Self {
+        if parallel {
+            big.par_iter().map(copies).sum()
+        } else {
+            big.iter().map(copies).sum()
+        }
+    }
+}
+
+impl Squared {
+    /// Whether `x` equals any element of `small`, comparing it with all of them.
+    fn matches<T: Element>(x: &T, small: &[T]) -> bool {
+        small.iter().fold(false, |found, y| found | (x == y))
+    }
+
+    /// How often `x` occurs in `small`, comparing it with all of them.
+    fn occurrences<T: Element>(x: &T, small: &[T]) -> usize {
+        small.iter().fold(0, |count, y| count + usize::from(x == y))
+    }
+
+    /// Whether the element of `small` at `i` occurs in `big` but not before
+    /// `i`, which holds once for every distinct element the arrays share.
+    fn first_shared<T: Element>(i: usize, big: &[T], small: &[T]) -> bool {
+        !Self::matches(&small[i], &small[..i]) & Self::matches(&small[i], big)
+    }
+}
+
+impl<T: Element> Intersect<T> for Squared {
+    fn description(&self) -> &str {
+        "compares every element of big with every element of small"
+    }
+
+    fn complexity(&self) -> &str {
+        "O(n·m)"
+    }
+
+    fn intersect(&self, big: &[T], small: &[T]) -> Vec<T> {
+        big.par_iter()
+            .filter(|x| Self::matches(*x, small))
+            .cloned()
+            .collect()
+    }
+
+    fn intersect_seq(&self, big: &[T], small: &[T]) -> Vec<T> {
+        big.iter()
+            .filter(|x| Self::matches(*x, small))
+            .cloned()
+            .collect()
+    }
+
+    fn intersect_count(&self, big: &[T], small: &[T]) -> usize {
+        big.par_iter().filter(|x| Self::matches(*x, small)).count()
+    }
+
+    fn intersect_count_seq(&self, big: &[T], small: &[T]) -> usize {
+        big.iter().filter(|x| Self::matches(*x, small)).count()
+    }
+
+    fn intersect_iter<'a>(&self, big: &'a [T], small: &'a [T]) -> Box<dyn Iterator<Item = T> + 'a> {
+        Box::new(big.iter().filter(|x| Self::matches(*x, small)).cloned())
+    }
+
+    fn honors(&self, _semantics: Semantics) -> bool {
+        true
+    }
+
+    fn intersect_as(&self, semantics: Semantics, big: &[T], small: &[T]) -> Vec<T> {
+        match semantics {
+            Semantics::Set => (0..small.len())
+                .into_par_iter()
+                .filter(|&i| Self::first_shared(i, big, small))
+                .map(|i| small[i].clone())
+                .collect(),
+            Semantics::MultisetBig => self.intersect(big, small),
+            Semantics::Pairs => big
+                .par_iter()
+                .flat_map_iter(|x| repeat_n(x.clone(), Self::occurrences(x, small)))
+                .collect(),
+        }
+    }
+
+    fn intersect_as_seq(&self, semantics: Semantics, big: &[T], small: &[T]) -> Vec<T> {
+        match semantics {
+            Semantics::Set => (0..small.len())
+                .filter(|&i| Self::first_shared(i, big, small))
+                .map(|i| small[i].clone())
+                .collect(),
+            Semantics::MultisetBig => self.intersect_seq(big, small),
+            Semantics::Pairs => big
+                .iter()
+                .flat_map(|x| repeat_n(x.clone(), Self::occurrences(x, small)))
+                .collect(),
+        }
+    }
+
+    fn intersect_count_as(&self, semantics: Semantics, big: &[T], small: &[T]) -> usize {
+        match semantics {
+            Semantics::Set => (0..small.len())
+                .into_par_iter()
+                .filter(|&i| Self::first_shared(i, big, small))
+                .count(),
+            Semantics::MultisetBig => self.intersect_count(big, small),
+            Semantics::Pairs => big.par_iter().map(|x| Self::occurrences(x, small)).sum(),
+        }
+    }
+
+    fn intersect_count_as_seq(&self, semantics: Semantics, big: &[T], small: &[T]) -> usize {
+        match semantics {
+            Semantics::Set => (0..small.len())
+                .filter(|&i| Self::first_shared(i, big, small))
+                .count(),
+            Semantics::MultisetBig => self.intersect_count_seq(big, small),
+            Semantics::Pairs => big.iter().map(|x| Self::occurrences(x, small)).sum(),
+        }
+    }
+
+    fn is_quadratic(&self) -> bool {
+        true
+    }
+
+    fn supports(&self, _op: Op) -> bool {
+        true
+    }
+
+    fn apply(&self, op: Op, big: &[T], small: &[T]) -> Vec<T> {
+        match op {
+            Op::Intersection => self.intersect(big, small),
+            Op::Union => Self::union(big, small, true),
+            Op::Difference => Self::difference(big, small, true),
+            Op::SymmetricDifference => Self::symmetric_difference(big, small, true),
+        }
+    }
+
+    fn apply_seq(&self, op: Op, big: &[T], small: &[T]) -> Vec<T> {
+        match op {
+            Op::Intersection => self.intersect_seq(big, small),
+            Op::Union => Self::union(big, small, false),
+            Op::Difference => Self::difference(big, small, false),
+            Op::SymmetricDifference => Self::symmetric_difference(big, small, false),
+        }
+    }
+}
+
+impl SquaredBreak {
+    /// Whether the element of `small` at `i` occurs in `big` but not before
+    /// `i`, stopping at the first match of either.
+    fn first_shared<T: Element>(i: usize, big: &[T], small: &[T]) -> bool {
+        !small[..i].contains(&small[i]) && big.contains(&small[i])
+    }
+
+    /// How often `x` occurs in `small`, which takes comparing it with all of
+    /// them.
+    fn occurrences<T: Element>(x: &T, small: &[T]) -> usize {
+        small.iter().filter(|y| *y == x).count()
+    }
+}
+
+impl<T: Element> Intersect<T> for SquaredBreak {
+    fn description(&self) -> &str {
+        "compares every element of big with small until it finds a match"
+    }
+
+    fn complexity(&self) -> &str {
+        "O(n·m)"
+    }
+
+    fn intersect(&self, big: &[T], small: &[T]) -> Vec<T> {
+        big.par_iter()
+            .filter(|i| small.par_iter().find_any(|j| j == i).is_some())
+            .cloned()
+            .collect()
+    }
+
+    fn intersect_seq(&self, big: &[T], small: &[T]) -> Vec<T> {
+        big.iter()
+            .filter(|i| small.iter().any(|j| j == *i))
+            .cloned()
+            .collect()
+    }
+
+    fn intersect_count(&self, big: &[T], small: &[T]) -> usize {
+        big.par_iter()
+            .filter(|i| small.par_iter().find_any(|j| j == i).is_some())
+            .count()
+    }
+
+    fn intersect_count_seq(&self, big: &[T], small: &[T]) -> usize {
+        big.iter().filter(|i| small.iter().any(|j| j == *i)).count()
+    }
+
+    fn intersect_iter<'a>(&self, big: &'a [T], small: &'a [T]) -> Box<dyn Iterator<Item = T> + 'a> {
+        Box::new(
+            big.iter()
+                .filter(|i| small.iter().any(|j| j == *i))
+                .cloned(),
+        )
+    }
+
+    fn honors(&self, _semantics: Semantics) -> bool {
+        true
+    }
+
+    fn intersect_as(&self, semantics: Semantics, big: &[T], small: &[T]) -> Vec<T> {
+        match semantics {
+            Semantics::Set => (0..small.len())
+                .into_par_iter()
+                .filter(|&i| Self::first_shared(i, big, small))
+                .map(|i| small[i].clone())
+                .collect(),
+            Semantics::MultisetBig => self.intersect(big, small),
+            Semantics::Pairs => Gather::gather(big, |x| Self::occurrences(x, small), true),
+        }
+    }
+
+    fn intersect_as_seq(&self, semantics: Semantics, big: &[T], small: &[T]) -> Vec<T> {
+        match semantics {
+            Semantics::Set => (0..small.len())
+                .filter(|&i| Self::first_shared(i, big, small))
+                .map(|i| small[i].clone())
+                .collect(),
+            Semantics::MultisetBig => self.intersect_seq(big, small),
+            Semantics::Pairs => Gather::gather(big, |x| Self::occurrences(x, small), false),
+        }
+    }
+
+    fn intersect_count_as(&self, semantics: Semantics, big: &[T], small: &[T]) -> usize {
+        match semantics {
+            Semantics::Set => (0..small.len())
+                .into_par_iter()
+                .filter(|&i| Self::first_shared(i, big, small))
+                .count(),
+            Semantics::MultisetBig => self.intersect_count(big, small),
+            Semantics::Pairs => Gather::gather(big, |x| Self::occurrences(x, small), true),
+        }
+    }
+
+    fn intersect_count_as_seq(&self, semantics: Semantics, big: &[T], small: &[T]) -> usize {
+        match semantics {
+            Semantics::Set => (0..small.len())
+                .filter(|&i| Self::first_shared(i, big, small))
+                .count(),
+            Semantics::MultisetBig => self.intersect_count_seq(big, small),
+            Semantics::Pairs => Gather::gather(big, |x| Self::occurrences(x, small), false),
+        }
+    }
+
+    fn is_quadratic(&self) -> bool {
+        true
+    }
+
+    fn supports(&self, _op: Op) -> bool {
+        true
+    }
+
+    fn apply(&self, op: Op, big: &[T], small: &[T]) -> Vec<T> {
+        match op {
+            Op::Intersection => self.intersect(big, small),
+            Op::Union => Self::union(big, small, true),
+            Op::Difference => Self::difference(big, small, true),
+            Op::SymmetricDifference => Self::symmetric_difference(big, small, true),
+        }
+    }
+
+    fn apply_seq(&self, op: Op, big: &[T], small: &[T]) -> Vec<T> {
+        match op {
+            Op::Intersection => self.intersect_seq(big, small),
+            Op::Union => Self::union(big, small, false),
+            Op::Difference => Self::difference(big, small, false),
+            Op::SymmetricDifference => Self::symmetric_difference(big, small, false),
+        }
+    }
+}
+
+impl BTree {
+    /// How often every element of `small` occurs in it.
+    fn occurrences<T: Ord>(small: &[T]) -> BTreeMap<&T, usize> {
+        let mut counts = BTreeMap::new();
+        for x in small {
+            *counts.entry(x).or_default() += 1;
+        }
+        counts
+    }
+
+    /// The intersection with `semantics` of `big` and a tree of `small`, in
+    /// parallel if requested.
+    ///
+    /// For sets, the tree holds a flag for every element, which the first
+    /// element of `big` to find it sets, so that no other one counts.
+    fn tree_as<T: Element, R: Gather<T>>(
+        semantics: Semantics,
+        big: &[T],
+        small: &[T],
+        parallel: bool,
+    ) -> R {
+        match semantics {
+            Semantics::Set => {
+                let taken: BTreeMap<&T, AtomicBool> =
+                    small.iter().map(|x| (x, AtomicBool::new(false))).collect();
+                let copies = |x: &T| {
+                    taken
+                        .get(x)
+                        .map_or(0, |t| usize::from(!t.swap(true, Relaxed)))
+                };
+                R::gather(big, copies, parallel)
+            }
+            Semantics::MultisetBig => {
+                let small = BTreeSet::from_iter(small);
+                R::gather(big, |x| usize::from(small.contains(x)), parallel)
+            }
+            Semantics::Pairs => {
+                let counts = Self::occurrences(small);
+                R::gather(big, |x| counts.get(x).copied().unwrap_or(0), parallel)
+            }
+        }
+    }
+}
+
+impl<T: Element> Intersect<T> for BTree {
+    fn description(&self) -> &str {
+        "builds a BTreeSet from small, looks every element of big up in it"
+    }
+
+    fn complexity(&self) -> &str {
+        "O((n + m) log m)"
+    }
+
+    fn intersect(&self, big: &[T], small: &[T]) -> Vec<T> {
+        let small = BTreeSet::from_iter(small);
+        big.par_iter()
+            .filter(|i| small.contains(i))
+            .cloned()
+            .collect()
+    }
+
+    fn intersect_seq(&self, big: &[T], small: &[T]) -> Vec<T> {
+        let small = BTreeSet::from_iter(small);
+        big.iter().filter(|i| small.contains(i)).cloned().collect()
+    }
+
+    fn intersect_count(&self, big: &[T], small: &[T]) -> usize {
+        let small = BTreeSet::from_iter(small);
+        big.par_iter().filter(|i| small.contains(i)).count()
+    }
+
+    fn intersect_count_seq(&self, big: &[T], small: &[T]) -> usize {
+        let small = BTreeSet::from_iter(small);
+        big.iter().filter(|i| small.contains(i)).count()
+    }
+
+    fn intersect_iter<'a>(&self, big: &'a [T], small: &'a [T]) -> Box<dyn Iterator<Item = T> + 'a> {
+        let small = BTreeSet::from_iter(small);
+        Box::new(big.iter().filter(move |i| small.contains(i)).cloned())
+    }
+
+    fn honors(&self, _semantics: Semantics) -> bool {
+        true
+    }
+
+    fn intersect_as(&self, semantics: Semantics, big: &[T], small: &[T]) -> Vec<T> {
+        Self::tree_as(semantics, big, small, true)
+    }
+
+    fn intersect_as_seq(&self, semantics: Semantics, big: &[T], small: &[T]) -> Vec<T> {
+        Self::tree_as(semantics, big, small, false)
+    }
+
+    fn intersect_count_as(&self, semantics: Semantics, big: &[T], small: &[T]) -> usize {
+        Self::tree_as(semantics, big, small, true)
+    }
+
+    fn intersect_count_as_seq(&self, semantics: Semantics, big: &[T], small: &[T]) -> usize {
+        Self::tree_as(semantics, big, small, false)
+    }
+
+    fn intersect_many(&self, arrays: &[Vec<T>]) -> Vec<T> {
+        let arrays = by_size(arrays);
+        let Some((smallest, others)) = arrays.split_first() else {
+            return Vec::new();
+        };
+        let mut set = BTreeSet::from_iter(*smallest);
+        for other in others {
+            set = other.par_iter().filter(|x| set.contains(x)).collect();
+        }
+        set.into_iter().cloned().collect()
+    }
+
+    fn intersect_many_seq(&self, arrays: &[Vec<T>]) -> Vec<T> {
+        let arrays = by_size(arrays);
+        let Some((smallest, others)) = arrays.split_first() else {
+            return Vec::new();
+        };
+        let mut set = BTreeSet::from_iter(*smallest);
+        for other in others {
+            set = other.iter().filter(|x| set.contains(x)).collect();
+        }
+        set.into_iter().cloned().collect()
+    }
+
+    fn prepare<'a>(&self, small: &'a [T]) -> Option<Box<dyn Prepared<T> + 'a>> {
+        lookup(BTreeSet::from_iter(small), |set, x| set.contains(x))
+    }
+
+    fn supports(&self, _op: Op) -> bool {
+        true
+    }
+
+    fn apply(&self, op: Op, big: &[T], small: &[T]) -> Vec<T> {
+        match op {
+            Op::Intersection => self.intersect(big, small),
+            Op::Union => Self::union(big, small, true),
+            Op::Difference => Self::difference(big, small, true),
+            Op::SymmetricDifference => Self::symmetric_difference(big, small, true),
+        }
+    }
+
+    fn apply_seq(&self, op: Op, big: &[T], small: &[T]) -> Vec<T> {
+        match op {
+            Op::Intersection => self.intersect_seq(big, small),
+            Op::Union => Self::union(big, small, false),
+            Op::Difference => Self::difference(big, small, false),
+            Op::SymmetricDifference => Self::symmetric_difference(big, small, false),
+        }
+    }
+}
+
+impl BTreeBoth {
+    /// Calls `emit` for every element of both maps, in ascending order, with
+    /// how often it occurs in `big` and in `small`, by seeking each map to the
+    /// next candidate of the other one.
+    fn leapfrog<T: Ord>(
+        big: &BTreeMap<&T, usize>,
+        small: &BTreeMap<&T, usize>,
+        mut emit: impl FnMut(&T, usize, usize),
+    ) {
+        let (mut seeking, mut other) = (big, small);
+        // whether `seeking` is `small`, and the candidate with its count there
+        let mut swapped = false;
+        let Some((&first, &count)) = seeking.first_key_value() else {
+            return;
+        };
+        let mut candidate = (first, count);
+        while let Some((&next, &count)) = other
+            .range::<&T, _>((Included(candidate.0), Unbounded))
+            .next()
+        {
+            if next == candidate.0 {
+                match swapped {
+                    false => emit(next, candidate.1, count),
+                    true => emit(next, count, candidate.1),
+                }
+                match other
+                    .range::<&T, _>((Excluded(candidate.0), Unbounded))
+                    .next()
+                {
+                    Some((&next, &count)) => candidate = (next, count),
+                    None => return,
+                }
+            } else {
+                candidate = (next, count);
+            }
+            (seeking, other) = (other, seeking);
+            swapped = !swapped;
+        }
+    }
+
+    fn maps<'a, T: Element>(
+        big: &'a [T],
+        small: &'a [T],
+        parallel: bool,
+    ) -> (BTreeMap<&'a T, usize>, BTreeMap<&'a T, usize>) {
+        if parallel {
+            rayon::join(|| BTree::occurrences(big), || BTree::occurrences(small))
+        } else {
+            (BTree::occurrences(big), BTree::occurrences(small))
+        }
+    }
+}
+
+impl<T: Element> Intersect<T> for BTreeBoth {
+    fn description(&self) -> &str {
+        "counts both arrays in BTreeMaps, skips through them with range() from candidate to candidate"
+    }
+
+    fn complexity(&self) -> &str {
+        "O(n log n + m log m)"
+    }
+
+    fn intersect(&self, big: &[T], small: &[T]) -> Vec<T> {
+        self.intersect_as(Semantics::Set, big, small)
+    }
+
+    fn intersect_seq(&self, big: &[T], small: &[T]) -> Vec<T> {
+        self.intersect_as_seq(Semantics::Set, big, small)
+    }
+
+    fn intersect_count(&self, big: &[T], small: &[T]) -> usize {
+        self.intersect_count_as(Semantics::Set, big, small)
+    }
+
+    fn intersect_count_seq(&self, big: &[T], small: &[T]) -> usize {
+        self.intersect_count_as_seq(Semantics::Set, big, small)
+    }
+
+    fn keeps_duplicates(&self) -> bool {
+        false
+    }
+
+    fn honors(&self, _semantics: Semantics) -> bool {
+        true
+    }
+
+    fn intersect_as(&self, semantics: Semantics, big: &[T], small: &[T]) -> Vec<T> {
+        let (big, small) = Self::maps(big, small, true);
+        from_runs(semantics, |emit| Self::leapfrog(&big, &small, emit))
+    }
+
+    fn intersect_as_seq(&self, semantics: Semantics, big: &[T], small: &[T]) -> Vec<T> {
+        let (big, small) = Self::maps(big, small, false);
+        from_runs(semantics, |emit| Self::leapfrog(&big, &small, emit))
+    }
+
+    fn intersect_count_as(&self, semantics: Semantics, big: &[T], small: &[T]) -> usize {
+        let (big, small) = Self::maps(big, small, true);
+        count_runs(semantics, |emit| Self::leapfrog(&big, &small, emit))
+    }
+
+    fn intersect_count_as_seq(&self, semantics: Semantics, big: &[T], small: &[T]) -> usize {
+        let (big, small) = Self::maps(big, small, false);
+        count_runs(semantics, |emit| Self::leapfrog(&big, &small, emit))
+    }
+}
+
+impl Binary {
+    /// How often `x` is in the intersection with `semantics` of the sorted
+    /// `small` and an array of elements like `x`.
+    ///
+    /// For sets, `taken` holds a flag for every element of `small`, which the
+    /// first element to find it sets, so that no other one counts.
+    fn copies<T: Element>(semantics: Semantics, x: &T, small: &[T], taken: &[AtomicBool]) -> usize {
+        let start = small.partition_point(|y| y < x);
+        let equal = small[start..].partition_point(|y| y == x);
+        match semantics {
+            _ if equal == 0 => 0,
+            Semantics::Set => usize::from(!taken[start].swap(true, Relaxed)),
+            Semantics::MultisetBig => 1,
+            Semantics::Pairs => equal,
+        }
+    }
+
+    /// The flags [`Binary::copies`] needs for `small`, none unless `semantics`
+    /// is [`Semantics::Set`].
+    fn flags<T>(semantics: Semantics, small: &[T]) -> Vec<AtomicBool> {
+        match semantics {
+            Semantics::Set => small.iter().map(|_| AtomicBool::new(false)).collect(),
+            _ => Vec::new(),
+        }
+    }
+
+    /// The intersection with `semantics` of `big` and the sorted `small`, in
+    /// parallel if requested.
+    fn sorted_as<T: Element, R: Gather<T>>(
+        semantics: Semantics,
+        big: &[T],
+        small: &[T],
+        parallel: bool,
+    ) -> R {
+        let taken = Self::flags(semantics, small);
+        R::gather(big, |x| Self::copies(semantics, x, small, &taken), parallel)
+    }
+}
+
+impl<T: Element> Intersect<T> for Binary {
+    fn description(&self) -> &str {
+        "sorts small, binary searches it for every element of big"
+    }
+
+    fn complexity(&self) -> &str {
+        "O((n + m) log m)"
+    }
+
+    fn intersect(&self, big: &[T], small: &[T]) -> Vec<T> {
+        let small = sorted(small, false);
+        big.par_iter()
+            .filter(|i| small.binary_search(i).is_ok())
+            .cloned()
+            .collect()
+    }
+
+    fn intersect_seq(&self, big: &[T], small: &[T]) -> Vec<T> {
+        let small = sorted(small, false);
+        big.iter()
+            .filter(|i| small.binary_search(i).is_ok())
+            .cloned()
+            .collect()
+    }
+
+    fn intersect_sorted(&self, big: &[T], small: &[T]) -> Vec<T> {
+        big.par_iter()
+            .filter(|i| small.binary_search(i).is_ok())
+            .cloned()
+            .collect()
+    }
+
+    fn intersect_sorted_seq(&self, big: &[T], small: &[T]) -> Vec<T> {
+        big.iter()
+            .filter(|i| small.binary_search(i).is_ok())
+            .cloned()
+            .collect()
+    }
+
+    fn intersect_count(&self, big: &[T], small: &[T]) -> usize {
+        let small = sorted(small, false);
+        big.par_iter()
+            .filter(|i| small.binary_search(i).is_ok())
+            .count()
+    }
+
+    fn intersect_count_seq(&self, big: &[T], small: &[T]) -> usize {
+        let small = sorted(small, false);
+        big.iter()
+            .filter(|i| small.binary_search(i).is_ok())
+            .count()
+    }
+
+    fn intersect_iter<'a>(&self, big: &'a [T], small: &'a [T]) -> Box<dyn Iterator<Item = T> + 'a> {
+        let small = sorted(small, false);
+        Box::new(
+            big.iter()
+                .filter(move |i| small.binary_search(i).is_ok())
+                .cloned(),
+        )
+    }
+
+    fn honors(&self, _semantics: Semantics) -> bool {
+        true
+    }
+
+    fn intersect_as(&self, semantics: Semantics, big: &[T], small: &[T]) -> Vec<T> {
+        match semantics {
+            Semantics::MultisetBig => self.intersect(big, small),
+            _ => Self::sorted_as(semantics, big, &sorted(small, false), true),
+        }
+    }
+
+    fn intersect_as_seq(&self, semantics: Semantics, big: &[T], small: &[T]) -> Vec<T> {
+        match semantics {
+            Semantics::MultisetBig => self.intersect_seq(big, small),
+            _ => Self::sorted_as(semantics, big, &sorted(small, false), false),
+        }
+    }
+
+    fn intersect_count_as(&self, semantics: Semantics, big: &[T], small: &[T]) -> usize {
+        match semantics {
+            Semantics::MultisetBig => self.intersect_count(big, small),
+            _ => Self::sorted_as(semantics, big, &sorted(small, false), true),
+        }
+    }
+
+    fn intersect_count_as_seq(&self, semantics: Semantics, big: &[T], small: &[T]) -> usize {
+        match semantics {
+            Semantics::MultisetBig => self.intersect_count_seq(big, small),
+            _ => Self::sorted_as(semantics, big, &sorted(small, false), false),
+        }
+    }
+
+    fn intersect_many(&self, arrays: &[Vec<T>]) -> Vec<T> {
+        let arrays = by_size(arrays);
+        let Some((smallest, others)) = arrays.split_first() else {
+            return Vec::new();
+        };
+        let others: Vec<_> = others.iter().map(|other| sorted(other, true)).collect();
+        let mut candidates = smallest.to_vec();
+        candidates.par_sort_unstable();
+        candidates.dedup();
+        candidates
+            .into_par_iter()
+            .filter(|x| others.iter().all(|other| other.binary_search(x).is_ok()))
+            .collect()
+    }
+
+    fn intersect_many_seq(&self, arrays: &[Vec<T>]) -> Vec<T> {
+        let arrays = by_size(arrays);
+        let Some((smallest, others)) = arrays.split_first() else {
+            return Vec::new();
+        };
+        let others: Vec<_> = others.iter().map(|other| sorted(other, false)).collect();
+        let mut candidates = smallest.to_vec();
+        candidates.sort_unstable();
+        candidates.dedup();
+        candidates
+            .into_iter()
+            .filter(|x| others.iter().all(|other| other.binary_search(x).is_ok()))
+            .collect()
+    }
+
+    fn prepare<'a>(&self, small: &'a [T]) -> Option<Box<dyn Prepared<T> + 'a>> {
+        lookup(sorted(small, false), |small, x| {
+            small.binary_search(x).is_ok()
+        })
+    }
+
+    fn supports(&self, _op: Op) -> bool {
+        true
+    }
+
+    fn apply(&self, op: Op, big: &[T], small: &[T]) -> Vec<T> {
+        match op {
+            Op::Intersection => self.intersect(big, small),
+            Op::Union => Self::union(big, small, true),
+            Op::Difference => Self::difference(big, small, true),
+            Op::SymmetricDifference => Self::symmetric_difference(big, small, true),
+        }
+    }
+
+    fn apply_seq(&self, op: Op, big: &[T], small: &[T]) -> Vec<T> {
+        match op {
+            Op::Intersection => self.intersect_seq(big, small),
+            Op::Union => Self::union(big, small, false),
+            Op::Difference => Self::difference(big, small, false),
+            Op::SymmetricDifference => Self::symmetric_difference(big, small, false),
+        }
+    }
+}
+
+impl<T: Element> Intersect<T> for BinaryStable {
+    fn description(&self) -> &str {
+        "sorts small with the stable sort, binary searches it for every element of big"
+    }
+
+    fn complexity(&self) -> &str {
+        "O((n + m) log m)"
+    }
+
+    fn intersect(&self, big: &[T], small: &[T]) -> Vec<T> {
+        Binary.intersect_sorted(big, &stably_sorted(small, true))
+    }
+
+    fn intersect_seq(&self, big: &[T], small: &[T]) -> Vec<T> {
+        Binary.intersect_sorted_seq(big, &stably_sorted(small, false))
+    }
+
+    fn intersect_sorted(&self, big: &[T], small: &[T]) -> Vec<T> {
+        Binary.intersect_sorted(big, small)
+    }
+
+    fn intersect_sorted_seq(&self, big: &[T], small: &[T]) -> Vec<T> {
+        Binary.intersect_sorted_seq(big, small)
+    }
+
+    fn intersect_count(&self, big: &[T], small: &[T]) -> usize {
+        let small = stably_sorted(small, true);
+        big.par_iter()
+            .filter(|i| small.binary_search(i).is_ok())
+            .count()
+    }
+
+    fn intersect_count_seq(&self, big: &[T], small: &[T]) -> usize {
+        let small = stably_sorted(small, false);
+        big.iter()
+            .filter(|i| small.binary_search(i).is_ok())
+            .count()
+    }
+
+    fn honors(&self, _semantics: Semantics) -> bool {
+        true
+    }
+
+    fn intersect_as(&self, semantics: Semantics, big: &[T], small: &[T]) -> Vec<T> {
+        match semantics {
+            Semantics::MultisetBig => self.intersect(big, small),
+            _ => Binary::sorted_as(semantics, big, &stably_sorted(small, true), true),
+        }
+    }
+
+    fn intersect_as_seq(&self, semantics: Semantics, big: &[T], small: &[T]) -> Vec<T> {
+        match semantics {
+            Semantics::MultisetBig => self.intersect_seq(big, small),
+            _ => Binary::sorted_as(semantics, big, &stably_sorted(small, false), false),
+        }
+    }
+
+    fn intersect_count_as(&self, semantics: Semantics, big: &[T], small: &[T]) -> usize {
+        match semantics {
+            Semantics::MultisetBig => self.intersect_count(big, small),
+            _ => Binary::sorted_as(semantics, big, &stably_sorted(small, true), true),
+        }
+    }
+
+    fn intersect_count_as_seq(&self, semantics: Semantics, big: &[T], small: &[T]) -> usize {
+        match semantics {
+            Semantics::MultisetBig => self.intersect_count_seq(big, small),
+            _ => Binary::sorted_as(semantics, big, &stably_sorted(small, false), false),
+        }
+    }
+
+    fn prepare<'a>(&self, small: &'a [T]) -> Option<Box<dyn Prepared<T> + 'a>> {
+        lookup(stably_sorted(small, false), |small, x| {
+            small.binary_search(x).is_ok()
+        })
+    }
+}
+
+impl<T: Element> Intersect<T> for Hash {
+    fn description(&self) -> &str {
+        "builds a HashSet from small, looks every element of big up in it"
+    }
+
+    fn complexity(&self) -> &str {
+        "O(n + m) expected"
+    }
+
+    fn intersect(&self, big: &[T], small: &[T]) -> Vec<T> {
+        let small: HashSet<&T> = small.iter().collect();
+        big.par_iter()
+            .filter(|i| small.contains(i))
+            .cloned()
+            .collect()
+    }
+
+    fn intersect_seq(&self, big: &[T], small: &[T]) -> Vec<T> {
+        let small: HashSet<&T> = small.iter().collect();
+        big.iter().filter(|i| small.contains(i)).cloned().collect()
+    }
+
+    fn intersect_count(&self, big: &[T], small: &[T]) -> usize {
+        let small: HashSet<&T> = small.iter().collect();
+        big.par_iter().filter(|i| small.contains(i)).count()
+    }
+
+    fn intersect_count_seq(&self, big: &[T], small: &[T]) -> usize {
+        let small: HashSet<&T> = small.iter().collect();
+        big.iter().filter(|i| small.contains(i)).count()
+    }
+
+    fn intersect_iter<'a>(&self, big: &'a [T], small: &'a [T]) -> Box<dyn Iterator<Item = T> + 'a> {
+        let small: HashSet<&T> = small.iter().collect();
+        Box::new(big.iter().filter(move |i| small.contains(i)).cloned())
+    }
+
+    fn honors(&self, _semantics: Semantics) -> bool {
+        true
+    }
+
+    fn intersect_as(&self, semantics: Semantics, big: &[T], small: &[T]) -> Vec<T> {
+        hashed_as::<T, RandomState>(semantics, big, small, true)
+    }
+
+    fn intersect_as_seq(&self, semantics: Semantics, big: &[T], small: &[T]) -> Vec<T> {
+        hashed_as::<T, RandomState>(semantics, big, small, false)
+    }
+
+    fn intersect_count_as(&self, semantics: Semantics, big: &[T], small: &[T]) -> usize {
+        hashed_count_as::<T, RandomState>(semantics, big, small, true)
+    }
+
+    fn intersect_count_as_seq(&self, semantics: Semantics, big: &[T], small: &[T]) -> usize {
+        hashed_count_as::<T, RandomState>(semantics, big, small, false)
+    }
+
+    fn intersect_many(&self, arrays: &[Vec<T>]) -> Vec<T> {
+        let arrays = by_size(arrays);
+        let Some((smallest, others)) = arrays.split_first() else {
+            return Vec::new();
+        };
+        let mut set: HashSet<&T> = smallest.iter().collect();
+        for other in others {
+            set = other.par_iter().filter(|x| set.contains(x)).collect();
+        }
+        set.into_iter().cloned().collect()
+    }
+
+    fn intersect_many_seq(&self, arrays: &[Vec<T>]) -> Vec<T> {
+        let arrays = by_size(arrays);
+        let Some((smallest, others)) = arrays.split_first() else {
+            return Vec::new();
+        };
+        let mut set: HashSet<&T> = smallest.iter().collect();
+        for other in others {
+            set = other.iter().filter(|x| set.contains(x)).collect();
+        }
+        set.into_iter().cloned().collect()
+    }
+
+    fn prepare<'a>(&self, small: &'a [T]) -> Option<Box<dyn Prepared<T> + 'a>> {
+        lookup(small.iter().collect::<HashSet<&T>>(), |set, x| {
+            set.contains(x)
+        })
+    }
+
+    fn supports(&self, _op: Op) -> bool {
+        true
+    }
+
+    fn apply(&self, op: Op, big: &[T], small: &[T]) -> Vec<T> {
+        match op {
+            Op::Intersection => self.intersect(big, small),
+            Op::Union => Self::union(big, small, true),
+            Op::Difference => Self::difference(big, small, true),
+            Op::SymmetricDifference => Self::symmetric_difference(big, small, true),
+        }
+    }
+
+    fn apply_seq(&self, op: Op, big: &[T], small: &[T]) -> Vec<T> {
+        match op {
+            Op::Intersection => self.intersect_seq(big, small),
+            Op::Union => Self::union(big, small, false),
+            Op::Difference => Self::difference(big, small, false),
+            Op::SymmetricDifference => Self::symmetric_difference(big, small, false),
+        }
+    }
+}
+
+/// The elements of `big` that are in a set of `small` hashed with `S`, in
+/// parallel if requested.
+fn hashed<T: Element, S: BuildHasher + Default + Sync>(
+    big: &[T],
+    small: &[T],
+    parallel: bool,
+) -> Vec<T> {
+    let small: HashSet<&T, S> = small.iter().collect();
+    if parallel {
+        big.par_iter()
+            .filter(|i| small.contains(i))
+            .cloned()
+            .collect()
+    } else {
+        big.iter().filter(|i| small.contains(i)).cloned().collect()
+    }
+}
+
+/// Counts what [`hashed`] returns without collecting it.
+fn hashed_count<T: Element, S: BuildHasher + Default + Sync>(
+    big: &[T],
+    small: &[T],
+    parallel: bool,
+) -> usize {
+    let small: HashSet<&T, S> = small.iter().collect();
+    if parallel {
+        big.par_iter().filter(|i| small.contains(i)).count()
+    } else {
+        big.iter().filter(|i| small.contains(i)).count()
+    }
+}
+
+/// The intersection with `semantics` of `big` and a set of `small` hashed
+/// with `S`, or a map of how often its elements occur for
+/// [`Semantics::Pairs`], in parallel if requested.
+fn hashed_as<T: Element, S: BuildHasher + Default + Send + Sync>(
+    semantics: Semantics,
+    big: &[T],
+    small: &[T],
+    parallel: bool,
+) -> Vec<T> {
+    match semantics {
+        Semantics::Set if parallel => {
+            let small: HashSet<&T, S> = small.iter().collect();
+            let found: HashSet<&T, S> = big.par_iter().filter(|x| small.contains(x)).collect();
+            found.into_iter().cloned().collect()
+        }
+        Semantics::Set => {
+            // removing what was found keeps the next equal element out
+            let mut small: HashSet<&T, S> = small.iter().collect();
+            big.iter().filter(|x| small.remove(x)).cloned().collect()
+        }
+        Semantics::MultisetBig => hashed::<T, S>(big, small, parallel),
+        Semantics::Pairs => {
+            let counts = occurrences::<T, S>(small);
+            let pairs = |x: &T| repeat_n(x.clone(), counts.get(x).copied().unwrap_or(0));
+            if parallel {
+                big.par_iter().flat_map_iter(pairs).collect()
+            } else {
+                big.iter().flat_map(pairs).collect()
+            }
+        }
+    }
+}
+
+/// Counts what [`hashed_as`] returns without collecting it.
+fn hashed_count_as<T: Element, S: BuildHasher + Default + Send + Sync>(
+    semantics: Semantics,
+    big: &[T],
+    small: &[T],
+    parallel: bool,
+) -> usize {
+    match semantics {
+        Semantics::Set if parallel => {
+            let small: HashSet<&T, S> = small.iter().collect();
+            let found: HashSet<&T, S> = big.par_iter().filter(|x| small.contains(x)).collect();
+            found.len()
+        }
+        Semantics::Set => {
+            let mut small: HashSet<&T, S> = small.iter().collect();
+            big.iter().filter(|x| small.remove(x)).count()
+        }
+        Semantics::MultisetBig => hashed_count::<T, S>(big, small, parallel),
+        Semantics::Pairs => {
+            let counts = occurrences::<T, S>(small);
+            let pairs = |x: &T| counts.get(x).copied().unwrap_or(0);
+            if parallel {
+                big.par_iter().map(pairs).sum()
+            } else {
+                big.iter().map(pairs).sum()
+            }
+        }
+    }
+}
+
+/// How often every element of `small` occurs in it, hashed with `S`.
+fn occurrences<T: Element, S: BuildHasher + Default>(small: &[T]) -> HashMap<&T, usize, S> {
+    let mut counts = HashMap::default();
+    for x in small {
+        *counts.entry(x).or_default() += 1;
+    }
+    counts
+}
+
+impl<T: Element> Intersect<T> for HashFx {
+    fn description(&self) -> &str {
+        "builds a HashSet with the Fx hasher from small, looks every element of big up in it"
+    }
+
+    fn complexity(&self) -> &str {
+        "O(n + m) expected"
+    }
+
+    fn intersect(&self, big: &[T], small: &[T]) -> Vec<T> {
+        hashed::<T, FxBuildHasher>(big, small, true)
+    }
+
+    fn intersect_seq(&self, big: &[T], small: &[T]) -> Vec<T> {
+        hashed::<T, FxBuildHasher>(big, small, false)
+    }
+
+    fn intersect_count(&self, big: &[T], small: &[T]) -> usize {
+        hashed_count::<T, FxBuildHasher>(big, small, true)
+    }
+
+    fn intersect_count_seq(&self, big: &[T], small: &[T]) -> usize {
+        hashed_count::<T, FxBuildHasher>(big, small, false)
+    }
+
+    fn intersect_iter<'a>(&self, big: &'a [T], small: &'a [T]) -> Box<dyn Iterator<Item = T> + 'a> {
+        let small: FxHashSet<&T> = small.iter().collect();
+        Box::new(big.iter().filter(move |i| small.contains(i)).cloned())
+    }
+
+    fn honors(&self, _semantics: Semantics) -> bool {
+        true
+    }
+
+    fn intersect_as(&self, semantics: Semantics, big: &[T], small: &[T]) -> Vec<T> {
+        hashed_as::<T, FxBuildHasher>(semantics, big, small, true)
+    }
+
+    fn intersect_as_seq(&self, semantics: Semantics, big: &[T], small: &[T]) -> Vec<T> {
+        hashed_as::<T, FxBuildHasher>(semantics, big, small, false)
+    }
+
+    fn intersect_count_as(&self, semantics: Semantics, big: &[T], small: &[T]) -> usize {
+        hashed_count_as::<T, FxBuildHasher>(semantics, big, small, true)
+    }
+
+    fn intersect_count_as_seq(&self, semantics: Semantics, big: &[T], small: &[T]) -> usize {
+        hashed_count_as::<T, FxBuildHasher>(semantics, big, small, false)
+    }
+
+    fn prepare<'a>(&self, small: &'a [T]) -> Option<Box<dyn Prepared<T> + 'a>> {
+        lookup(small.iter().collect::<FxHashSet<&T>>(), |set, x| {
+            set.contains(x)
+        })
+    }
+}
+
+impl<T: Element> Intersect<T> for HashA {
+    fn description(&self) -> &str {
+        "builds a HashSet with the ahash hasher from small, looks every element of big up in it"
+    }
+
+    fn complexity(&self) -> &str {
+        "O(n + m) expected"
+    }
+
+    fn intersect(&self, big: &[T], small: &[T]) -> Vec<T> {
+        hashed::<T, ahash::RandomState>(big, small, true)
+    }
+
+    fn intersect_seq(&self, big: &[T], small: &[T]) -> Vec<T> {
+        hashed::<T, ahash::RandomState>(big, small, false)
+    }
+
+    fn intersect_count(&self, big: &[T], small: &[T]) -> usize {
+        hashed_count::<T, ahash::RandomState>(big, small, true)
+    }
+
+    fn intersect_count_seq(&self, big: &[T], small: &[T]) -> usize {
+        hashed_count::<T, ahash::RandomState>(big, small, false)
+    }
+
+    fn intersect_iter<'a>(&self, big: &'a [T], small: &'a [T]) -> Box<dyn Iterator<Item = T> + 'a> {
+        let small: AHashSet<&T> = small.iter().collect();
+        Box::new(big.iter().filter(move |i| small.contains(i)).cloned())
+    }
+
+    fn honors(&self, _semantics: Semantics) -> bool {
+        true
+    }
+
+    fn intersect_as(&self, semantics: Semantics, big: &[T], small: &[T]) -> Vec<T> {
+        hashed_as::<T, ahash::RandomState>(semantics, big, small, true)
+    }
+
+    fn intersect_as_seq(&self, semantics: Semantics, big: &[T], small: &[T]) -> Vec<T> {
+        hashed_as::<T, ahash::RandomState>(semantics, big, small, false)
+    }
+
+    fn intersect_count_as(&self, semantics: Semantics, big: &[T], small: &[T]) -> usize {
+        hashed_count_as::<T, ahash::RandomState>(semantics, big, small, true)
+    }
+
+    fn intersect_count_as_seq(&self, semantics: Semantics, big: &[T], small: &[T]) -> usize {
+        hashed_count_as::<T, ahash::RandomState>(semantics, big, small, false)
+    }
+
+    fn prepare<'a>(&self, small: &'a [T]) -> Option<Box<dyn Prepared<T> + 'a>> {
+        lookup(small.iter().collect::<AHashSet<&T>>(), |set, x| {
+            set.contains(x)
+        })
+    }
+}
+
+impl PartitionedHash {
+    /// Creates a method that splits the arrays into `partitions` buckets, or
+    /// one per thread of the current pool if `None`.
+    pub fn new(partitions: Option<usize>) -> Self {
+        PartitionedHash { partitions }
+    }
+
+    fn partitions(&self) -> usize {
+        self.partitions.unwrap_or_else(current_num_threads).max(1)
+    }
+
+    /// Splits `values` into `partitions` buckets by their hash, keeping their
+    /// order within every bucket.
+    fn partition<'a, T: Element>(
+        values: &'a [T],
+        partitions: usize,
+        parallel: bool,
+    ) -> Vec<Vec<&'a T>> {
+        let split = |values: &'a [T]| {
+            let mut buckets = vec![Vec::new(); partitions];
+            for x in values {
+                buckets[FixedState::default().hash_one(x) as usize % partitions].push(x);
+            }
+            buckets
+        };
+        if !parallel {
+            return split(values);
+        }
+        let chunks: Vec<Vec<Vec<&T>>> = values
+            .par_chunks(values.len().div_ceil(partitions).max(1))
+            .map(split)
+            .collect();
+        (0..partitions)
+            .into_par_iter()
+            .map(|bucket| {
+                chunks
+                    .iter()
+                    .flat_map(|chunk| &chunk[bucket])
+                    .copied()
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// The buckets of `big` next to a set of the matching bucket of `small`.
+    fn buckets<'a, T: Element>(
+        &self,
+        big: &'a [T],
+        small: &'a [T],
+        parallel: bool,
+    ) -> Vec<(Vec<&'a T>, HashSet<&'a T>)> {
+        let set = |(big, small): (Vec<&'a T>, Vec<&'a T>)| (big, small.into_iter().collect());
+        let buckets = self.split(big, small, parallel);
+        if parallel {
+            buckets.into_par_iter().map(set).collect()
+        } else {
+            buckets.into_iter().map(set).collect()
+        }
+    }
+
+    /// The buckets of `big` next to the matching bucket of `small`.
+    fn split<'a, T: Element>(
+        &self,
+        big: &'a [T],
+        small: &'a [T],
+        parallel: bool,
+    ) -> Vec<(Vec<&'a T>, Vec<&'a T>)> {
+        let partitions = self.partitions();
+        let (big, small) = if parallel {
+            rayon::join(
+                || Self::partition(big, partitions, true),
+                || Self::partition(small, partitions, true),
+            )
+        } else {
+            (
+                Self::partition(big, partitions, false),
+                Self::partition(small, partitions, false),
+            )
+        };
+        big.into_iter().zip(small).collect()
+    }
+
+    /// Every element of a bucket of `big` with how often it is in the
+    /// intersection with `semantics` of the bucket and the matching one of
+    /// `small`.
+    fn bucket_as<'a, T: Element>(
+        semantics: Semantics,
+        (big, small): (Vec<&'a T>, Vec<&'a T>),
+    ) -> impl Iterator<Item = (&'a T, usize)> {
+        let mut counts: HashMap<&T, usize> = HashMap::new();
+        for x in small {
+            *counts.entry(x).or_default() += 1;
+        }
+        big.into_iter().map(move |x| {
+            let copies = match semantics {
+                // removing what was found keeps the next equal element out
+                Semantics::Set => usize::from(counts.remove(x).is_some()),
+                Semantics::MultisetBig => usize::from(counts.contains_key(x)),
+                Semantics::Pairs => counts.get(x).copied().unwrap_or(0),
+            };
+            (x, copies)
+        })
+    }
+}
+
+impl<T: Element> Intersect<T> for PartitionedHash {
+    fn name(&self) -> String {
+        match self.partitions {
+            Some(partitions) => format!("PartitionedHash({})", partitions),
+            None => "PartitionedHash".to_string(),
+        }
+    }
+
+    fn description(&self) -> &str {
+        "splits both arrays into buckets by hash, intersects every pair of buckets with a HashSet"
+    }
+
+    fn complexity(&self) -> &str {
+        "O(n + m) expected"
+    }
+
+    fn intersect(&self, big: &[T], small: &[T]) -> Vec<T> {
+        self.buckets(big, small, true)
+            .into_par_iter()
+            .flat_map_iter(|(big, small)| {
+                big.into_iter().filter(move |x| small.contains(x)).cloned()
+            })
+            .collect()
+    }
+
+    fn intersect_seq(&self, big: &[T], small: &[T]) -> Vec<T> {
+        self.buckets(big, small, false)
+            .into_iter()
+            .flat_map(|(big, small)| big.into_iter().filter(move |x| small.contains(x)).cloned())
+            .collect()
+    }
+
+    fn intersect_count(&self, big: &[T], small: &[T]) -> usize {
+        self.buckets(big, small, true)
+            .into_par_iter()
+            .map(|(big, small)| big.iter().filter(|x| small.contains(*x)).count())
+            .sum()
+    }
+
+    fn intersect_count_seq(&self, big: &[T], small: &[T]) -> usize {
+        self.buckets(big, small, false)
+            .into_iter()
+            .map(|(big, small)| big.iter().filter(|x| small.contains(*x)).count())
+            .sum()
+    }
+
+    fn honors(&self, _semantics: Semantics) -> bool {
+        true
+    }
+
+    fn intersect_as(&self, semantics: Semantics, big: &[T], small: &[T]) -> Vec<T> {
+        self.split(big, small, true)
+            .into_par_iter()
+            .flat_map_iter(|bucket| {
+                Self::bucket_as(semantics, bucket)
+                    .flat_map(|(x, copies)| repeat_n(x.clone(), copies))
+            })
+            .collect()
+    }
+
+    fn intersect_as_seq(&self, semantics: Semantics, big: &[T], small: &[T]) -> Vec<T> {
+        self.split(big, small, false)
+            .into_iter()
+            .flat_map(|bucket| {
+                Self::bucket_as(semantics, bucket)
+                    .flat_map(|(x, copies)| repeat_n(x.clone(), copies))
+            })
+            .collect()
+    }
+
+    fn intersect_count_as(&self, semantics: Semantics, big: &[T], small: &[T]) -> usize {
+        self.split(big, small, true)
+            .into_par_iter()
+            .map(|bucket| {
+                Self::bucket_as(semantics, bucket)
+                    .map(|(_, copies)| copies)
+                    .sum::<usize>()
+            })
+            .sum()
+    }
+
+    fn intersect_count_as_seq(&self, semantics: Semantics, big: &[T], small: &[T]) -> usize {
+        self.split(big, small, false)
+            .into_iter()
+            .flat_map(|bucket| Self::bucket_as(semantics, bucket))
+            .map(|(_, copies)| copies)
+            .sum()
+    }
+}
+
+/// Borrows `values` if they are already sorted, otherwise sorts a copy, in
+/// parallel if requested.
+fn sorted<T: Element>(values: &[T], parallel: bool) -> Cow<'_, [T]> {
+    if values.is_sorted() {
+        return Cow::Borrowed(values);
+    }
+    let mut values = values.to_vec();
+    if parallel {
+        values.par_sort_unstable();
+    } else {
+        values.sort_unstable();
+    }
+    Cow::Owned(values)
+}
+
+/// Does the same as [`sorted`] with the stable sort.
+fn stably_sorted<T: Element>(values: &[T], parallel: bool) -> Cow<'_, [T]> {
+    if values.is_sorted() {
+        return Cow::Borrowed(values);
+    }
+    let mut values = values.to_vec();
+    if parallel {
+        values.par_sort();
+    } else {
+        values.sort();
+    }
+    Cow::Owned(values)
+}
+
+/// Sorts both slices, in parallel if requested.
+fn sorted_copies<'a, T: Element>(
+    big: &'a [T],
+    small: &'a [T],
+    parallel: bool,
+) -> (Cow<'a, [T]>, Cow<'a, [T]>) {
+    (sorted(big, parallel), sorted(small, parallel))
+}
+
+/// How many elements at the start of the sorted `values` equal the first one.
+fn run_length<T: Element>(values: &[T]) -> usize {
+    values.partition_point(|x| *x == values[0])
+}
+
+/// The intersection with `semantics` of the arrays whose shared elements
+/// `runs` emits, with how often they occur in each.
+fn from_runs<T: Element>(
+    semantics: Semantics,
+    runs: impl FnOnce(&mut dyn FnMut(&T, usize, usize)),
+) -> Vec<T> {
+    let mut result = Vec::new();
+    runs(&mut |x, in_big, in_small| {
+        result.extend(repeat_n(
+            x.clone(),
+            semantics.multiplicity(in_big, in_small),
+        ));
+    });
+    result
+}
+
+/// Counts what [`from_runs`] returns without collecting it.
+fn count_runs<T: Element>(
+    semantics: Semantics,
+    runs: impl FnOnce(&mut dyn FnMut(&T, usize, usize)),
+) -> usize {
+    let mut count = 0;
+    runs(&mut |_, in_big, in_small| count += semantics.multiplicity(in_big, in_small));
+    count
+}
+
+impl Merge {
+    /// Calls `emit` for every element of the sorted `big` that is in the sorted `small`.
+    fn merge<T: Element>(big: &[T], small: &[T], mut emit: impl FnMut(&T)) {
+        let (mut i, mut j) = (0, 0);
+        while i < big.len() && j < small.len() {
+            match big[i].cmp(&small[j]) {
+                Ordering::Less => i += 1,
+                Ordering::Greater => j += 1,
+                Ordering::Equal => {
+                    emit(&big[i]);
+                    i += 1;
+                }
+            }
+        }
+    }
+
+    /// Calls `emit` for every element the sorted arrays share, with how often
+    /// it occurs in `big` and in `small`.
+    fn merge_runs<T: Element>(big: &[T], small: &[T], mut emit: impl FnMut(&T, usize, usize)) {
+        let (mut i, mut j) = (0, 0);
+        while i < big.len() && j < small.len() {
+            match big[i].cmp(&small[j]) {
+                Ordering::Less => i += 1,
+                Ordering::Greater => j += 1,
+                Ordering::Equal => {
+                    let in_big = run_length(&big[i..]);
+                    let in_small = run_length(&small[j..]);
+                    emit(&big[i], in_big, in_small);
+                    (i, j) = (i + in_big, j + in_small);
+                }
+            }
+        }
+    }
+
+    /// Yields what `merge` emits, one element at a time.
+    fn merge_iter<'a, T: Element>(
+        big: Cow<'a, [T]>,
+        small: Cow<'a, [T]>,
+    ) -> impl Iterator<Item = T> + 'a {
+        let (mut i, mut j) = (0, 0);
+        from_fn(move || {
+            while i < big.len() && j < small.len() {
+                match big[i].cmp(&small[j]) {
+                    Ordering::Less => i += 1,
+                    Ordering::Greater => j += 1,
+                    Ordering::Equal => {
+                        i += 1;
+                        return Some(big[i - 1].clone());
+                    }
+                }
+            }
+            None
+        })
+    }
+}
+
+impl<T: Element> Intersect<T> for Merge {
+    fn description(&self) -> &str {
+        "sorts both arrays, walks them side by side"
+    }
+
+    fn complexity(&self) -> &str {
+        "O(n log n + m log m)"
+    }
+
+    fn intersect(&self, big: &[T], small: &[T]) -> Vec<T> {
+        let (big, small) = sorted_copies(big, small, true);
+        let mut result = Vec::new();
+        Self::merge(&big, &small, |x| result.push(x.clone()));
+        result
+    }
+
+    fn intersect_seq(&self, big: &[T], small: &[T]) -> Vec<T> {
+        let (big, small) = sorted_copies(big, small, false);
+        let mut result = Vec::new();
+        Self::merge(&big, &small, |x| result.push(x.clone()));
+        result
+    }
+
+    fn intersect_sorted(&self, big: &[T], small: &[T]) -> Vec<T> {
+        let mut result = Vec::new();
+        Self::merge(big, small, |x| result.push(x.clone()));
+        result
+    }
+
+    fn intersect_sorted_seq(&self, big: &[T], small: &[T]) -> Vec<T> {
+        self.intersect_sorted(big, small)
+    }
+
+    fn intersect_count(&self, big: &[T], small: &[T]) -> usize {
+        let (big, small) = sorted_copies(big, small, true);
+        let mut count = 0;
+        Self::merge(&big, &small, |_| count += 1);
+        count
+    }
+
+    fn intersect_count_seq(&self, big: &[T], small: &[T]) -> usize {
+        let (big, small) = sorted_copies(big, small, false);
+        let mut count = 0;
+        Self::merge(&big, &small, |_| count += 1);
+        count
+    }
+
+    fn intersect_iter<'a>(&self, big: &'a [T], small: &'a [T]) -> Box<dyn Iterator<Item = T> + 'a> {
+        let (big, small) = sorted_copies(big, small, false);
+        Box::new(Self::merge_iter(big, small))
+    }
+
+    fn honors(&self, _semantics: Semantics) -> bool {
+        true
+    }
+
+    fn intersect_as(&self, semantics: Semantics, big: &[T], small: &[T]) -> Vec<T> {
+        let (big, small) = sorted_copies(big, small, true);
+        from_runs(semantics, |emit| Self::merge_runs(&big, &small, emit))
+    }
+
+    fn intersect_as_seq(&self, semantics: Semantics, big: &[T], small: &[T]) -> Vec<T> {
+        let (big, small) = sorted_copies(big, small, false);
+        from_runs(semantics, |emit| Self::merge_runs(&big, &small, emit))
+    }
+
+    fn intersect_count_as(&self, semantics: Semantics, big: &[T], small: &[T]) -> usize {
+        let (big, small) = sorted_copies(big, small, true);
+        count_runs(semantics, |emit| Self::merge_runs(&big, &small, emit))
+    }
+
+    fn intersect_count_as_seq(&self, semantics: Semantics, big: &[T], small: &[T]) -> usize {
+        let (big, small) = sorted_copies(big, small, false);
+        count_runs(semantics, |emit| Self::merge_runs(&big, &small, emit))
+    }
+}
+
+impl Galloping {
+    /// Calls `emit` for every element of the sorted `big` that is in the sorted `small`.
+    fn gallop<T: Element>(big: &[T], small: &[T], mut emit: impl FnMut(&T)) {
+        let mut cursor = 0;
+        for value in small {
+            if cursor == big.len() {
+                break;
+            }
+            let mut step = 1;
+            while cursor + step < big.len() && big[cursor + step] < *value {
+                step *= 2;
+            }
+            let end = (cursor + step + 1).min(big.len());
+            cursor += big[cursor..end].partition_point(|x| x < value);
+            while cursor < big.len() && big[cursor] == *value {
+                emit(value);
+                cursor += 1;
+            }
+        }
+    }
+
+    /// Calls `emit` for every element the sorted arrays share, with how often
+    /// it occurs in `big` and in `small`.
+    fn gallop_runs<T: Element>(big: &[T], small: &[T], mut emit: impl FnMut(&T, usize, usize)) {
+        let (mut cursor, mut next) = (0, 0);
+        while next < small.len() && cursor < big.len() {
+            let value = &small[next];
+            let in_small = run_length(&small[next..]);
+            next += in_small;
+            let mut step = 1;
+            while cursor + step < big.len() && big[cursor + step] < *value {
+                step *= 2;
+            }
+            let end = (cursor + step + 1).min(big.len());
+            cursor += big[cursor..end].partition_point(|x| x < value);
+            if cursor < big.len() && big[cursor] == *value {
+                let in_big = run_length(&big[cursor..]);
+                emit(value, in_big, in_small);
+                cursor += in_big;
+            }
+        }
+    }
+
+    /// Yields what `gallop` emits, one element at a time.
+    fn gallop_iter<'a, T: Element>(
+        big: Cow<'a, [T]>,
+        small: Cow<'a, [T]>,
+    ) -> impl Iterator<Item = T> + 'a {
+        let (mut cursor, mut next) = (0, 0);
+        // whether the cursor was already moved to the value at `next`
+        let mut found = false;
+        from_fn(move || loop {
+            let value = small.get(next)?;
+            if cursor == big.len() {
+                return None;
+            }
+            if !found {
+                let mut step = 1;
+                while cursor + step < big.len() && big[cursor + step] < *value {
+                    step *= 2;
+                }
+                let end = (cursor + step + 1).min(big.len());
+                cursor += big[cursor..end].partition_point(|x| x < value);
+                found = true;
+            }
+            if cursor < big.len() && big[cursor] == *value {
+                cursor += 1;
+                return Some(value.clone());
+            }
+            next += 1;
+            found = false;
+        })
+    }
+}
+
+impl<T: Element> Intersect<T> for Galloping {
+    fn description(&self) -> &str {
+        "sorts both arrays, gallops through big with exponential search"
+    }
+
+    fn complexity(&self) -> &str {
+        "O(n log n + m log m)"
+    }
+
+    fn intersect(&self, big: &[T], small: &[T]) -> Vec<T> {
+        let (big, small) = sorted_copies(big, small, true);
+        let mut result = Vec::new();
+        Self::gallop(&big, &small, |x| result.push(x.clone()));
+        result
+    }
+
+    fn intersect_seq(&self, big: &[T], small: &[T]) -> Vec<T> {
+        let (big, small) = sorted_copies(big, small, false);
+        let mut result = Vec::new();
+        Self::gallop(&big, &small, |x| result.push(x.clone()));
+        result
+    }
+
+    fn intersect_sorted(&self, big: &[T], small: &[T]) -> Vec<T> {
+        let mut result = Vec::new();
+        Self::gallop(big, small, |x| result.push(x.clone()));
+        result
+    }
+
+    fn intersect_sorted_seq(&self, big: &[T], small: &[T]) -> Vec<T> {
+        self.intersect_sorted(big, small)
+    }
+
+    fn intersect_count(&self, big: &[T], small: &[T]) -> usize {
+        let (big, small) = sorted_copies(big, small, true);
+        let mut count = 0;
+        Self::gallop(&big, &small, |_| count += 1);
+        count
+    }
+
+    fn intersect_count_seq(&self, big: &[T], small: &[T]) -> usize {
+        let (big, small) = sorted_copies(big, small, false);
+        let mut count = 0;
+        Self::gallop(&big, &small, |_| count += 1);
+        count
+    }
+
+    fn intersect_iter<'a>(&self, big: &'a [T], small: &'a [T]) -> Box<dyn Iterator<Item = T> + 'a> {
+        let (big, small) = sorted_copies(big, small, false);
+        Box::new(Self::gallop_iter(big, small))
+    }
+
+    fn honors(&self, _semantics: Semantics) -> bool {
+        true
+    }
+
+    fn intersect_as(&self, semantics: Semantics, big: &[T], small: &[T]) -> Vec<T> {
+        let (big, small) = sorted_copies(big, small, true);
+        from_runs(semantics, |emit| Self::gallop_runs(&big, &small, emit))
+    }
+
+    fn intersect_as_seq(&self, semantics: Semantics, big: &[T], small: &[T]) -> Vec<T> {
+        let (big, small) = sorted_copies(big, small, false);
+        from_runs(semantics, |emit| Self::gallop_runs(&big, &small, emit))
+    }
+
+    fn intersect_count_as(&self, semantics: Semantics, big: &[T], small: &[T]) -> usize {
+        let (big, small) = sorted_copies(big, small, true);
+        count_runs(semantics, |emit| Self::gallop_runs(&big, &small, emit))
+    }
+
+    fn intersect_count_as_seq(&self, semantics: Semantics, big: &[T], small: &[T]) -> usize {
+        let (big, small) = sorted_copies(big, small, false);
+        count_runs(semantics, |emit| Self::gallop_runs(&big, &small, emit))
+    }
+}
+
+impl Auto {
+    /// Creates a method that binary searches `small` if it has at most `tiny`
+    /// elements or `big` is at least `ratio` times as long, and looks the
+    /// elements up in a `HashSet` otherwise.
+    pub fn new(ratio: usize, tiny: usize) -> Self {
+        Auto { ratio, tiny }
+    }
+
+    /// The method that intersects `big` with `small`.
+    fn strategy<'a, T: Element>(&'a self, big: &[T], small: &[T]) -> &'a dyn Intersect<T> {
+        if small.len() <= self.tiny || big.len() / self.ratio.max(1) >= small.len() {
+            &Binary
+        } else {
+            &Hash
+        }
+    }
+}
+
+impl Default for Auto {
+    /// Binary searches arrays of up to 64 elements and arrays at least 512
+    /// times shorter than the other one.
+    fn default() -> Self {
+        Auto::new(512, 64)
+    }
+}
+
+impl<T: Element> Intersect<T> for Auto {
+    fn name_for(&self, big: &[T], small: &[T]) -> String {
+        format!("Auto({})", self.strategy(big, small).name())
+    }
+
+    fn description(&self) -> &str {
+        "binary searches small if it is tiny or much shorter than big, hashes it otherwise"
+    }
+
+    fn complexity(&self) -> &str {
+        "O(n log m) or O(n + m) expected"
+    }
+
+    fn intersect(&self, big: &[T], small: &[T]) -> Vec<T> {
+        self.strategy(big, small).intersect(big, small)
+    }
+
+    fn intersect_seq(&self, big: &[T], small: &[T]) -> Vec<T> {
+        self.strategy(big, small).intersect_seq(big, small)
+    }
+
+    fn intersect_sorted(&self, big: &[T], small: &[T]) -> Vec<T> {
+        self.strategy(big, small).intersect_sorted(big, small)
+    }
+
+    fn intersect_sorted_seq(&self, big: &[T], small: &[T]) -> Vec<T> {
+        self.strategy(big, small).intersect_sorted_seq(big, small)
+    }
+
+    fn intersect_count(&self, big: &[T], small: &[T]) -> usize {
+        self.strategy(big, small).intersect_count(big, small)
+    }
+
+    fn intersect_count_seq(&self, big: &[T], small: &[T]) -> usize {
+        self.strategy(big, small).intersect_count_seq(big, small)
+    }
+
+    fn intersect_iter<'a>(&self, big: &'a [T], small: &'a [T]) -> Box<dyn Iterator<Item = T> + 'a> {
+        self.strategy(big, small).intersect_iter(big, small)
+    }
+
+    fn honors(&self, semantics: Semantics) -> bool {
+        Intersect::<T>::honors(&Binary, semantics) && Intersect::<T>::honors(&Hash, semantics)
+    }
+
+    fn intersect_as(&self, semantics: Semantics, big: &[T], small: &[T]) -> Vec<T> {
+        self.strategy(big, small)
+            .intersect_as(semantics, big, small)
+    }
+
+    fn intersect_as_seq(&self, semantics: Semantics, big: &[T], small: &[T]) -> Vec<T> {
+        self.strategy(big, small)
+            .intersect_as_seq(semantics, big, small)
+    }
+
+    fn intersect_count_as(&self, semantics: Semantics, big: &[T], small: &[T]) -> usize {
+        self.strategy(big, small)
+            .intersect_count_as(semantics, big, small)
+    }
+
+    fn intersect_count_as_seq(&self, semantics: Semantics, big: &[T], small: &[T]) -> usize {
+        self.strategy(big, small)
+            .intersect_count_as_seq(semantics, big, small)
+    }
+
+    /// Picks from the length of `small` alone, since the queries are not known yet.
+    fn prepare<'a>(&self, small: &'a [T]) -> Option<Box<dyn Prepared<T> + 'a>> {
+        self.strategy(&[], small).prepare(small)
+    }
+
+    fn supports(&self, _op: Op) -> bool {
+        true
+    }
+
+    fn apply(&self, op: Op, big: &[T], small: &[T]) -> Vec<T> {
+        self.strategy(big, small).apply(op, big, small)
+    }
+
+    fn apply_seq(&self, op: Op, big: &[T], small: &[T]) -> Vec<T> {
+        self.strategy(big, small).apply_seq(op, big, small)
+    }
+}
+
+impl<T: Element> Intersect<T> for StdIntersection {
+    fn description(&self) -> &str {
+        "collects both arrays into HashSets, calls HashSet::intersection on a single thread"
+    }
+
+    fn complexity(&self) -> &str {
+        "O(n + m) expected"
+    }
+
+    fn intersect(&self, big: &[T], small: &[T]) -> Vec<T> {
+        self.intersect_seq(big, small)
+    }
+
+    // the hasher of `HashSet::new` with fixed keys, so that the result comes
+    // out in the same order every run
+    fn intersect_seq(&self, big: &[T], small: &[T]) -> Vec<T> {
+        let big: HashSet<&T, FixedState> = big.iter().collect();
+        let small: HashSet<&T, FixedState> = small.iter().collect();
+        big.intersection(&small).map(|&x| x.clone()).collect()
+    }
+
+    fn keeps_duplicates(&self) -> bool {
+        false
+    }
+
+    fn honors(&self, _semantics: Semantics) -> bool {
+        true
+    }
+
+    fn intersect_as(&self, semantics: Semantics, big: &[T], small: &[T]) -> Vec<T> {
+        self.intersect_as_seq(semantics, big, small)
+    }
+
+    // sets have no counts, so this walks maps the way `intersection` walks sets
+    fn intersect_as_seq(&self, semantics: Semantics, big: &[T], small: &[T]) -> Vec<T> {
+        if semantics == Semantics::Set {
+            return self.intersect_seq(big, small);
+        }
+        let big = occurrences::<T, FixedState>(big);
+        let small = occurrences::<T, FixedState>(small);
+        big.iter()
+            .flat_map(|(&x, &in_big)| {
+                let in_small = small.get(x).copied().unwrap_or(0);
+                repeat_n(x.clone(), semantics.multiplicity(in_big, in_small))
+            })
+            .collect()
+    }
+}
+
+impl<T: Element> Intersect<T> for Retain {
+    fn description(&self) -> &str {
+        "copies big, calls Vec::retain with a HashSet of small on a single thread"
+    }
+
+    fn complexity(&self) -> &str {
+        "O(n + m) expected"
+    }
+
+    fn intersect(&self, big: &[T], small: &[T]) -> Vec<T> {
+        self.intersect_seq(big, small)
+    }
+
+    fn intersect_seq(&self, big: &[T], small: &[T]) -> Vec<T> {
+        let small: HashSet<&T> = small.iter().collect();
+        let mut result = big.to_vec();
+        result.retain(|x| small.contains(x));
+        result
+    }
+
+    fn honors(&self, _semantics: Semantics) -> bool {
+        true
+    }
+
+    fn intersect_as(&self, semantics: Semantics, big: &[T], small: &[T]) -> Vec<T> {
+        self.intersect_as_seq(semantics, big, small)
+    }
+
+    fn intersect_as_seq(&self, semantics: Semantics, big: &[T], small: &[T]) -> Vec<T> {
+        let mut counts = occurrences::<T, RandomState>(small);
+        let mut result = big.to_vec();
+        match semantics {
+            // removing what was found keeps the next equal element out
+            Semantics::Set => result.retain(|x| counts.remove(x).is_some()),
+            Semantics::MultisetBig => result.retain(|x| counts.contains_key(x)),
+            Semantics::Pairs => {
+                result.retain(|x| counts.contains_key(x));
+                return result
+                    .iter()
+                    .flat_map(|x| repeat_n(x.clone(), counts[x]))
+                    .collect();
+            }
+        }
+        result
+    }
+}
+
+impl Simd {
+    /// Whether the CPU supports the instructions the block comparison is compiled for.
+    fn supported() -> bool {
+        #[cfg(target_arch = "x86_64")]
+        return is_x86_feature_detected!("avx2");
+        #[cfg(target_arch = "aarch64")]
+        return true;
+        #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+        return false;
+    }
+
+    fn run(big: &[usize], small: &[usize], emit: impl FnMut(usize)) {
+        #[cfg(target_arch = "x86_64")]
+        if is_x86_feature_detected!("avx2") {
+            // SAFETY: the CPU supports avx2, which was just checked.
+            return unsafe { Self::blocks_avx2(big, small, emit) };
+        }
+        Self::blocks(big, small, emit)
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[target_feature(enable = "avx2")]
+    unsafe fn blocks_avx2(big: &[usize], small: &[usize], emit: impl FnMut(usize)) {
+        Self::blocks(big, small, emit)
+    }
+
+    /// Intersects two sorted slices by comparing every element of a block of `big`
+    /// with every element of a block of `small` at once, then finishes the tail
+    /// with scalar binary searches. Calls `emit` for every element found.
+    #[inline(always)]
+    fn blocks(big: &[usize], small: &[usize], mut emit: impl FnMut(usize)) {
+        let (mut i, mut j) = (0, 0);
+        let mut matched = [false; LANES];
+        while i + LANES <= big.len() && j + LANES <= small.len() {
+            let a = &big[i..i + LANES];
+            let b = &small[j..j + LANES];
+            for (lane, x) in a.iter().enumerate() {
+                matched[lane] |= b.iter().fold(false, |found, y| found | (x == y));
+            }
+            if a[LANES - 1] <= b[LANES - 1] {
+                a.iter()
+                    .zip(matched)
+                    .filter(|(_, m)| *m)
+                    .for_each(|(x, _)| emit(*x));
+                matched = [false; LANES];
+                i += LANES;
+            } else {
+                j += LANES;
+            }
+        }
+        let tail = &small[j..];
+        big[i..]
+            .iter()
+            .enumerate()
+            .filter(|(k, x)| matched.get(*k) == Some(&true) || tail.binary_search(x).is_ok())
+            .for_each(|(_, x)| emit(*x));
+    }
+
+    /// Calls `emit` for every element [`Simd::run`] finds in the sorted
+    /// arrays, with how often it is in the intersection with `semantics`.
+    fn run_as(
+        semantics: Semantics,
+        big: &[usize],
+        small: &[usize],
+        mut emit: impl FnMut(usize, usize),
+    ) {
+        // the elements are found in ascending order, so equal ones follow
+        // each other
+        let mut last = None;
+        Self::run(big, small, |x| {
+            let copies = match semantics {
+                Semantics::Set => usize::from(last != Some(x)),
+                Semantics::MultisetBig => 1,
+                Semantics::Pairs => {
+                    let start = small.partition_point(|&y| y < x);
+                    small[start..].partition_point(|&y| y == x)
+                }
+            };
+            last = Some(x);
+            emit(x, copies);
+        })
+    }
+}
+
+impl Intersect for Simd {
+    fn description(&self) -> &str {
+        "sorts both arrays, compares them in blocks the compiler vectorizes"
+    }
+
+    fn complexity(&self) -> &str {
+        "O(n log n + m log m)"
+    }
+
+    fn intersect(&self, big: &[usize], small: &[usize]) -> Vec<usize> {
+        let (big, small) = sorted_copies(big, small, true);
+        let mut result = Vec::new();
+        Self::run(&big, &small, |x| result.push(x));
+        result
+    }
+
+    fn intersect_seq(&self, big: &[usize], small: &[usize]) -> Vec<usize> {
+        let (big, small) = sorted_copies(big, small, false);
+        let mut result = Vec::new();
+        Self::run(&big, &small, |x| result.push(x));
+        result
+    }
+
+    fn intersect_sorted(&self, big: &[usize], small: &[usize]) -> Vec<usize> {
+        let mut result = Vec::new();
+        Self::run(big, small, |x| result.push(x));
+        result
+    }
+
+    fn intersect_sorted_seq(&self, big: &[usize], small: &[usize]) -> Vec<usize> {
+        self.intersect_sorted(big, small)
+    }
+
+    fn intersect_count(&self, big: &[usize], small: &[usize]) -> usize {
+        let (big, small) = sorted_copies(big, small, true);
+        let mut count = 0;
+        Self::run(&big, &small, |_| count += 1);
+        count
+    }
+
+    fn intersect_count_seq(&self, big: &[usize], small: &[usize]) -> usize {
+        let (big, small) = sorted_copies(big, small, false);
+        let mut count = 0;
+        Self::run(&big, &small, |_| count += 1);
+        count
+    }
+
+    fn honors(&self, _semantics: Semantics) -> bool {
+        true
+    }
+
+    fn intersect_as(&self, semantics: Semantics, big: &[usize], small: &[usize]) -> Vec<usize> {
+        let (big, small) = sorted_copies(big, small, true);
+        let mut result = Vec::new();
+        Self::run_as(semantics, &big, &small, |x, copies| {
+            result.extend(repeat_n(x, copies))
+        });
+        result
+    }
+
+    fn intersect_as_seq(&self, semantics: Semantics, big: &[usize], small: &[usize]) -> Vec<usize> {
+        let (big, small) = sorted_copies(big, small, false);
+        let mut result = Vec::new();
+        Self::run_as(semantics, &big, &small, |x, copies| {
+            result.extend(repeat_n(x, copies))
+        });
+        result
+    }
+
+    fn intersect_count_as(&self, semantics: Semantics, big: &[usize], small: &[usize]) -> usize {
+        let (big, small) = sorted_copies(big, small, true);
+        let mut count = 0;
+        Self::run_as(semantics, &big, &small, |_, copies| count += copies);
+        count
+    }
+
+    fn intersect_count_as_seq(
+        &self,
+        semantics: Semantics,
+        big: &[usize],
+        small: &[usize],
+    ) -> usize {
+        let (big, small) = sorted_copies(big, small, false);
+        let mut count = 0;
+        Self::run_as(semantics, &big, &small, |_, copies| count += copies);
+        count
+    }
+
+    fn skip_reason(&self, _big: &[usize], _small: &[usize]) -> Option<String> {
+        (!Self::supported()).then(|| "no SIMD support detected on this CPU".to_string())
+    }
+}
+
+/// How often the values `small` holds more than once occur in it, found with
+/// `insert`, which adds a value to a structure and tells whether it is new.
+fn repeats(small: &[usize], mut insert: impl FnMut(usize) -> bool) -> HashMap<usize, usize> {
+    let mut repeats = HashMap::new();
+    for &x in small {
+        if !insert(x) {
+            *repeats.entry(x).or_insert(1) += 1;
+        }
+    }
+    repeats
+}
+
+/// How many pairs `x` forms with the values of a structure, given whether it
+/// contains `x` and the [`repeats`] of the values it was built from.
+fn pairs(found: bool, repeats: &HashMap<usize, usize>, x: usize) -> usize {
+    match found {
+        true => repeats.get(&x).copied().unwrap_or(1),
+        false => 0,
+    }
+}
+
+#[cfg(feature = "roaring")]
+impl Roaring {
+    /// The intersection with `semantics` of `big` and a bitmap of `small`, in
+    /// parallel if requested.
+    ///
+    /// For sets, a flag for every value of the bitmap, found by its rank, is
+    /// set by the first element of `big` to find it, so that no other one
+    /// counts.
+    fn bitmap_as<R: Gather<usize>>(
+        semantics: Semantics,
+        big: &[usize],
+        small: &[usize],
+        parallel: bool,
+    ) -> R {
+        let collect = || -> RoaringTreemap { small.iter().map(|&x| x as u64).collect() };
+        match semantics {
+            Semantics::Set => {
+                let bitmap = collect();
+                let taken: Vec<AtomicBool> =
+                    (0..bitmap.len()).map(|_| AtomicBool::new(false)).collect();
+                let first =
+                    |x: usize| !taken[bitmap.rank(x as u64) as usize - 1].swap(true, Relaxed);
+                let copies = |&x: &usize| usize::from(bitmap.contains(x as u64) && first(x));
+                R::gather(big, copies, parallel)
+            }
+            Semantics::MultisetBig => {
+                let bitmap = collect();
+                R::gather(big, |&x| usize::from(bitmap.contains(x as u64)), parallel)
+            }
+            Semantics::Pairs => {
+                let mut bitmap = RoaringTreemap::new();
+                let repeats = repeats(small, |x| bitmap.insert(x as u64));
+                let copies = |&x: &usize| pairs(bitmap.contains(x as u64), &repeats, x);
+                R::gather(big, copies, parallel)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "roaring")]
+impl Intersect for Roaring {
+    fn description(&self) -> &str {
+        "builds a compressed Roaring bitmap from small, looks every element of big up in it"
+    }
+
+    fn complexity(&self) -> &str {
+        "O(n + m log m)"
+    }
+
+    fn intersect(&self, big: &[usize], small: &[usize]) -> Vec<usize> {
+        let small: RoaringTreemap = small.iter().map(|&x| x as u64).collect();
+        big.par_iter()
+            .filter(|&&x| small.contains(x as u64))
+            .copied()
+            .collect()
+    }
+
+    fn intersect_seq(&self, big: &[usize], small: &[usize]) -> Vec<usize> {
+        let small: RoaringTreemap = small.iter().map(|&x| x as u64).collect();
+        big.iter()
+            .filter(|&&x| small.contains(x as u64))
+            .copied()
+            .collect()
+    }
+
+    fn prepare<'a>(&self, small: &'a [usize]) -> Option<Box<dyn Prepared + 'a>> {
+        let small: RoaringTreemap = small.iter().map(|&x| x as u64).collect();
+        lookup(small, |set, &x| set.contains(x as u64))
+    }
+
+    fn intersect_count(&self, big: &[usize], small: &[usize]) -> usize {
+        let small: RoaringTreemap = small.iter().map(|&x| x as u64).collect();
+        big.par_iter()
+            .filter(|&&x| small.contains(x as u64))
+            .count()
+    }
+
+    fn intersect_count_seq(&self, big: &[usize], small: &[usize]) -> usize {
+        let small: RoaringTreemap = small.iter().map(|&x| x as u64).collect();
+        big.iter().filter(|&&x| small.contains(x as u64)).count()
+    }
+
+    fn intersect_iter<'a>(
+        &self,
+        big: &'a [usize],
+        small: &'a [usize],
+    ) -> Box<dyn Iterator<Item = usize> + 'a> {
+        let small: RoaringTreemap = small.iter().map(|&x| x as u64).collect();
+        Box::new(
+            big.iter()
+                .filter(move |&&x| small.contains(x as u64))
+                .copied(),
+        )
+    }
+
+    fn honors(&self, _semantics: Semantics) -> bool {
+        true
+    }
+
+    fn intersect_as(&self, semantics: Semantics, big: &[usize], small: &[usize]) -> Vec<usize> {
+        Self::bitmap_as(semantics, big, small, true)
+    }
+
+    fn intersect_as_seq(&self, semantics: Semantics, big: &[usize], small: &[usize]) -> Vec<usize> {
+        Self::bitmap_as(semantics, big, small, false)
+    }
+
+    fn intersect_count_as(&self, semantics: Semantics, big: &[usize], small: &[usize]) -> usize {
+        Self::bitmap_as(semantics, big, small, true)
+    }
+
+    fn intersect_count_as_seq(
+        &self,
+        semantics: Semantics,
+        big: &[usize],
+        small: &[usize],
+    ) -> usize {
+        Self::bitmap_as(semantics, big, small, false)
+    }
+}
+
+impl Bitset {
+    /// Creates a bitset that may use up to `budget_mib` MiB for its bitmap.
+    pub fn new(budget_mib: usize) -> Self {
+        Bitset {
+            budget: budget_mib << 20,
+        }
+    }
+
+    fn words(small: &[usize]) -> usize {
+        small.iter().max().map_or(0, |max| max / 64 + 1)
+    }
+
+    fn build(small: &[usize]) -> Vec<u64> {
+        let mut bits = vec![0u64; Self::words(small)];
+        small.iter().for_each(|&x| bits[x / 64] |= 1 << (x % 64));
+        bits
+    }
+
+    fn contains(bits: &[u64], x: usize) -> bool {
+        bits.get(x / 64)
+            .is_some_and(|word| word >> (x % 64) & 1 == 1)
+    }
+
+    /// The intersection with `semantics` of `big` and a bitmap of `small`, in
+    /// parallel if requested.
+    ///
+    /// For sets, the first element of `big` to find its bit clears it, so
+    /// that no other one counts.
+    fn bitmap_as<R: Gather<usize>>(
+        semantics: Semantics,
+        big: &[usize],
+        small: &[usize],
+        parallel: bool,
+    ) -> R {
+        match semantics {
+            Semantics::Set => {
+                let bits: Vec<AtomicU64> =
+                    Self::build(small).into_iter().map(AtomicU64::new).collect();
+                let take = |x: usize| {
+                    bits.get(x / 64).is_some_and(|word| {
+                        word.fetch_and(!(1 << (x % 64)), Relaxed) >> (x % 64) & 1 == 1
+                    })
+                };
+                R::gather(big, |&x| usize::from(take(x)), parallel)
+            }
+            Semantics::MultisetBig => {
+                let bits = Self::build(small);
+                R::gather(big, |&x| usize::from(Self::contains(&bits, x)), parallel)
+            }
+            Semantics::Pairs => {
+                let mut bits = vec![0u64; Self::words(small)];
+                let repeats = repeats(small, |x| {
+                    let new = !Self::contains(&bits, x);
+                    bits[x / 64] |= 1 << (x % 64);
+                    new
+                });
+                R::gather(
+                    big,
+                    |&x| pairs(Self::contains(&bits, x), &repeats, x),
+                    parallel,
+                )
+            }
+        }
+    }
+}
+
+impl Intersect for Bitset {
+    fn description(&self) -> &str {
+        "builds a bitmap indexed by value from small, tests the bit of every element of big"
+    }
+
+    fn complexity(&self) -> &str {
+        "O(n + m + max)"
+    }
+
+    fn intersect(&self, big: &[usize], small: &[usize]) -> Vec<usize> {
+        let bits = Self::build(small);
+        big.par_iter()
+            .filter(|&&x| Self::contains(&bits, x))
+            .copied()
+            .collect()
+    }
+
+    fn intersect_seq(&self, big: &[usize], small: &[usize]) -> Vec<usize> {
+        let bits = Self::build(small);
+        big.iter()
+            .filter(|&&x| Self::contains(&bits, x))
+            .copied()
+            .collect()
+    }
+
+    fn prepare<'a>(&self, small: &'a [usize]) -> Option<Box<dyn Prepared + 'a>> {
+        lookup(Self::build(small), |bits, &x| Self::contains(bits, x))
+    }
+
+    fn intersect_count(&self, big: &[usize], small: &[usize]) -> usize {
+        let bits = Self::build(small);
+        big.par_iter()
+            .filter(|&&x| Self::contains(&bits, x))
+            .count()
+    }
+
+    fn intersect_count_seq(&self, big: &[usize], small: &[usize]) -> usize {
+        let bits = Self::build(small);
+        big.iter().filter(|&&x| Self::contains(&bits, x)).count()
+    }
+
+    fn intersect_iter<'a>(
+        &self,
+        big: &'a [usize],
+        small: &'a [usize],
+    ) -> Box<dyn Iterator<Item = usize> + 'a> {
+        let bits = Self::build(small);
+        Box::new(
+            big.iter()
+                .filter(move |&&x| Self::contains(&bits, x))
+                .copied(),
+        )
+    }
+
+    fn honors(&self, _semantics: Semantics) -> bool {
+        true
+    }
+
+    fn intersect_as(&self, semantics: Semantics, big: &[usize], small: &[usize]) -> Vec<usize> {
+        Self::bitmap_as(semantics, big, small, true)
+    }
+
+    fn intersect_as_seq(&self, semantics: Semantics, big: &[usize], small: &[usize]) -> Vec<usize> {
+        Self::bitmap_as(semantics, big, small, false)
+    }
+
+    fn intersect_count_as(&self, semantics: Semantics, big: &[usize], small: &[usize]) -> usize {
+        Self::bitmap_as(semantics, big, small, true)
+    }
+
+    fn intersect_count_as_seq(
+        &self,
+        semantics: Semantics,
+        big: &[usize],
+        small: &[usize],
+    ) -> usize {
+        Self::bitmap_as(semantics, big, small, false)
+    }
+
+    fn skip_reason(&self, _big: &[usize], small: &[usize]) -> Option<String> {
+        let bytes = Self::words(small).saturating_mul(8);
+        (bytes > self.budget).then(|| {
+            format!(
+                "bitmap needs {} MiB, budget is {} MiB",
+                bytes >> 20,
+                self.budget >> 20
+            )
+        })
+    }
+}
+
+impl DirectIndex {
+    /// Creates a presence array that may use up to `budget_mib` MiB.
+    pub fn new(budget_mib: usize) -> Self {
+        DirectIndex {
+            budget: budget_mib << 20,
+        }
+    }
+
+    fn len(small: &[usize]) -> usize {
+        small.iter().max().map_or(0, |max| max.saturating_add(1))
+    }
+
+    fn build(small: &[usize]) -> Vec<bool> {
+        let mut present = vec![false; Self::len(small)];
+        small.iter().for_each(|&x| present[x] = true);
+        present
+    }
+
+    fn contains(present: &[bool], x: usize) -> bool {
+        present.get(x).copied().unwrap_or(false)
+    }
+
+    /// The intersection with `semantics` of `big` and a presence array of
+    /// `small`, in parallel if requested.
+    ///
+    /// For sets, the first element of `big` to find its boolean clears it, so
+    /// that no other one counts.
+    fn indexed_as<R: Gather<usize>>(
+        semantics: Semantics,
+        big: &[usize],
+        small: &[usize],
+        parallel: bool,
+    ) -> R {
+        match semantics {
+            Semantics::Set => {
+                let present: Vec<AtomicBool> = Self::build(small)
+                    .into_iter()
+                    .map(AtomicBool::new)
+                    .collect();
+                let take = |x: usize| present.get(x).is_some_and(|p| p.swap(false, Relaxed));
+                R::gather(big, |&x| usize::from(take(x)), parallel)
+            }
+            Semantics::MultisetBig => {
+                let present = Self::build(small);
+                R::gather(big, |&x| usize::from(Self::contains(&present, x)), parallel)
+            }
+            Semantics::Pairs => {
+                let mut present = vec![false; Self::len(small)];
+                let repeats = repeats(small, |x| !replace(&mut present[x], true));
+                R::gather(
+                    big,
+                    |&x| pairs(Self::contains(&present, x), &repeats, x),
+                    parallel,
+                )
+            }
+        }
+    }
+}
+
+impl Intersect for DirectIndex {
+    fn description(&self) -> &str {
+        "builds an array of booleans indexed by value from small, reads the one of every element of big"
+    }
+
+    fn complexity(&self) -> &str {
+        "O(n + m + max)"
+    }
+
+    fn intersect(&self, big: &[usize], small: &[usize]) -> Vec<usize> {
+        let present = Self::build(small);
+        big.par_iter()
+            .filter(|&&x| Self::contains(&present, x))
+            .copied()
+            .collect()
+    }
+
+    fn intersect_seq(&self, big: &[usize], small: &[usize]) -> Vec<usize> {
+        let present = Self::build(small);
+        big.iter()
+            .filter(|&&x| Self::contains(&present, x))
+            .copied()
+            .collect()
+    }
+
+    fn prepare<'a>(&self, small: &'a [usize]) -> Option<Box<dyn Prepared + 'a>> {
+        lookup(Self::build(small), |present, &x| Self::contains(present, x))
+    }
+
+    fn intersect_count(&self, big: &[usize], small: &[usize]) -> usize {
+        let present = Self::build(small);
+        big.par_iter()
+            .filter(|&&x| Self::contains(&present, x))
+            .count()
+    }
+
+    fn intersect_count_seq(&self, big: &[usize], small: &[usize]) -> usize {
+        let present = Self::build(small);
+        big.iter().filter(|&&x| Self::contains(&present, x)).count()
+    }
+
+    fn intersect_iter<'a>(
+        &self,
+        big: &'a [usize],
+        small: &'a [usize],
+    ) -> Box<dyn Iterator<Item = usize> + 'a> {
+        let present = Self::build(small);
+        Box::new(
+            big.iter()
+                .filter(move |&&x| Self::contains(&present, x))
+                .copied(),
+        )
+    }
+
+    fn honors(&self, _semantics: Semantics) -> bool {
+        true
+    }
+
+    fn intersect_as(&self, semantics: Semantics, big: &[usize], small: &[usize]) -> Vec<usize> {
+        Self::indexed_as(semantics, big, small, true)
+    }
+
+    fn intersect_as_seq(&self, semantics: Semantics, big: &[usize], small: &[usize]) -> Vec<usize> {
+        Self::indexed_as(semantics, big, small, false)
+    }
+
+    fn intersect_count_as(&self, semantics: Semantics, big: &[usize], small: &[usize]) -> usize {
+        Self::indexed_as(semantics, big, small, true)
+    }
+
+    fn intersect_count_as_seq(
+        &self,
+        semantics: Semantics,
+        big: &[usize],
+        small: &[usize],
+    ) -> usize {
+        Self::indexed_as(semantics, big, small, false)
+    }
+
+    fn skip_reason(&self, _big: &[usize], small: &[usize]) -> Option<String> {
+        let bytes = Self::len(small);
+        (bytes > self.budget).then(|| {
+            format!(
+                "presence array needs {} MiB, budget is {} MiB",
+                bytes >> 20,
+                self.budget >> 20
+            )
+        })
+    }
+}
+
+/// A Bloom filter with two hash functions.
+struct BloomFilter {
+    bits: Vec<u64>,
+}
+
+impl BloomFilter {
+    fn new(values: &[usize], false_positive_rate: f64) -> Self {
+        let bits = (-(values.len() as f64) * false_positive_rate.ln() / LN_2.powi(2))
+            .ceil()
+            .max(64.0) as usize;
+        let mut filter = BloomFilter {
+            bits: vec![0u64; bits.div_ceil(64)],
+        };
+        values.iter().for_each(|&x| {
+            let (h1, h2) = filter.positions(x);
+            filter.bits[h1 / 64] |= 1 << (h1 % 64);
+            filter.bits[h2 / 64] |= 1 << (h2 % 64);
+        });
+        filter
+    }
+
+    fn positions(&self, value: usize) -> (usize, usize) {
+        let mut x = (value as u64).wrapping_add(0x9e3779b97f4a7c15);
+        x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+        x ^= x >> 31;
+        let len = self.bits.len() * 64;
+        ((x >> 32) as usize % len, x as u32 as usize % len)
+    }
+
+    fn contains(&self, value: usize) -> bool {
+        let (h1, h2) = self.positions(value);
+        self.bits[h1 / 64] >> (h1 % 64) & 1 == 1 && self.bits[h2 / 64] >> (h2 % 64) & 1 == 1
+    }
+}
+
+impl BloomHash {
+    /// Creates a filter sized for the given false positive rate.
+    ///
+    /// # Panics
+    ///
+    /// If the rate isn't strictly between 0 and 1, where the filter would
+    /// need infinitely many bits or none.
+    pub fn new(false_positive_rate: f64) -> Self {
+        assert!(
+            false_positive_rate > 0.0 && false_positive_rate < 1.0,
+            "the false positive rate must be between 0 and 1, not {}",
+            false_positive_rate
+        );
+        BloomHash {
+            false_positive_rate,
+        }
+    }
+
+    /// The intersection with `semantics` of `big` and a set of `small`, which
+    /// only the elements that pass the filter are looked up in, in parallel if
+    /// requested.
+    ///
+    /// For sets, the set holds a flag for every element, which the first
+    /// element of `big` to find it sets, so that no other one counts.
+    fn filtered_as<R: Gather<usize>>(
+        &self,
+        semantics: Semantics,
+        big: &[usize],
+        small: &[usize],
+        parallel: bool,
+    ) -> R {
+        let filter = BloomFilter::new(small, self.false_positive_rate);
+        match semantics {
+            Semantics::Set => {
+                let taken: HashMap<usize, AtomicBool> =
+                    small.iter().map(|&x| (x, AtomicBool::new(false))).collect();
+                let first = |x: usize| taken.get(&x).is_some_and(|t| !t.swap(true, Relaxed));
+                R::gather(
+                    big,
+                    |&x| usize::from(filter.contains(x) && first(x)),
+                    parallel,
+                )
+            }
+            Semantics::MultisetBig => {
+                let small: HashSet<usize> = small.iter().copied().collect();
+                let found = |x: usize| filter.contains(x) && small.contains(&x);
+                R::gather(big, |&x| usize::from(found(x)), parallel)
+            }
+            Semantics::Pairs => {
+                let counts = occurrences::<usize, RandomState>(small);
+                let found = |x: usize| match filter.contains(x) {
+                    true => counts.get(&x).copied().unwrap_or(0),
+                    false => 0,
+                };
+                R::gather(big, |&x| found(x), parallel)
+            }
+        }
+    }
+}
+
+impl Intersect for BloomHash {
+    fn name(&self) -> String {
+        format!("BloomHash({})", self.false_positive_rate)
+    }
+
+    fn description(&self) -> &str {
+        "filters big with a Bloom filter of small, looks the rest up in a HashSet of it"
+    }
+
+    fn complexity(&self) -> &str {
+        "O(n + m) expected"
+    }
+
+    fn intersect(&self, big: &[usize], small: &[usize]) -> Vec<usize> {
+        let filter = BloomFilter::new(small, self.false_positive_rate);
+        let small: HashSet<usize> = small.iter().copied().collect();
+        big.par_iter()
+            .filter(|&&x| filter.contains(x) && small.contains(&x))
+            .copied()
+            .collect()
+    }
+
+    fn intersect_seq(&self, big: &[usize], small: &[usize]) -> Vec<usize> {
+        let filter = BloomFilter::new(small, self.false_positive_rate);
+        let small: HashSet<usize> = small.iter().copied().collect();
+        big.iter()
+            .filter(|&&x| filter.contains(x) && small.contains(&x))
+            .copied()
+            .collect()
+    }
+
+    fn prepare<'a>(&self, small: &'a [usize]) -> Option<Box<dyn Prepared + 'a>> {
+        let filter = BloomFilter::new(small, self.false_positive_rate);
+        let set: HashSet<usize> = small.iter().copied().collect();
+        lookup((filter, set), |(filter, set), &x| {
+            filter.contains(x) && set.contains(&x)
+        })
+    }
+
+    fn intersect_count(&self, big: &[usize], small: &[usize]) -> usize {
+        let filter = BloomFilter::new(small, self.false_positive_rate);
+        let small: HashSet<usize> = small.iter().copied().collect();
+        big.par_iter()
+            .filter(|&&x| filter.contains(x) && small.contains(&x))
+            .count()
+    }
+
+    fn intersect_count_seq(&self, big: &[usize], small: &[usize]) -> usize {
+        let filter = BloomFilter::new(small, self.false_positive_rate);
+        let small: HashSet<usize> = small.iter().copied().collect();
+        big.iter()
+            .filter(|&&x| filter.contains(x) && small.contains(&x))
+            .count()
+    }
+
+    fn intersect_iter<'a>(
+        &self,
+        big: &'a [usize],
+        small: &'a [usize],
+    ) -> Box<dyn Iterator<Item = usize> + 'a> {
+        let filter = BloomFilter::new(small, self.false_positive_rate);
+        let small: HashSet<usize> = small.iter().copied().collect();
+        Box::new(
+            big.iter()
+                .filter(move |&&x| filter.contains(x) && small.contains(&x))
+                .copied(),
+        )
+    }
+
+    fn honors(&self, _semantics: Semantics) -> bool {
+        true
+    }
+
+    fn intersect_as(&self, semantics: Semantics, big: &[usize], small: &[usize]) -> Vec<usize> {
+        self.filtered_as(semantics, big, small, true)
+    }
+
+    fn intersect_as_seq(&self, semantics: Semantics, big: &[usize], small: &[usize]) -> Vec<usize> {
+        self.filtered_as(semantics, big, small, false)
+    }
+
+    fn intersect_count_as(&self, semantics: Semantics, big: &[usize], small: &[usize]) -> usize {
+        self.filtered_as(semantics, big, small, true)
+    }
+
+    fn intersect_count_as_seq(
+        &self,
+        semantics: Semantics,
+        big: &[usize],
+        small: &[usize],
+    ) -> usize {
+        self.filtered_as(semantics, big, small, false)
+    }
+}
@@ -0,0 +1,350 @@
+//! Set operations besides intersection, for the methods that support them,
+//! and how the intersection treats duplicates.
+//!
+//! Every operation besides intersection treats its inputs as sets, so each
+//! distinct element of the result occurs exactly once.
+
+use std::collections::{BTreeSet, HashSet};
+
+use clap::ValueEnum;
+use rayon::{
+    iter::{IndexedParallelIterator, IntoParallelRefIterator, ParallelIterator},
+    slice::ParallelSliceMut,
+};
+
+use crate::{BTree, Binary, Element, Hash, Squared, SquaredBreak};
+
+/// The set operation being benchmarked.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum Op {
+    /// The elements of `big` that occur in `small`.
+    Intersection,
+    /// Every distinct element of either array.
+    Union,
+    /// The distinct elements of `big` that don't occur in `small`.
+    Difference,
+    /// The distinct elements that occur in exactly one of the arrays.
+    SymmetricDifference,
+}
+
+impl Op {
+    /// The name used on the command line and in messages.
+    pub fn name(self) -> &'static str {
+        match self {
+            Op::Intersection => "intersection",
+            Op::Union => "union",
+            Op::Difference => "difference",
+            Op::SymmetricDifference => "symmetric difference",
+        }
+    }
+
+    /// Whether switching the arrays computes the same set.
+    pub fn is_symmetric(self) -> bool {
+        self != Op::Difference
+    }
+
+    /// What is computed, including which array is subtracted from which.
+    pub fn description(self) -> &'static str {
+        match self {
+            Op::Difference => {
+                "difference (bigger minus smaller array, smaller minus bigger in switched order)"
+            }
+            op => op.name(),
+        }
+    }
+}
+
+/// How often an element occurs in the intersection of arrays with
+/// duplicates.
+///
+/// Methods compute the semantics they [honor](crate::Intersect::honors) in
+/// [`intersect_as`](crate::Intersect::intersect_as) while they are timed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum Semantics {
+    /// Every distinct element that occurs in both arrays, once.
+    Set,
+    /// Every element of `big` that occurs in `small`, as often as it occurs
+    /// in `big`.
+    #[default]
+    MultisetBig,
+    /// An element for every pair of equal elements of `big` and `small`.
+    Pairs,
+}
+
+impl Semantics {
+    /// The name used on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            Semantics::Set => "set",
+            Semantics::MultisetBig => "multiset-big",
+            Semantics::Pairs => "pairs",
+        }
+    }
+
+    /// What the intersection contains, for the run header.
+    pub fn description(self) -> &'static str {
+        match self {
+            Semantics::Set => "every distinct element once",
+            Semantics::MultisetBig => "an element per occurrence in the first array",
+            Semantics::Pairs => "an element per pair of equal elements",
+        }
+    }
+
+    /// How often an element that occurs `in_big` times in `big` and
+    /// `in_small` times in `small` is in the intersection.
+    pub fn multiplicity(self, in_big: usize, in_small: usize) -> usize {
+        match self {
+            _ if in_big == 0 || in_small == 0 => 0,
+            Semantics::Set => 1,
+            Semantics::MultisetBig => in_big,
+            Semantics::Pairs => in_big * in_small,
+        }
+    }
+}
+
+impl Squared {
+    /// Keeps every element that isn't equal to any element before it.
+    pub(crate) fn union<T: Element>(big: &[T], small: &[T], parallel: bool) -> Vec<T> {
+        let all: Vec<&T> = big.iter().chain(small).collect();
+        let first = |i: usize, x: &T| all[..i].iter().filter(|y| **y == x).count() == 0;
+        if parallel {
+            all.par_iter()
+                .enumerate()
+                .filter(|(i, x)| first(*i, x))
+                .map(|(_, x)| (*x).clone())
+                .collect()
+        } else {
+            all.iter()
+                .enumerate()
+                .filter(|(i, x)| first(*i, x))
+                .map(|(_, x)| (*x).clone())
+                .collect()
+        }
+    }
+
+    /// Keeps every element of `big` that isn't equal to any element before it
+    /// or to any element of `small`.
+    pub(crate) fn difference<T: Element>(big: &[T], small: &[T], parallel: bool) -> Vec<T> {
+        let count = |values: &[T], x: &T| values.iter().filter(|y| *y == x).count();
+        let keep = |i: usize, x: &T| count(&big[..i], x) == 0 && count(small, x) == 0;
+        if parallel {
+            big.par_iter()
+                .enumerate()
+                .filter(|(i, x)| keep(*i, x))
+                .map(|(_, x)| x.clone())
+                .collect()
+        } else {
+            big.iter()
+                .enumerate()
+                .filter(|(i, x)| keep(*i, x))
+                .map(|(_, x)| x.clone())
+                .collect()
+        }
+    }
+
+    pub(crate) fn symmetric_difference<T: Element>(
+        big: &[T],
+        small: &[T],
+        parallel: bool,
+    ) -> Vec<T> {
+        let mut result = Self::difference(big, small, parallel);
+        result.extend(Self::difference(small, big, parallel));
+        result
+    }
+}
+
+impl SquaredBreak {
+    /// Keeps every element that doesn't occur before it, stopping at the first
+    /// earlier occurrence.
+    pub(crate) fn union<T: Element>(big: &[T], small: &[T], parallel: bool) -> Vec<T> {
+        let all: Vec<&T> = big.iter().chain(small).collect();
+        if parallel {
+            all.par_iter()
+                .enumerate()
+                .filter(|(i, x)| !all[..*i].contains(x))
+                .map(|(_, x)| (*x).clone())
+                .collect()
+        } else {
+            all.iter()
+                .enumerate()
+                .filter(|(i, x)| !all[..*i].contains(x))
+                .map(|(_, x)| (*x).clone())
+                .collect()
+        }
+    }
+
+    /// Keeps every element of `big` that doesn't occur before it or in
+    /// `small`. Unlike intersection, an element that isn't in `small` can
+    /// only be kept after comparing it with all of `small`.
+    pub(crate) fn difference<T: Element>(big: &[T], small: &[T], parallel: bool) -> Vec<T> {
+        let keep = |i: usize, x: &T| !big[..i].contains(x) && !small.contains(x);
+        if parallel {
+            big.par_iter()
+                .enumerate()
+                .filter(|(i, x)| keep(*i, x))
+                .map(|(_, x)| x.clone())
+                .collect()
+        } else {
+            big.iter()
+                .enumerate()
+                .filter(|(i, x)| keep(*i, x))
+                .map(|(_, x)| x.clone())
+                .collect()
+        }
+    }
+
+    pub(crate) fn symmetric_difference<T: Element>(
+        big: &[T],
+        small: &[T],
+        parallel: bool,
+    ) -> Vec<T> {
+        let mut result = Self::difference(big, small, parallel);
+        result.extend(Self::difference(small, big, parallel));
+        result
+    }
+}
+
+impl BTree {
+    /// The distinct elements of `big` that aren't in `small`.
+    fn missing<'a, T: Element>(
+        big: &'a [T],
+        small: &BTreeSet<&T>,
+        parallel: bool,
+    ) -> BTreeSet<&'a T> {
+        if parallel {
+            big.par_iter().filter(|x| !small.contains(x)).collect()
+        } else {
+            big.iter().filter(|x| !small.contains(x)).collect()
+        }
+    }
+
+    pub(crate) fn union<T: Element>(big: &[T], small: &[T], parallel: bool) -> Vec<T> {
+        let small: BTreeSet<&T> = small.iter().collect();
+        let extra = Self::missing(big, &small, parallel);
+        small.into_iter().chain(extra).cloned().collect()
+    }
+
+    pub(crate) fn difference<T: Element>(big: &[T], small: &[T], parallel: bool) -> Vec<T> {
+        let small: BTreeSet<&T> = small.iter().collect();
+        Self::missing(big, &small, parallel)
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+
+    pub(crate) fn symmetric_difference<T: Element>(
+        big: &[T],
+        small: &[T],
+        parallel: bool,
+    ) -> Vec<T> {
+        let big_set: BTreeSet<&T> = big.iter().collect();
+        let small_set: BTreeSet<&T> = small.iter().collect();
+        let extra = Self::missing(small, &big_set, parallel);
+        Self::missing(big, &small_set, parallel)
+            .into_iter()
+            .chain(extra)
+            .cloned()
+            .collect()
+    }
+}
+
+impl Binary {
+    /// The distinct elements of `big` that aren't in the sorted `small`, sorted.
+    fn missing<'a, T: Element>(big: &'a [T], small: &[&T], parallel: bool) -> Vec<&'a T> {
+        let mut missing: Vec<&T> = if parallel {
+            big.par_iter()
+                .filter(|x| small.binary_search(x).is_err())
+                .collect()
+        } else {
+            big.iter()
+                .filter(|x| small.binary_search(x).is_err())
+                .collect()
+        };
+        if parallel {
+            missing.par_sort_unstable();
+        } else {
+            missing.sort_unstable();
+        }
+        missing.dedup();
+        missing
+    }
+
+    /// Sorts and deduplicates references to the elements of `small`.
+    fn distinct<T: Element>(small: &[T]) -> Vec<&T> {
+        let mut small: Vec<&T> = small.iter().collect();
+        small.sort_unstable();
+        small.dedup();
+        small
+    }
+
+    pub(crate) fn union<T: Element>(big: &[T], small: &[T], parallel: bool) -> Vec<T> {
+        let small = Self::distinct(small);
+        let extra = Self::missing(big, &small, parallel);
+        small.into_iter().chain(extra).cloned().collect()
+    }
+
+    pub(crate) fn difference<T: Element>(big: &[T], small: &[T], parallel: bool) -> Vec<T> {
+        let small = Self::distinct(small);
+        Self::missing(big, &small, parallel)
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+
+    pub(crate) fn symmetric_difference<T: Element>(
+        big: &[T],
+        small: &[T],
+        parallel: bool,
+    ) -> Vec<T> {
+        let extra = Self::missing(small, &Self::distinct(big), parallel);
+        Self::missing(big, &Self::distinct(small), parallel)
+            .into_iter()
+            .chain(extra)
+            .cloned()
+            .collect()
+    }
+}
+
+impl Hash {
+    /// The distinct elements of `big` that aren't in `small`.
+    fn missing<'a, T: Element>(
+        big: &'a [T],
+        small: &HashSet<&T>,
+        parallel: bool,
+    ) -> HashSet<&'a T> {
+        if parallel {
+            big.par_iter().filter(|x| !small.contains(x)).collect()
+        } else {
+            big.iter().filter(|x| !small.contains(x)).collect()
+        }
+    }
+
+    pub(crate) fn union<T: Element>(big: &[T], small: &[T], parallel: bool) -> Vec<T> {
+        let small: HashSet<&T> = small.iter().collect();
+        let extra = Self::missing(big, &small, parallel);
+        small.into_iter().chain(extra).cloned().collect()
+    }
+
+    pub(crate) fn difference<T: Element>(big: &[T], small: &[T], parallel: bool) -> Vec<T> {
+        let small: HashSet<&T> = small.iter().collect();
+        Self::missing(big, &small, parallel)
+            .into_iter()
+            .cloned()
+            .collect()
+    }
+
+    pub(crate) fn symmetric_difference<T: Element>(
+        big: &[T],
+        small: &[T],
+        parallel: bool,
+    ) -> Vec<T> {
+        let big_set: HashSet<&T> = big.iter().collect();
+        let small_set: HashSet<&T> = small.iter().collect();
+        let extra = Self::missing(small, &big_set, parallel);
+        Self::missing(big, &small_set, parallel)
+            .into_iter()
+            .chain(extra)
+            .cloned()
+            .collect()
+    }
+}
@@ -0,0 +1,51 @@
+//! Printing messages to stdout at the verbosity the user asked for.
+
+use std::fmt::Display;
+
+/// How much is printed besides the results.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Verbosity {
+    /// Only one line per method, for scripts.
+    Quiet,
+    /// The data generation, the results and whether they are equal.
+    Normal,
+    /// Everything, including the samples of every method.
+    Verbose,
+}
+
+/// Prints lines to stdout that are only shown at some verbosities.
+#[derive(Clone, Copy)]
+pub struct Output {
+    verbosity: Verbosity,
+}
+
+impl Output {
+    /// Prints the lines shown at `verbosity`.
+    pub fn new(verbosity: Verbosity) -> Self {
+        Output { verbosity }
+    }
+
+    /// The verbosity the lines are printed at.
+    pub fn verbosity(&self) -> Verbosity {
+        self.verbosity
+    }
+
+    /// Whether lines of `verbosity` are shown.
+    pub fn shows(&self, verbosity: Verbosity) -> bool {
+        self.verbosity >= verbosity
+    }
+
+    /// Prints a line shown at the default verbosity and above.
+    pub fn normal(&self, line: impl Display) {
+        if self.shows(Verbosity::Normal) {
+            println!("{}", line);
+        }
+    }
+
+    /// Prints a line only shown when asked for more detail.
+    pub fn verbose(&self, line: impl Display) {
+        if self.shows(Verbosity::Verbose) {
+            println!("{}", line);
+        }
+    }
+}
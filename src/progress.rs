@@ -0,0 +1,98 @@
+//! Showing which method is running on a line that is rewritten in place.
+
+use std::{
+    io::{self, Write},
+    sync::{
+        atomic::{AtomicUsize, Ordering::Relaxed},
+        Mutex,
+    },
+    time::Instant,
+};
+
+use crossterm::{
+    cursor::MoveToColumn,
+    queue,
+    style::Print,
+    terminal::{Clear, ClearType},
+};
+
+use crate::terminal_width;
+
+/// A status line on stdout, updated as the benchmark runs.
+///
+/// Only create one when stdout is a terminal, the line is drawn with
+/// carriage returns and escape codes.
+pub struct Progress {
+    started: Instant,
+    total: AtomicUsize,
+    done: AtomicUsize,
+    /// Serializes drawing, and holds whether a line is currently shown.
+    shown: Mutex<bool>,
+}
+
+impl Default for Progress {
+    fn default() -> Self {
+        Progress {
+            started: Instant::now(),
+            total: AtomicUsize::new(0),
+            done: AtomicUsize::new(0),
+            shown: Mutex::new(false),
+        }
+    }
+}
+
+impl Progress {
+    /// Creates a status line whose elapsed time starts now.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts counting `total` more runs of a method.
+    pub(crate) fn begin(&self, total: usize) {
+        self.total.store(total, Relaxed);
+        self.done.store(0, Relaxed);
+    }
+
+    /// Shows that `name` is at `stage`, like `run 2 of 5`.
+    pub(crate) fn update(&self, name: &str, stage: &str) {
+        let line = format!(
+            "{}/{} done, running {} ({}), {:.1}s elapsed",
+            self.done.load(Relaxed),
+            self.total.load(Relaxed),
+            name,
+            stage,
+            self.started.elapsed().as_secs_f64()
+        );
+        let line: String = line
+            .chars()
+            .take(terminal_width().saturating_sub(1))
+            .collect();
+        let mut shown = self.shown.lock().unwrap();
+        let mut out = io::stdout();
+        // the status line is best effort, a failed write only loses an update
+        let _ = queue!(
+            out,
+            MoveToColumn(0),
+            Clear(ClearType::CurrentLine),
+            Print(line)
+        )
+        .and_then(|_| out.flush());
+        *shown = true;
+    }
+
+    /// Counts a finished run of a method.
+    pub(crate) fn finish(&self) {
+        self.done.fetch_add(1, Relaxed);
+    }
+
+    /// Removes the status line, so that the next output starts on a clean line.
+    pub fn clear(&self) {
+        let mut shown = self.shown.lock().unwrap();
+        if *shown {
+            let mut out = io::stdout();
+            let _ = queue!(out, MoveToColumn(0), Clear(ClearType::CurrentLine))
+                .and_then(|_| out.flush());
+            *shown = false;
+        }
+    }
+}
@@ -0,0 +1,94 @@
+//! The serialized form of a product, shared by the JSON reports, the
+//! baselines and the history log.
+
+use std::{fmt::Debug, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+use crate::Product;
+
+/// The version of the schema of the JSON reports, baselines and history
+/// records, increased whenever a field is removed or changes its meaning.
+///
+/// Files written before the schema was versioned read as version 0.
+pub const SCHEMA_VERSION: u32 = 1;
+
+/// How many values of the result a [`ProductRecord`] keeps.
+pub const RESULT_SAMPLE_LEN: usize = 8;
+
+/// A product without its samples and with only the first values of its
+/// result, as written to every machine-readable output.
+///
+/// Durations are whole nanoseconds, saturating at `u64::MAX` (about 584
+/// years). Every field but the name and the time defaults when missing, so
+/// baselines written before the other fields existed still read.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
+pub struct ProductRecord {
+    /// The name of the product.
+    pub name: String,
+    /// The aggregated time in nanoseconds, or the limit if it timed out.
+    pub time_ns: u64,
+    /// The number of timed runs.
+    pub runs: usize,
+    /// The mean of the samples in nanoseconds.
+    pub mean_ns: u64,
+    /// The population standard deviation of the samples in nanoseconds.
+    pub std_dev_ns: u64,
+    /// The fastest sample in nanoseconds.
+    pub min_ns: u64,
+    /// The slowest sample in nanoseconds.
+    pub max_ns: u64,
+    /// The number of elements in the result, or the count when only counting.
+    pub result_len: usize,
+    /// The first [`RESULT_SAMPLE_LEN`] values of the result in their `Debug`
+    /// form.
+    pub result_sample: Vec<String>,
+    /// The limit the product ran longer than.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timed_out_ns: Option<u64>,
+    /// Why the product didn't run.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skipped: Option<String>,
+}
+
+/// The nanoseconds of `duration`, saturating at `u64::MAX`.
+pub(crate) fn nanos(duration: Duration) -> u64 {
+    duration.as_nanos().min(u64::MAX as u128) as u64
+}
+
+/// Fails for files of a newer schema than this version understands.
+pub(crate) fn check_schema(version: u32) -> Result<(), String> {
+    if version > SCHEMA_VERSION {
+        Err(format!(
+            "schema version {} is newer than the supported version {}",
+            version, SCHEMA_VERSION
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+impl<T: Debug> From<&Product<T>> for ProductRecord {
+    fn from(product: &Product<T>) -> Self {
+        let ran = !product.samples.is_empty();
+        ProductRecord {
+            name: product.name.clone(),
+            time_ns: nanos(product.time),
+            runs: product.samples.len(),
+            mean_ns: if ran { nanos(product.mean()) } else { 0 },
+            std_dev_ns: if ran { nanos(product.std_dev()) } else { 0 },
+            min_ns: product.samples.iter().min().map_or(0, |&t| nanos(t)),
+            max_ns: product.samples.iter().max().map_or(0, |&t| nanos(t)),
+            result_len: product.result_len(),
+            result_sample: product
+                .result
+                .iter()
+                .take(RESULT_SAMPLE_LEN)
+                .map(|value| format!("{:?}", value))
+                .collect(),
+            timed_out_ns: product.timed_out.map(nanos),
+            skipped: product.skipped.clone(),
+        }
+    }
+}
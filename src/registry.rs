@@ -0,0 +1,97 @@
+//! Collecting the methods to benchmark by name.
+
+use crate::Intersect;
+
+/// The methods to benchmark, in the order they were registered.
+///
+/// Names are unique, so every product can be traced back to one method.
+pub struct MethodRegistry<T = usize> {
+    methods: Vec<Box<dyn Intersect<T>>>,
+}
+
+impl<T> Default for MethodRegistry<T> {
+    fn default() -> Self {
+        MethodRegistry {
+            methods: Vec::new(),
+        }
+    }
+}
+
+/// The name of a method without its parameters, as used by [`MethodRegistry::select`].
+fn base_name<T>(method: &dyn Intersect<T>) -> String {
+    let name = method.name();
+    name.split('(').next().unwrap_or_default().to_string()
+}
+
+impl<T> MethodRegistry<T> {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a method after the ones already registered, unless a method with
+    /// the same name is registered already.
+    pub fn register(&mut self, method: Box<dyn Intersect<T>>) -> Result<(), String> {
+        let name = method.name();
+        if self.get(&name).is_some() {
+            return Err(format!("method '{}' is already registered", name));
+        }
+        self.methods.push(method);
+        Ok(())
+    }
+
+    /// The method with exactly this name, if there is one.
+    pub fn get(&self, name: &str) -> Option<&dyn Intersect<T>> {
+        self.iter().find(|method| method.name() == name)
+    }
+
+    /// The registered methods in registration order.
+    pub fn iter(&self) -> impl Iterator<Item = &dyn Intersect<T>> {
+        self.methods.iter().map(|method| &**method)
+    }
+
+    /// The registered methods, ready to be passed to [`run_benchmark`](crate::run_benchmark).
+    pub fn methods(&self) -> &[Box<dyn Intersect<T>>] {
+        &self.methods
+    }
+
+    /// The number of registered methods.
+    pub fn len(&self) -> usize {
+        self.methods.len()
+    }
+
+    /// Whether no method is registered.
+    pub fn is_empty(&self) -> bool {
+        self.methods.is_empty()
+    }
+
+    /// Keeps only the methods whose name without parameters matches one of
+    /// `names`, ignoring case, or all of them if `names` is empty.
+    ///
+    /// Fails if a name doesn't match any method.
+    pub fn select(self, names: &[String]) -> Result<Self, String> {
+        if names.is_empty() {
+            return Ok(self);
+        }
+        let mut available: Vec<String> = self.iter().map(base_name).collect();
+        available.dedup();
+        let names: Vec<String> = names.iter().map(|name| name.to_lowercase()).collect();
+        if let Some(unknown) = names
+            .iter()
+            .find(|name| !available.iter().any(|m| m.to_lowercase() == **name))
+        {
+            return Err(format!(
+                "unknown method '{}', valid methods are: {}",
+                unknown,
+                available.join(", ")
+            ));
+        }
+        Ok(MethodRegistry {
+            methods: self
+                .methods
+                .into_iter()
+                .filter(|m| names.contains(&base_name(&**m).to_lowercase()))
+                .collect(),
+        })
+    }
+}
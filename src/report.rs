@@ -0,0 +1,1178 @@
+//! Rendering the results as tables, CSV and JSON.
+
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, HashSet},
+    io::{self, Write},
+    iter::{once, repeat_n},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use clap::ValueEnum;
+use prettytable::{Cell, Row, Table};
+use serde::{Serialize, Serializer};
+
+use crate::{
+    benchmark::SWITCHED_ORDER,
+    record::{ProductRecord, SCHEMA_VERSION},
+    Element, Environment, Memory, Phases, Product, Scenario, Significance, Similarity,
+    Verification,
+};
+
+/// How a product compares to the one before it.
+#[derive(Serialize)]
+pub struct Comparison {
+    /// How many times faster the current product is, if it took any time at all.
+    pub ratio: Option<f64>,
+    /// How much less time the current product took, negative if it was slower.
+    pub difference_ns: i128,
+    /// The current time as a percentage of the previous one, if that took any time.
+    pub percent: Option<f64>,
+}
+
+impl Comparison {
+    /// Compares the time of a product to the time of the one before it.
+    pub fn new(previous: Duration, current: Duration) -> Self {
+        let (previous, current) = (previous.as_nanos(), current.as_nanos());
+        Comparison {
+            ratio: (current > 0).then(|| previous as f64 / current as f64),
+            difference_ns: previous as i128 - current as i128,
+            percent: (previous > 0).then(|| current as f64 / previous as f64 * 100.0),
+        }
+    }
+
+    /// The ratio formatted like `2.00x`, or `n/a`.
+    pub fn ratio(&self) -> String {
+        self.ratio
+            .map_or("n/a".to_string(), |ratio| format!("{:.2}x", ratio))
+    }
+
+    /// The percentage formatted like `50.00%`, or `n/a`.
+    pub fn percent(&self) -> String {
+        self.percent
+            .map_or("n/a".to_string(), |percent| format!("{:.2}%", percent))
+    }
+
+    /// The difference formatted with `time`, prefixed with `-` if negative.
+    pub fn difference(&self, time: impl Fn(Duration) -> String) -> String {
+        let magnitude = time(Duration::from_nanos(
+            self.difference_ns.unsigned_abs().min(u64::MAX as u128) as u64,
+        ));
+        if self.difference_ns < 0 {
+            format!("-{}", magnitude)
+        } else {
+            magnitude
+        }
+    }
+
+    /// Compares the first product to the last one.
+    pub fn overall<T>(products: &[Product<T>]) -> Self {
+        Comparison::new(products[0].time, products.last().unwrap().time)
+    }
+}
+
+/// The sum of the times of all products.
+pub fn total_time<T>(products: &[Product<T>]) -> Duration {
+    products
+        .iter()
+        .map(|x| x.time)
+        .fold(Duration::ZERO, |a, b| a + b)
+}
+
+/// The overall numbers of a run, shown below the table.
+#[derive(Debug, PartialEq)]
+pub struct Summary {
+    /// How long the whole benchmark took.
+    pub wall_time: Duration,
+    /// The name and time of the fastest product.
+    pub fastest: (String, Duration),
+    /// The name and time of the slowest product.
+    pub slowest: (String, Duration),
+    /// The geometric mean of the times, counting times below 1ns as 1ns.
+    pub geometric_mean: Duration,
+    /// How many times longer the slowest product took than the fastest one,
+    /// if that took any time at all.
+    pub spread: Option<f64>,
+}
+
+impl Summary {
+    /// Summarizes the products that didn't time out, or returns `None` if
+    /// every one did.
+    pub fn new<T>(products: &[Product<T>], wall_time: Duration) -> Option<Self> {
+        let ran: Vec<&Product<T>> = products
+            .iter()
+            .filter(|product| product.timed_out.is_none())
+            .collect();
+        let fastest = ran.iter().min_by_key(|product| product.time)?;
+        let slowest = ran.iter().max_by_key(|product| product.time)?;
+        let mean_log = ran
+            .iter()
+            .map(|product| (product.time.as_nanos().max(1) as f64).ln())
+            .sum::<f64>()
+            / ran.len() as f64;
+        Some(Summary {
+            wall_time,
+            fastest: (fastest.name.clone(), fastest.time),
+            slowest: (slowest.name.clone(), slowest.time),
+            geometric_mean: Duration::from_nanos(mean_log.exp().round() as u64),
+            spread: Comparison::new(slowest.time, fastest.time).ratio,
+        })
+    }
+
+    /// Writes the summary, one number per line, with the times in `unit`.
+    pub fn write(&self, out: &mut impl Write, unit: TimeUnit) -> io::Result<()> {
+        let time = |duration| unit.format_or(duration, |d| format!("{:?}", d));
+        writeln!(out, "\nthe benchmark took {}", time(self.wall_time))?;
+        writeln!(
+            out,
+            "fastest: {} ({})",
+            self.fastest.0,
+            time(self.fastest.1)
+        )?;
+        writeln!(
+            out,
+            "slowest: {} ({})",
+            self.slowest.0,
+            time(self.slowest.1)
+        )?;
+        writeln!(out, "geometric mean: {}", time(self.geometric_mean))?;
+        match self.spread {
+            Some(spread) => writeln!(out, "spread: {:.2}x", spread),
+            None => writeln!(out, "spread: n/a"),
+        }
+    }
+}
+
+pub(crate) fn serialize_nanos<S: Serializer>(
+    duration: &Duration,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u128(duration.as_nanos())
+}
+
+fn with_stats<T>(
+    mut row: Vec<String>,
+    detailed: bool,
+    product: Option<&Product<T>>,
+    time: impl Fn(Duration) -> String,
+) -> Vec<String> {
+    if detailed {
+        let (mean, std_dev) = product.map_or(("-".to_string(), "-".to_string()), |p| {
+            (time(p.mean()), time(p.std_dev()))
+        });
+        row.insert(2, mean);
+        row.insert(3, std_dev);
+    }
+    row
+}
+
+/// The product the ratio columns of the table compare every product to.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Reference {
+    /// The product in the row above, chaining the comparisons.
+    #[default]
+    Previous,
+    /// The fastest product.
+    Fastest,
+    /// The slowest product.
+    Slowest,
+    /// The product in the first row.
+    First,
+    /// The product with exactly this name.
+    Method(String),
+}
+
+impl Reference {
+    /// The index of the product to compare to, or `None` to compare every
+    /// product to the one before it.
+    ///
+    /// Fails if no product has the name of a [`Reference::Method`].
+    pub fn resolve<T>(&self, products: &[Product<T>]) -> Result<Option<usize>, String> {
+        let ran = || (0..products.len()).filter(|&i| products[i].timed_out.is_none());
+        let index = match self {
+            Reference::Previous => return Ok(None),
+            Reference::Fastest => ran().min_by_key(|&i| products[i].time),
+            Reference::Slowest => ran().max_by_key(|&i| products[i].time),
+            Reference::First => (!products.is_empty()).then_some(0),
+            Reference::Method(name) => {
+                let index = products.iter().position(|p| &p.name == name);
+                if index.is_none() {
+                    let names: Vec<&str> = products.iter().map(|p| p.name.as_str()).collect();
+                    return Err(format!(
+                        "the baseline '{}' did not run, methods that ran are: {}",
+                        name,
+                        names.join(", ")
+                    ));
+                }
+                index
+            }
+        };
+        Ok(index)
+    }
+}
+
+/// The order the products are shown in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum SortOrder {
+    /// The fastest product first.
+    TimeAsc,
+    /// The slowest product first, like the benchmark returns them.
+    #[default]
+    TimeDesc,
+    /// Alphabetically by name, so that the rows of different runs line up.
+    Name,
+}
+
+impl SortOrder {
+    /// Sorts the products, keeping the order of products that compare equal.
+    pub fn sort<T>(self, products: &mut [Product<T>]) {
+        match self {
+            SortOrder::TimeAsc => products.sort_by_key(|product| product.time),
+            SortOrder::TimeDesc => products.sort_by_key(|product| Reverse(product.time)),
+            SortOrder::Name => products.sort_by(|a, b| a.name.cmp(&b.name)),
+        }
+    }
+}
+
+/// Moves the products of both orders of a method and flavor next to each
+/// other, the normal order first, where the first of them was.
+pub fn group_orders<T>(products: &mut [Product<T>]) {
+    let mut pairs: Vec<String> = Vec::new();
+    for product in products.iter() {
+        if !pairs.iter().any(|name| name == product.pair_name()) {
+            pairs.push(product.pair_name().to_string());
+        }
+    }
+    products.sort_by_key(|product| {
+        let pair = pairs.iter().position(|name| name == product.pair_name());
+        (pair, product.switched)
+    });
+}
+
+/// A column of the table that can be selected with [`TableOptions::columns`].
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum Column {
+    /// The name of the product.
+    Name,
+    /// The time of the product.
+    Time,
+    /// How many times faster the product is than the one it is compared to.
+    RatioPrevious,
+    /// The time as a multiple of the fastest time.
+    RatioFastest,
+    /// How much less time the product took than the one it is compared to.
+    AbsDiff,
+    /// The time as a percentage of the one it is compared to.
+    Percent,
+    /// The name of the product it is compared to.
+    ComparedTo,
+    /// The number of elements in the result, flagged when it differs from
+    /// most products of the same order.
+    #[value(alias = "result-count")]
+    Matches,
+    /// How many times faster the faster order of a method is, shown when
+    /// [`TableOptions::group_orders`] is set.
+    OrderSensitivity,
+    /// How many input elements the product went through per second, shown
+    /// when [`TableOptions::elements`] is set.
+    Throughput,
+    /// The number of timed runs, flagged when the times didn't get stable,
+    /// shown when timing until stable.
+    Runs,
+}
+
+/// How the table is built.
+#[derive(Clone, Default)]
+pub struct TableOptions {
+    /// The product the ratio columns compare to.
+    pub reference: Reference,
+    /// Add a row with the sum of all times, compared from the first product
+    /// to the last one.
+    pub total: bool,
+    /// The columns to show in this order, or every column if empty.
+    pub columns: Vec<Column>,
+    /// The products of both orders are next to each other, see
+    /// [`group_orders`], so the first of them shows how much the order matters.
+    pub group_orders: bool,
+    /// The number of elements in both arrays, to show the throughput of every
+    /// product.
+    pub elements: Option<usize>,
+    /// The elements are membership queries, so the throughput is shown in
+    /// queries per second.
+    pub queries: bool,
+    /// The unit every duration is shown in.
+    pub time_unit: TimeUnit,
+    /// The width [`print_table`] fits the table into with the
+    /// [`Layout::Compact`] layout if the full one is wider, or `None` to
+    /// always show the full table. Fitting moves the reasons products were
+    /// skipped or timed out into footnotes below the table.
+    pub width: Option<usize>,
+}
+
+/// How [`print_table`] lays out the table to fit the width.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Layout {
+    /// Every column with its full header.
+    Full,
+    /// Without the absolute differences and the products they are compared
+    /// to, with abbreviated headers, and with the middle of the method names
+    /// cut out to the given number of characters if they still don't fit.
+    Compact { name_width: Option<usize> },
+}
+
+/// The fewest characters of a name the compact table keeps.
+const MIN_NAME_WIDTH: usize = 16;
+
+/// What the benchmark appends to the names of the methods, in the reverse
+/// order it appends them.
+const SUFFIXES: [&str; 3] = [" (baseline)", SWITCHED_ORDER, " seq"];
+
+/// The columns the compact table leaves out.
+const DROPPED: [&str; 2] = ["Absolute time difference", "Compared to"];
+
+/// The headers the compact table shortens, and their abbreviations.
+const ABBREVIATIONS: [(&str, &str); 12] = [
+    ("Time taken", "Time"),
+    ("times faster than previous", "x prev"),
+    ("times faster than baseline", "x base"),
+    ("percent of previous time", "% prev"),
+    ("percent of baseline time", "% base"),
+    ("vs fastest", "x fastest"),
+    ("Order sensitivity", "Order"),
+    ("Delta vs baseline", "Delta"),
+    ("Setup time", "Setup"),
+    ("Probe time", "Probe"),
+    ("Bytes allocated", "Allocated"),
+    ("Peak bytes", "Peak"),
+];
+
+/// The width a table with columns of these widths takes, borders included.
+fn table_width(widths: &[usize]) -> usize {
+    1 + widths.iter().map(|width| width + 3).sum::<usize>()
+}
+
+/// Chooses the layout of a table that should be at most `width` characters
+/// wide, from the widths of the columns of the full and of the compact
+/// table, the names first.
+pub fn choose_layout(width: usize, full: &[usize], compact: &[usize]) -> Layout {
+    if table_width(full) <= width {
+        return Layout::Full;
+    }
+    let excess = table_width(compact).saturating_sub(width);
+    Layout::Compact {
+        name_width: (excess > 0).then(|| {
+            compact[0]
+                .saturating_sub(excess)
+                .max(MIN_NAME_WIDTH)
+                .min(compact[0])
+        }),
+    }
+}
+
+/// The number of characters of the widest cell of every column.
+fn column_widths(rows: &[Vec<String>]) -> Vec<usize> {
+    (0..rows[0].len())
+        .map(|i| rows.iter().map(|row| row[i].chars().count()).max().unwrap())
+        .collect()
+}
+
+/// The rows of the compact table, without the [`DROPPED`] columns and with
+/// abbreviated headers.
+fn compact_rows(rows: &[Vec<String>]) -> Vec<Vec<String>> {
+    let kept: Vec<usize> = (0..rows[0].len())
+        .filter(|&i| !DROPPED.contains(&rows[0][i].as_str()))
+        .collect();
+    let abbreviate = |header: &String| {
+        ABBREVIATIONS
+            .iter()
+            .find(|(full, _)| full == header)
+            .map_or_else(|| header.clone(), |(_, short)| short.to_string())
+    };
+    rows.iter()
+        .enumerate()
+        .map(|(r, row)| {
+            kept.iter()
+                .map(|&i| match r {
+                    0 => abbreviate(&row[i]),
+                    _ => row[i].clone(),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// Splits `name` into the method and the flavor, order and baseline mark
+/// at its end.
+fn split_suffix(name: &str) -> (&str, &str) {
+    let method = SUFFIXES.iter().fold(name, |method, suffix| {
+        method.strip_suffix(suffix).unwrap_or(method)
+    });
+    name.split_at(method.len())
+}
+
+/// Cuts the middle out of the method of `name` to shorten it to `width`
+/// characters, keeping the flavor and order at its end whole, even if that
+/// takes more characters. The switched order is shortened to "switched" if
+/// it leaves too little of the method.
+fn truncate(name: &str, width: usize) -> String {
+    if name.chars().count() <= width {
+        return name.to_string();
+    }
+    let (method, suffix) = split_suffix(name);
+    let len = method.chars().count();
+    let mut suffix = suffix.to_string();
+    // a character of the method on either side of the cut at least
+    if suffix.chars().count() + 3 > width {
+        suffix = suffix.replacen(SWITCHED_ORDER, " switched", 1);
+    }
+    let kept = width.saturating_sub(suffix.chars().count() + 1).max(2);
+    if kept >= len {
+        return name.to_string();
+    }
+    let tail = kept / 2;
+    let mut cut: String = method.chars().take(kept - tail).collect();
+    cut.push('…');
+    cut.extend(method.chars().skip(len - tail));
+    cut.push_str(&suffix);
+    cut
+}
+
+/// The fewest characters from `width` on that [`truncate`] can shorten the
+/// names to without making any two of them equal.
+fn distinct_width<'a>(names: impl Iterator<Item = &'a str> + Clone, width: usize) -> usize {
+    let longest = names.clone().map(|name| name.chars().count()).max();
+    (width..longest.unwrap_or(width))
+        .find(|&width| {
+            let mut seen = HashSet::new();
+            names.clone().all(|name| seen.insert(truncate(name, width)))
+        })
+        .unwrap_or(width.max(longest.unwrap_or(0)))
+}
+
+/// Replaces the reasons in the time cells of the products that were skipped
+/// or timed out, which would widen the time column, with the numbers of
+/// footnotes, and returns the footnotes. Products with the same reason
+/// share one.
+fn footnote<T>(
+    rows: &mut [Vec<String>],
+    products: &[Product<T>],
+    skipped: &[Product<T>],
+) -> Vec<String> {
+    let mut notes: Vec<String> = Vec::new();
+    let mut number = |note: String| {
+        let i = notes.iter().position(|n| *n == note).unwrap_or_else(|| {
+            notes.push(note);
+            notes.len() - 1
+        });
+        format!("[{}]", i + 1)
+    };
+    for (row, product) in rows[1..].iter_mut().zip(products.iter().chain(skipped)) {
+        if let Some(reason) = &product.skipped {
+            row[1] = format!("skipped {}", number(format!("skipped: {}", reason)));
+        } else if let Some(limit) = row[1].strip_suffix(" (timed out)") {
+            row[1] = format!("{} {}", limit, number("timed out".to_string()));
+        }
+    }
+    notes
+}
+
+/// The rows of the table in the layout that fits `width`, and the footnotes
+/// to print below it.
+fn fit<T>(
+    mut rows: Vec<Vec<String>>,
+    products: &[Product<T>],
+    skipped: &[Product<T>],
+    width: usize,
+) -> (Vec<Vec<String>>, Vec<String>) {
+    if table_width(&column_widths(&rows)) <= width {
+        return (rows, Vec::new());
+    }
+    let notes = footnote(&mut rows, products, skipped);
+    let compact = compact_rows(&rows);
+    let rows = match choose_layout(width, &column_widths(&rows), &column_widths(&compact)) {
+        Layout::Full => rows,
+        Layout::Compact { name_width: None } => compact,
+        Layout::Compact {
+            name_width: Some(name_width),
+        } => {
+            let names = compact.iter().map(|row| row[0].as_str());
+            let name_width = distinct_width(names, name_width);
+            compact
+                .into_iter()
+                .map(|mut row| {
+                    row[0] = truncate(&row[0], name_width);
+                    row
+                })
+                .collect()
+        }
+    };
+    (rows, notes)
+}
+
+/// Builds the cells of the results table, formatting durations in the
+/// [`TableOptions::time_unit`], or with `auto` if that is chosen per value.
+pub(crate) fn table_rows<T>(
+    products: &[Product<T>],
+    skipped: &[Product<T>],
+    reference: Option<usize>,
+    options: &TableOptions,
+    auto: fn(Duration) -> String,
+) -> Vec<Vec<String>> {
+    let time = |duration| options.time_unit.format_or(duration, auto);
+    let total = options.total;
+    let detailed = products.iter().any(|p| p.samples.len() > 1);
+    let (faster, percent) = match reference {
+        None => ("times faster than previous", "percent of previous time"),
+        Some(_) => ("times faster than baseline", "percent of baseline time"),
+    };
+    let mut header: Vec<String> = [
+        "Name",
+        if detailed {
+            products[0].aggregate.name()
+        } else {
+            "Time taken"
+        },
+        faster,
+        "Absolute time difference",
+        percent,
+        "Compared to",
+    ]
+    .map(String::from)
+    .to_vec();
+    // the selectable column every cell of a row belongs to
+    let mut kinds = vec![
+        Some(Column::Name),
+        Some(Column::Time),
+        Some(Column::RatioPrevious),
+        Some(Column::AbsDiff),
+        Some(Column::Percent),
+        Some(Column::ComparedTo),
+    ];
+    if detailed {
+        header.insert(2, "Mean".to_string());
+        header.insert(3, "Std dev".to_string());
+        kinds.splice(2..2, [None, None]);
+    }
+    let mut rows = vec![header];
+    let dash = || "-".to_string();
+    let time_taken = |product: &Product<T>| match product.timed_out {
+        Some(limit) => format!("> {} (timed out)", time(limit)),
+        None => time(product.time),
+    };
+    let compared_to = |i: usize| match reference {
+        None => i.checked_sub(1),
+        Some(reference) => (reference != i).then_some(reference),
+    };
+
+    products.iter().enumerate().for_each(|(i, product)| {
+        let name = if reference == Some(i) {
+            format!("{} (baseline)", product.name)
+        } else {
+            product.name.clone()
+        };
+        let other = compared_to(i).map(|j| &products[j]);
+        let row = match other {
+            Some(other) if other.timed_out.is_none() && product.timed_out.is_none() => {
+                let comparison = Comparison::new(other.time, product.time);
+                vec![
+                    name,
+                    time(product.time),
+                    comparison.ratio(),
+                    comparison.difference(time),
+                    comparison.percent(),
+                    other.name.clone(),
+                ]
+            }
+            _ => vec![name, time_taken(product), dash(), dash(), dash(), dash()],
+        };
+        // timed out products have no samples to compute statistics from
+        let product = Some(product).filter(|p| p.timed_out.is_none());
+        rows.push(with_stats(row, detailed, product, time));
+    });
+    skipped.iter().for_each(|product| {
+        let row = vec![
+            product.name.clone(),
+            format!(
+                "skipped: {}",
+                product.skipped.as_deref().unwrap_or_default()
+            ),
+            dash(),
+            dash(),
+            dash(),
+            dash(),
+        ];
+        rows.push(with_stats::<T>(row, detailed, None, time));
+    });
+    // the rows after the products that have no cell in the extra columns
+    let trailing = skipped.len() + usize::from(total);
+    let total_row = match reference {
+        None => {
+            let comparison = Comparison::overall(products);
+            vec![
+                "Total".to_string(),
+                time(total_time(products)),
+                comparison.ratio(),
+                comparison.difference(time),
+                comparison.percent(),
+                dash(),
+            ]
+        }
+        // the total isn't a product, so it has nothing to compare to the baseline
+        Some(_) => vec![
+            "Total".to_string(),
+            time(total_time(products)),
+            dash(),
+            dash(),
+            dash(),
+            dash(),
+        ],
+    };
+    if total {
+        rows.push(with_stats::<T>(total_row, detailed, None, time));
+    }
+    let (fastest, _) = time_range(products);
+    let vs_fastest = |time: Duration| Comparison::new(time, fastest).ratio();
+    let column = once("vs fastest".to_string())
+        .chain(products.iter().map(|p| match p.timed_out {
+            Some(_) => dash(),
+            None => vs_fastest(p.time),
+        }))
+        .chain(repeat_n(dash(), skipped.len()))
+        .chain(total.then(|| vs_fastest(total_time(products))));
+    rows.iter_mut()
+        .zip(column)
+        .for_each(|(row, cell)| row.push(cell));
+    kinds.push(Some(Column::RatioFastest));
+    if let Some(elements) = options.elements {
+        let unit = if options.queries { "queries" } else { "elem" };
+        let column = once("Throughput".to_string())
+            .chain(products.iter().map(|p| {
+                match p.timed_out {
+                    Some(_) => dash(),
+                    None => throughput(elements, p.time)
+                        .map_or_else(dash, |per_second| format_throughput(per_second, unit)),
+                }
+            }))
+            .chain(repeat_n(dash(), trailing));
+        rows.iter_mut()
+            .zip(column)
+            .for_each(|(row, cell)| row.push(cell));
+        kinds.push(Some(Column::Throughput));
+    }
+    if options.group_orders {
+        let column = once("Order sensitivity".to_string())
+            .chain(products.iter().enumerate().map(|(i, product)| {
+                let other = products[i + 1..].iter().find(|other| {
+                    other.pair_name() == product.pair_name() && other.switched != product.switched
+                });
+                match other {
+                    Some(other) if product.timed_out.is_none() && other.timed_out.is_none() => {
+                        let (slower, faster) = if product.time < other.time {
+                            (other, product)
+                        } else {
+                            (product, other)
+                        };
+                        let order = if faster.switched {
+                            "switched"
+                        } else {
+                            "normal"
+                        };
+                        let ratio = Comparison::new(slower.time, faster.time).ratio();
+                        format!("{} ({} order faster)", ratio, order)
+                    }
+                    _ => dash(),
+                }
+            }))
+            .chain(repeat_n(dash(), trailing));
+        rows.iter_mut()
+            .zip(column)
+            .for_each(|(row, cell)| row.push(cell));
+        kinds.push(Some(Column::OrderSensitivity));
+    }
+    // the results of both orders differ for some operations and semantics,
+    // so every product is compared to the usual count of its own order
+    let usual = |switched: bool| {
+        let mut counts = BTreeMap::new();
+        products
+            .iter()
+            .filter(|p| p.switched == switched && p.timed_out.is_none())
+            .for_each(|p| *counts.entry(p.result_len()).or_insert(0) += 1);
+        counts
+            .into_iter()
+            .rev()
+            .max_by_key(|&(_, n)| n)
+            .map(|(count, _)| count)
+    };
+    let usual = [usual(false), usual(true)];
+    let column = once("Matches".to_string())
+        .chain(products.iter().map(|p| {
+            let count = p.result_len();
+            match (p.timed_out, usual[usize::from(p.switched)]) {
+                (Some(_), _) => dash(),
+                (None, Some(usual)) if usual != count => format!("{} (mismatch)", count),
+                _ => count.to_string(),
+            }
+        }))
+        .chain(repeat_n(dash(), trailing));
+    rows.iter_mut()
+        .zip(column)
+        .for_each(|(row, cell)| row.push(cell));
+    kinds.push(Some(Column::Matches));
+    if products.iter().any(|p| p.converged.is_some()) {
+        let column = once("Runs".to_string())
+            .chain(products.iter().map(|p| match p.converged {
+                Some(true) => p.samples.len().to_string(),
+                Some(false) => format!("{} (not converged)", p.samples.len()),
+                None => dash(),
+            }))
+            .chain(repeat_n(dash(), trailing));
+        rows.iter_mut()
+            .zip(column)
+            .for_each(|(row, cell)| row.push(cell));
+        kinds.push(Some(Column::Runs));
+    }
+    if detailed {
+        let column = once("Significance".to_string())
+            .chain((0..products.len()).map(|i| {
+                compared_to(i)
+                    .and_then(|j| Significance::new(&products[j].samples, &products[i].samples))
+                    .map_or_else(dash, |significance| significance.format())
+            }))
+            .chain(repeat_n(dash(), trailing));
+        rows.iter_mut()
+            .zip(column)
+            .for_each(|(row, cell)| row.push(cell));
+        kinds.push(None);
+    }
+    if products.iter().any(|p| p.phases.is_some()) {
+        let cells = |phase: fn(&Phases) -> Duration| {
+            products
+                .iter()
+                .map(move |p| p.phases.as_ref().map_or_else(dash, |ph| time(phase(ph))))
+                .chain(repeat_n(dash(), trailing))
+        };
+        let setup = once("Setup time".to_string()).chain(cells(|ph| ph.setup));
+        let probe = once("Probe time".to_string()).chain(cells(|ph| ph.probe));
+        rows.iter_mut()
+            .zip(setup.zip(probe))
+            .for_each(|(row, (setup, probe))| row.extend([setup, probe]));
+        kinds.extend([None, None]);
+    }
+    if products.iter().any(|p| p.baseline.is_some()) {
+        let column = once("Delta vs baseline".to_string())
+            .chain(products.iter().map(|p| {
+                p.baseline
+                    .map_or("not in baseline".to_string(), |delta| delta.format())
+            }))
+            .chain(repeat_n(dash(), trailing));
+        rows.iter_mut()
+            .zip(column)
+            .for_each(|(row, cell)| row.push(cell));
+        kinds.push(None);
+    }
+    if products.iter().any(|p| p.memory.is_some()) {
+        let cells = |bytes: fn(&Memory) -> usize| {
+            products
+                .iter()
+                .map(move |p| {
+                    p.memory
+                        .as_ref()
+                        .map_or_else(dash, |m| format_bytes(bytes(m)))
+                })
+                .chain(repeat_n(dash(), trailing))
+        };
+        let allocated = once("Bytes allocated".to_string()).chain(cells(|m| m.allocated));
+        let peak = once("Peak bytes".to_string()).chain(cells(|m| m.peak));
+        rows.iter_mut()
+            .zip(allocated.zip(peak))
+            .for_each(|(row, (allocated, peak))| row.extend([allocated, peak]));
+        kinds.extend([None, None]);
+    }
+    if options.columns.is_empty() {
+        return rows;
+    }
+    let selected: Vec<usize> = options
+        .columns
+        .iter()
+        .filter_map(|column| kinds.iter().position(|kind| *kind == Some(*column)))
+        .collect();
+    rows.into_iter()
+        .map(|row| selected.iter().map(|&i| row[i].clone()).collect())
+        .collect()
+}
+
+/// The number of elements processed per second, or `None` for a zero time.
+pub fn throughput(elements: usize, time: Duration) -> Option<f64> {
+    (!time.is_zero()).then(|| elements as f64 / time.as_secs_f64())
+}
+
+/// Formats `unit`s per second with three significant digits and an SI
+/// prefix, like `412 Melem/s`.
+fn format_throughput(per_second: f64, unit: &str) -> String {
+    const PREFIXES: [&str; 5] = ["", "k", "M", "G", "T"];
+    let mut value = per_second;
+    let mut prefix = 0;
+    while value >= 999.5 && prefix + 1 < PREFIXES.len() {
+        value /= 1000.0;
+        prefix += 1;
+    }
+    let decimals = match value {
+        v if v >= 99.95 => 0,
+        v if v >= 9.995 => 1,
+        _ => 2,
+    };
+    format!("{:.*} {}{}/s", decimals, value, PREFIXES[prefix], unit)
+}
+
+/// Formats a byte count with two decimals in the largest binary unit that
+/// keeps it above one.
+fn format_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["KiB", "MiB", "GiB", "TiB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.2} {}", value, UNITS[unit])
+}
+
+pub(crate) fn resolve<T>(
+    reference: &Reference,
+    products: &[Product<T>],
+) -> io::Result<Option<usize>> {
+    reference
+        .resolve(products)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+}
+
+/// Whether a product is the fastest or the slowest, including ties.
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum Highlight {
+    Fastest,
+    Slowest,
+}
+
+/// The shortest and the longest time of the products, or zero if there are
+/// none.
+pub(crate) fn time_range<T>(products: &[Product<T>]) -> (Duration, Duration) {
+    let times = || products.iter().map(|product| product.time);
+    (
+        times().min().unwrap_or_default(),
+        times().max().unwrap_or_default(),
+    )
+}
+
+pub(crate) fn highlight<T>(products: &[Product<T>], product: &Product<T>) -> Option<Highlight> {
+    let (fastest, slowest) = time_range(products);
+    if products.is_empty() {
+        None
+    } else if product.time == fastest {
+        Some(Highlight::Fastest)
+    } else if product.time == slowest {
+        Some(Highlight::Slowest)
+    } else {
+        None
+    }
+}
+
+/// Prints the table, coloring the fastest and slowest rows when `color` is
+/// set, in which case the table always goes to stdout.
+///
+/// The ratio columns compare every product to the reference of `options`,
+/// failing with [`io::ErrorKind::InvalidInput`] if it didn't run. A table
+/// wider than [`TableOptions::width`] is shown in the compact [`Layout`].
+///
+/// `products` must not be empty.
+pub fn print_table<T>(
+    out: &mut impl Write,
+    products: &[Product<T>],
+    skipped: &[Product<T>],
+    options: &TableOptions,
+    color: bool,
+) -> io::Result<()> {
+    let reference = resolve(&options.reference, products)?;
+    let mut table = Table::new();
+    let mut rows = table_rows(products, skipped, reference, options, |d| {
+        format!("{:?}", d)
+    });
+    let mut notes = Vec::new();
+    // explicitly selected columns are kept as they are
+    if let Some(width) = options.width.filter(|_| options.columns.is_empty()) {
+        (rows, notes) = fit(rows, products, skipped, width);
+    }
+    rows.iter().enumerate().for_each(|(i, row)| {
+        let style = match i.checked_sub(1).and_then(|i| products.get(i)) {
+            Some(product) if color => match highlight(products, product) {
+                Some(Highlight::Fastest) => "Fg",
+                Some(Highlight::Slowest) => "Fr",
+                None => "",
+            },
+            _ => "",
+        };
+        table.add_row(Row::new(
+            row.iter()
+                .map(|cell| Cell::new(cell).style_spec(style))
+                .collect(),
+        ));
+    });
+    if color {
+        out.flush()?;
+        table.print_tty(true)?;
+    } else {
+        table.print(out)?;
+    }
+    for (i, note) in notes.iter().enumerate() {
+        writeln!(out, "[{}] {}", i + 1, note)?;
+    }
+    Ok(())
+}
+
+/// The unit durations are shown in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum TimeUnit {
+    /// The unit that fits each duration.
+    #[default]
+    Auto,
+    /// Whole nanoseconds.
+    Ns,
+    /// Microseconds with three decimals.
+    Us,
+    /// Milliseconds with three decimals.
+    Ms,
+    /// Seconds with three decimals.
+    S,
+}
+
+impl TimeUnit {
+    /// Formats `duration` in this unit, like [`format_compact`] if the unit
+    /// is chosen per value.
+    pub fn format(self, duration: Duration) -> String {
+        self.format_or(duration, format_compact)
+    }
+
+    /// Formats `duration` in this unit, or with `auto` if the unit is chosen
+    /// per value.
+    pub fn format_or(self, duration: Duration, auto: fn(Duration) -> String) -> String {
+        let nanos = duration.as_nanos();
+        match self {
+            TimeUnit::Auto => auto(duration),
+            TimeUnit::Ns => format!("{}ns", nanos),
+            TimeUnit::Us => format!("{:.3}µs", nanos as f64 / 1e3),
+            TimeUnit::Ms => format!("{:.3}ms", nanos as f64 / 1e6),
+            TimeUnit::S => format!("{:.3}s", nanos as f64 / 1e9),
+        }
+    }
+}
+
+/// Formats a duration with two decimals in the largest unit that keeps it above one.
+pub fn format_compact(duration: Duration) -> String {
+    let nanos = duration.as_nanos() as f64;
+    match nanos {
+        n if n < 1e3 => format!("{}ns", n),
+        n if n < 1e6 => format!("{:.2}µs", n / 1e3),
+        n if n < 1e9 => format!("{:.2}ms", n / 1e6),
+        n => format!("{:.2}s", n / 1e9),
+    }
+}
+
+/// Prints the same table as [`print_table`] as a Markdown table.
+pub fn print_markdown<T>(
+    out: &mut impl Write,
+    products: &[Product<T>],
+    skipped: &[Product<T>],
+    options: &TableOptions,
+) -> io::Result<()> {
+    let reference = resolve(&options.reference, products)?;
+    let rows = table_rows(products, skipped, reference, options, format_compact);
+    let mut line = |row: &Vec<String>| {
+        let cells: Vec<String> = row.iter().map(|cell| cell.replace('|', "\\|")).collect();
+        writeln!(out, "| {} |", cells.join(" | "))
+    };
+    line(&rows[0])?;
+    line(&vec!["---".to_string(); rows[0].len()])?;
+    rows[1..].iter().try_for_each(line)
+}
+
+/// Writes a `name<TAB>nanos` line per product that ran to completion.
+pub fn print_times<T>(out: &mut impl Write, products: &[Product<T>]) -> io::Result<()> {
+    products
+        .iter()
+        .filter(|product| product.skipped.is_none() && product.timed_out.is_none())
+        .try_for_each(|product| writeln!(out, "{}\t{}", product.name, product.time.as_nanos()))
+}
+
+/// Writes the time of every timed run of every product.
+pub fn print_samples<T>(out: &mut impl Write, products: &[Product<T>]) -> io::Result<()> {
+    writeln!(out, "\nsamples of every method:")?;
+    let max_name_len = products.iter().map(|p| p.name.len()).max().unwrap_or(0);
+    products
+        .iter()
+        .filter(|product| product.timed_out.is_none())
+        .try_for_each(|product| {
+            let samples: Vec<String> = product
+                .samples
+                .iter()
+                .map(|&sample| format_compact(sample))
+                .collect();
+            writeln!(
+                out,
+                "{:<x$}: {}",
+                product.name,
+                samples.join(", "),
+                x = max_name_len
+            )
+        })
+}
+
+/// Writes one CSV record per product, followed by the skipped ones, each
+/// with the sizes and the current [`Environment`].
+pub fn write_csv<T>(
+    writer: impl Write,
+    products: &[Product<T>],
+    skipped: &[Product<T>],
+    size_a: usize,
+    size_b: usize,
+) -> csv::Result<()> {
+    let mut writer = csv::Writer::from_writer(writer);
+    writer.write_record([
+        "name",
+        "time_ns",
+        "result_len",
+        "size_a",
+        "size_b",
+        "skipped",
+        "throughput_elem_per_s",
+        "cpus",
+        "threads",
+        "target",
+        "profile",
+        "version",
+        "rustc",
+    ])?;
+    let environment = Environment::current();
+    let (cpus, threads) = (
+        environment.cpus.to_string(),
+        environment.threads.to_string(),
+    );
+    for product in products.iter().chain(skipped) {
+        let time = match (&product.skipped, product.timed_out) {
+            (None, None) => product.time.as_nanos().to_string(),
+            _ => String::new(),
+        };
+        let skipped = match product.timed_out {
+            Some(limit) => format!("timed out after {:?}", limit),
+            None => product.skipped.clone().unwrap_or_default(),
+        };
+        let throughput = match (&product.skipped, product.timed_out) {
+            (None, None) => throughput(size_a + size_b, product.time),
+            _ => None,
+        };
+        writer.write_record([
+            product.name.as_str(),
+            &time,
+            &product.result_len().to_string(),
+            &size_a.to_string(),
+            &size_b.to_string(),
+            &skipped,
+            &throughput.map_or_else(String::new, |t| t.to_string()),
+            &cpus,
+            &threads,
+            &environment.target,
+            &environment.profile,
+            &environment.version,
+            &environment.rustc,
+        ])?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Everything about a run, serialized as the JSON report.
+#[derive(Serialize)]
+pub struct Report<'a> {
+    schema_version: u32,
+    started_at_ms: u128,
+    finished_at_ms: u128,
+    seed: u64,
+    size_a: usize,
+    size_b: usize,
+    products: Vec<ProductReport<'a>>,
+    skipped: Vec<ProductRecord>,
+    #[serde(serialize_with = "serialize_nanos")]
+    total_time_ns: Duration,
+    total: Option<Comparison>,
+    all_equal: bool,
+    disagreeing: Vec<&'a str>,
+    similarity: Option<Similarity>,
+    environment: Environment,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scenario: Option<Scenario>,
+}
+
+#[derive(Serialize)]
+struct ProductReport<'a> {
+    #[serde(flatten)]
+    record: ProductRecord,
+    throughput_elem_per_s: Option<f64>,
+    compared_to: Option<&'a str>,
+    comparison: Option<Comparison>,
+}
+
+impl<'a> Report<'a> {
+    /// Summarizes a run that started at `started_at` and finished now, with
+    /// the names of the products that `verification` found to disagree.
+    pub fn new<T: Element>(
+        started_at: SystemTime,
+        seed: u64,
+        sizes: (usize, usize),
+        products: &'a [Product<T>],
+        skipped: &'a [Product<T>],
+        verification: &Verification<'a, T>,
+        similarity: Option<Similarity>,
+    ) -> Self {
+        let millis = |time: SystemTime| {
+            time.duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis()
+        };
+        let previous = [None].into_iter().chain(products.iter().map(Some));
+        Report {
+            schema_version: SCHEMA_VERSION,
+            started_at_ms: millis(started_at),
+            finished_at_ms: millis(SystemTime::now()),
+            seed,
+            size_a: sizes.0,
+            size_b: sizes.1,
+            products: products
+                .iter()
+                .zip(previous)
+                .map(|(product, previous)| ProductReport {
+                    record: ProductRecord::from(product),
+                    throughput_elem_per_s: match product.timed_out {
+                        Some(_) => None,
+                        None => throughput(sizes.0 + sizes.1, product.time),
+                    },
+                    compared_to: previous.map(|p| p.name.as_str()),
+                    comparison: previous.map(|p| Comparison::new(p.time, product.time)),
+                })
+                .collect(),
+            skipped: skipped.iter().map(ProductRecord::from).collect(),
+            total_time_ns: total_time(products),
+            total: (!products.is_empty()).then(|| Comparison::overall(products)),
+            all_equal: verification.passed(),
+            disagreeing: verification.disagreeing(),
+            similarity,
+            environment: Environment::current(),
+            scenario: None,
+        }
+    }
+
+    /// Records the scenario the settings of the run came from.
+    pub fn with_scenario(self, scenario: Option<Scenario>) -> Self {
+        Report { scenario, ..self }
+    }
+}
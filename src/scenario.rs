@@ -0,0 +1,140 @@
+//! Reading named sets of command line settings from a TOML file.
+
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+use toml::{Table, Value};
+
+/// A named set of command line settings from a scenario file, like
+///
+/// ```toml
+/// [scenarios.huge-skewed]
+/// size_a = 10_000_000
+/// size_b = 1_000
+/// methods = ["Hash", "Binary"]
+/// ```
+///
+/// Every key is the name of a command line flag with underscores instead of
+/// dashes. A flag that takes no value is set with `true`.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct Scenario {
+    /// The name of the scenario in the file.
+    pub name: String,
+    /// The file the scenario was read from.
+    pub file: String,
+    /// The values of every setting, none for a flag that takes no value.
+    pub settings: BTreeMap<String, Vec<String>>,
+    /// The settings replaced by flags on the command line.
+    #[serde(default)]
+    pub overridden: Vec<String>,
+}
+
+/// The value of a setting as command line values.
+fn values(key: &str, value: &Value) -> Result<Option<Vec<String>>, String> {
+    Ok(Some(match value {
+        Value::Boolean(true) => Vec::new(),
+        Value::Boolean(false) => return Ok(None),
+        Value::String(text) => vec![text.clone()],
+        Value::Integer(number) => vec![number.to_string()],
+        Value::Float(number) => vec![number.to_string()],
+        Value::Array(items) => items
+            .iter()
+            .map(|item| match item {
+                Value::Array(_) | Value::Table(_) => {
+                    Err(format!("`{}` must be a list of plain values", key))
+                }
+                Value::String(text) => Ok(text.clone()),
+                item => Ok(item.to_string()),
+            })
+            .collect::<Result<_, _>>()?,
+        Value::Datetime(_) | Value::Table(_) => {
+            return Err(format!(
+                "`{}` must be a string, a number, a boolean or a list",
+                key
+            ))
+        }
+    }))
+}
+
+impl Scenario {
+    /// Reads the scenario `name` from the TOML `text` of `file`, whose keys
+    /// must all be in `known`.
+    ///
+    /// Fails naming the offending key if the file has anything but a
+    /// `scenarios` table, or the scenario a key that isn't known.
+    pub fn parse(text: &str, file: &str, name: &str, known: &[String]) -> Result<Self, String> {
+        let table: Table = text.parse().map_err(|e| format!("{}", e))?;
+        if let Some(key) = table.keys().find(|key| *key != "scenarios") {
+            return Err(format!("unknown key `{}`, expected `scenarios`", key));
+        }
+        let scenarios = match table.get("scenarios") {
+            Some(Value::Table(scenarios)) => scenarios.clone(),
+            Some(_) => return Err("`scenarios` must be a table".to_string()),
+            None => Table::new(),
+        };
+        let Some(scenario) = scenarios.get(name) else {
+            let names: Vec<&str> = scenarios.keys().map(String::as_str).collect();
+            return Err(match names.len() {
+                0 => format!("no scenario `{}`, the file has none", name),
+                _ => format!(
+                    "no scenario `{}`, expected one of {}",
+                    name,
+                    names.join(", ")
+                ),
+            });
+        };
+        let Value::Table(scenario) = scenario else {
+            return Err(format!("scenario `{}` must be a table", name));
+        };
+        let mut settings = BTreeMap::new();
+        for (key, value) in scenario {
+            if !known.contains(key) {
+                return Err(format!(
+                    "unknown field `{}` in scenario `{}`, expected one of {}",
+                    key,
+                    name,
+                    known.join(", ")
+                ));
+            }
+            if let Some(values) = values(key, value)? {
+                settings.insert(key.clone(), values);
+            }
+        }
+        Ok(Scenario {
+            name: name.to_string(),
+            file: file.to_string(),
+            settings,
+            overridden: Vec::new(),
+        })
+    }
+
+    /// The settings that aren't overridden, formatted like `size_a = 10,
+    /// methods = Hash,Binary, pre_sorted`, for the run header.
+    pub fn description(&self) -> String {
+        let settings: Vec<String> = self
+            .settings
+            .iter()
+            .filter(|(key, _)| !self.overridden.contains(key))
+            .map(|(key, values)| match values.is_empty() {
+                true => key.clone(),
+                false => format!("{} = {}", key, values.join(",")),
+            })
+            .collect();
+        let mut description = format!(
+            "scenario {} from {}: {}",
+            self.name,
+            self.file,
+            match settings.is_empty() {
+                true => "nothing".to_string(),
+                false => settings.join(", "),
+            }
+        );
+        if !self.overridden.is_empty() {
+            description.push_str(&format!(
+                " ({} set on the command line)",
+                self.overridden.join(", ")
+            ));
+        }
+        description
+    }
+}
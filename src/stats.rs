@@ -0,0 +1,127 @@
+//! Telling whether the difference between two sets of samples is noise.
+
+use std::time::Duration;
+
+/// The p-value below which a difference counts as significant.
+pub const SIGNIFICANCE_LEVEL: f64 = 0.05;
+
+/// The standard error of the mean of the samples relative to the mean, like
+/// `0.02` for 2%, if there are at least two samples.
+pub fn relative_standard_error(samples: &[Duration]) -> Option<f64> {
+    if samples.len() < 2 {
+        return None;
+    }
+    let n = samples.len() as f64;
+    let nanos = || samples.iter().map(|x| x.as_nanos() as f64);
+    let mean = nanos().sum::<f64>() / n;
+    let variance = nanos().map(|x| (x - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    Some(match mean {
+        0.0 => 0.0,
+        _ => (variance / n).sqrt() / mean,
+    })
+}
+
+/// Whether two sets of samples differ, and by how much.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Significance {
+    /// The two-sided p-value of the Mann–Whitney U test.
+    pub p_value: f64,
+    /// The 2.5th percentile of the ratios of every pair of samples.
+    pub ratio_low: f64,
+    /// The 97.5th percentile of the ratios of every pair of samples.
+    pub ratio_high: f64,
+}
+
+impl Significance {
+    /// Compares the samples of the previous product to the ones of the
+    /// current product, if both have at least two samples.
+    ///
+    /// The ratios are how many times faster the current samples are.
+    pub fn new(previous: &[Duration], current: &[Duration]) -> Option<Self> {
+        if previous.len() < 2 || current.len() < 2 {
+            return None;
+        }
+        let nanos = |samples: &[Duration]| -> Vec<f64> {
+            samples.iter().map(|d| d.as_nanos() as f64).collect()
+        };
+        let (previous, current) = (nanos(previous), nanos(current));
+        let mut ratios: Vec<f64> = previous
+            .iter()
+            .flat_map(|p| current.iter().map(move |c| p / c.max(1.0)))
+            .collect();
+        ratios.sort_by(f64::total_cmp);
+        let last = (ratios.len() - 1) as f64;
+        Some(Significance {
+            p_value: mann_whitney(&previous, &current),
+            ratio_low: ratios[(0.025 * last).floor() as usize],
+            ratio_high: ratios[(0.975 * last).ceil() as usize],
+        })
+    }
+
+    /// Whether the p-value is below [`SIGNIFICANCE_LEVEL`].
+    pub fn significant(&self) -> bool {
+        self.p_value < SIGNIFICANCE_LEVEL
+    }
+
+    /// Formats the verdict and the range like `significant (1.02x-1.10x)`.
+    pub fn format(&self) -> String {
+        format!(
+            "{} ({:.2}x-{:.2}x)",
+            if self.significant() {
+                "significant"
+            } else {
+                "noise"
+            },
+            self.ratio_low,
+            self.ratio_high
+        )
+    }
+}
+
+/// The two-sided p-value of the Mann–Whitney U test, using the normal
+/// approximation with tie and continuity correction.
+pub fn mann_whitney(a: &[f64], b: &[f64]) -> f64 {
+    let (n1, n2) = (a.len() as f64, b.len() as f64);
+    let mut values: Vec<(f64, bool)> = a
+        .iter()
+        .map(|&x| (x, true))
+        .chain(b.iter().map(|&x| (x, false)))
+        .collect();
+    values.sort_by(|x, y| x.0.total_cmp(&y.0));
+
+    let (mut rank_sum, mut ties) = (0.0, 0.0);
+    let mut start = 0;
+    while start < values.len() {
+        let end = start
+            + values[start..]
+                .iter()
+                .take_while(|x| x.0 == values[start].0)
+                .count();
+        let count = (end - start) as f64;
+        // tied values share the mean of the ranks they span
+        let rank = (start + end + 1) as f64 / 2.0;
+        rank_sum += rank * values[start..end].iter().filter(|x| x.1).count() as f64;
+        ties += count.powi(3) - count;
+        start = end;
+    }
+
+    let n = n1 + n2;
+    let u = rank_sum - n1 * (n1 + 1.0) / 2.0;
+    let mean = n1 * n2 / 2.0;
+    let variance = n1 * n2 / 12.0 * ((n + 1.0) - ties / (n * (n - 1.0)));
+    if variance <= 0.0 {
+        return 1.0;
+    }
+    let z = ((u - mean).abs() - 0.5).max(0.0) / variance.sqrt();
+    erfc(z / std::f64::consts::SQRT_2).min(1.0)
+}
+
+/// The complementary error function, accurate to about 1e-7.
+fn erfc(x: f64) -> f64 {
+    // Abramowitz and Stegun 7.1.26, for x >= 0
+    let t = 1.0 / (1.0 + 0.3275911 * x);
+    let poly = t
+        * (0.254829592
+            + t * (-0.284496736 + t * (1.421413741 + t * (-1.453152027 + t * 1.061405429))));
+    poly * (-x * x).exp()
+}
@@ -0,0 +1,116 @@
+//! Rendering the times as a standalone SVG bar chart.
+
+use std::io::{self, Write};
+
+use crate::{graph::fractions, html::escape, GraphScale, Product, TimeUnit};
+
+/// What the SVG chart shows besides the products.
+#[derive(Clone, Copy)]
+pub struct SvgOptions {
+    /// The seed the arrays were generated with.
+    pub seed: u64,
+    /// The number of elements in the first and the second array.
+    pub sizes: (usize, usize),
+    /// How the times are mapped to the length of the bars.
+    pub scale: GraphScale,
+    /// The unit of the duration labels.
+    pub time_unit: TimeUnit,
+}
+
+/// The height of the title and of every bar row, in pixels.
+const ROW_HEIGHT: usize = 20;
+/// The width of a character of the monospace font, in pixels.
+const CHAR_WIDTH: usize = 8;
+/// The width of a bar covering the whole graph, in pixels.
+const BAR_WIDTH: usize = 400;
+/// The space left after the longest bar for its duration label, in pixels.
+const LABEL_WIDTH: usize = 160;
+/// The space around the chart, in pixels.
+const MARGIN: usize = 10;
+
+/// Writes a horizontal bar per product, named on the left and labeled with
+/// its time at the end, under a title with the input sizes and the seed.
+///
+/// The bars have the lengths [`print_graph`](crate::print_graph) draws with
+/// the same scale, and products that timed out are drawn at full width.
+pub fn write_svg<T>(
+    out: &mut impl Write,
+    products: &[Product<T>],
+    options: &SvgOptions,
+) -> io::Result<()> {
+    let name_width = products
+        .iter()
+        .map(|p| p.name.chars().count())
+        .max()
+        .unwrap_or(0)
+        * CHAR_WIDTH;
+    let bars_x = MARGIN + name_width + CHAR_WIDTH;
+    let width = bars_x + BAR_WIDTH + LABEL_WIDTH + MARGIN;
+    let height = 2 * MARGIN + ROW_HEIGHT * (products.len().max(1) + 1);
+
+    writeln!(out, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>")?;
+    writeln!(
+        out,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" \
+         viewBox=\"0 0 {w} {h}\" font-family=\"monospace\" font-size=\"13\">",
+        w = width,
+        h = height
+    )?;
+    writeln!(out, "<rect width=\"100%\" height=\"100%\" fill=\"white\"/>")?;
+    writeln!(
+        out,
+        "<text x=\"{}\" y=\"{}\" font-weight=\"bold\">Times of arrays with the sizes {} \
+         and {} (seed {}) as a {} graph</text>",
+        MARGIN,
+        MARGIN + ROW_HEIGHT / 2 + 4,
+        options.sizes.0,
+        options.sizes.1,
+        options.seed,
+        options.scale.name()
+    )?;
+    if products.is_empty() {
+        writeln!(
+            out,
+            "<text x=\"{}\" y=\"{}\">No method ran.</text>",
+            MARGIN,
+            MARGIN + ROW_HEIGHT * 3 / 2 + 4
+        )?;
+    }
+    for (i, (product, fraction)) in products
+        .iter()
+        .zip(fractions(products, options.scale))
+        .enumerate()
+    {
+        let top = MARGIN + ROW_HEIGHT * (i + 1);
+        let text_y = top + ROW_HEIGHT / 2 + 4;
+        let time = |duration| options.time_unit.format(duration);
+        let (fraction, time) = match product.timed_out {
+            Some(limit) => (1.0, format!("&gt; {} (timed out)", time(limit))),
+            None => (fraction, time(product.time)),
+        };
+        let length = fraction * BAR_WIDTH as f64;
+        writeln!(
+            out,
+            "<text x=\"{}\" y=\"{}\" text-anchor=\"end\">{}</text>",
+            bars_x - CHAR_WIDTH,
+            text_y,
+            escape(&product.name)
+        )?;
+        writeln!(
+            out,
+            "<rect x=\"{}\" y=\"{}\" width=\"{:.2}\" height=\"{}\" fill=\"#4a78c2\"/>",
+            bars_x,
+            top + 3,
+            length,
+            ROW_HEIGHT - 6
+        )?;
+        writeln!(
+            out,
+            "<text x=\"{:.2}\" y=\"{}\">{}</text>",
+            bars_x as f64 + length + 4.0,
+            text_y,
+            time
+        )?;
+    }
+    writeln!(out, "</svg>")
+}
@@ -0,0 +1,313 @@
+//! Running the benchmark at several input sizes or thread counts.
+
+use std::{
+    fmt::Debug,
+    io::{self, Write},
+    time::Duration,
+};
+
+use prettytable::{Cell, Row, Table};
+use serde::Serialize;
+
+use crate::{
+    format_compact,
+    record::{ProductRecord, SCHEMA_VERSION},
+    Environment, Product, Scenario,
+};
+
+/// The fewest points a complexity can be estimated from.
+const MIN_FIT_POINTS: usize = 3;
+
+/// How much worse than the best curve another one may fit and still count as a tie.
+const TIE_TOLERANCE: f64 = 1e-9;
+
+/// A named growth curve as a function of the bigger and the smaller size.
+type Curve = (&'static str, fn(f64, f64) -> f64);
+
+/// The curves a [`Complexity`] can be closest to.
+const CURVES: [Curve; 4] = [
+    ("n", |n, _| n),
+    ("n log n", |n, _| n * n.log2().max(1.0)),
+    ("n*m", |n, m| n * m),
+    ("n+m", |n, m| n + m),
+];
+
+/// The products of running every method on arrays of one size.
+pub struct SweepPoint<T = usize> {
+    /// The number of elements in the bigger array.
+    pub big: usize,
+    /// The number of elements in the smaller array.
+    pub small: usize,
+    /// The products, including the skipped ones.
+    pub products: Vec<Product<T>>,
+}
+
+/// The product names in the order of the last point, followed by the ones
+/// that only ran at smaller sizes.
+pub(crate) fn names<T>(points: &[SweepPoint<T>]) -> Vec<&str> {
+    ordered_names(points.iter().map(|point| &point.products))
+}
+
+/// The names of the last products, followed by the ones that only occur
+/// earlier.
+fn ordered_names<'a, T: 'a>(
+    products: impl DoubleEndedIterator<Item = &'a Vec<Product<T>>>,
+) -> Vec<&'a str> {
+    let mut names: Vec<&str> = Vec::new();
+    for products in products.rev() {
+        for product in products {
+            if !names.contains(&product.name.as_str()) {
+                names.push(&product.name);
+            }
+        }
+    }
+    names
+}
+
+/// Prints a table with a row per product name and a column per point,
+/// holding the time the product took at that size.
+///
+/// Rows are ordered like the products of the last point, followed by the
+/// ones that only ran at smaller sizes.
+pub fn print_sweep<T>(out: &mut impl Write, points: &[SweepPoint<T>]) -> io::Result<()> {
+    let mut table = Table::new();
+    let header = points.iter().map(|p| format!("{} / {}", p.big, p.small));
+    table.add_row(Row::new(
+        ["Name".to_string()]
+            .into_iter()
+            .chain(header)
+            .map(|cell| Cell::new(&cell))
+            .collect(),
+    ));
+    for name in names(points) {
+        let times =
+            points.iter().map(
+                |point| match point.products.iter().find(|p| p.name == name) {
+                    Some(product) if product.skipped.is_some() => "skipped".to_string(),
+                    Some(product) => format_compact(product.time),
+                    None => "-".to_string(),
+                },
+            );
+        table.add_row(Row::new(
+            [name.to_string()]
+                .into_iter()
+                .chain(times)
+                .map(|cell| Cell::new(&cell))
+                .collect(),
+        ));
+    }
+    table.print(out)?;
+    Ok(())
+}
+
+/// The products of running every method in a thread pool of one size.
+pub struct ThreadPoint<T = usize> {
+    /// The number of threads in the pool.
+    pub threads: usize,
+    /// The products, including the skipped ones.
+    pub products: Vec<Product<T>>,
+}
+
+/// Prints a table with a row per product name and a column per thread count,
+/// holding the time the product took and its speedup over the first point.
+pub fn print_thread_sweep<T>(out: &mut impl Write, points: &[ThreadPoint<T>]) -> io::Result<()> {
+    let find = |point: &'_ ThreadPoint<T>, name: &str| {
+        point
+            .products
+            .iter()
+            .find(|p| p.name == name && p.skipped.is_none())
+            .map(|p| p.time)
+    };
+    let mut table = Table::new();
+    let header = points.iter().map(|point| match point.threads {
+        1 => "1 thread".to_string(),
+        threads => format!("{} threads", threads),
+    });
+    table.add_row(Row::new(
+        ["Name".to_string()]
+            .into_iter()
+            .chain(header)
+            .map(|cell| Cell::new(&cell))
+            .collect(),
+    ));
+    for name in ordered_names(points.iter().map(|point| &point.products)) {
+        let first = points.first().and_then(|point| find(point, name));
+        let times = points.iter().map(|point| match (find(point, name), first) {
+            (Some(time), Some(first)) if time > Duration::ZERO => format!(
+                "{} ({:.2}x)",
+                format_compact(time),
+                first.as_nanos() as f64 / time.as_nanos() as f64
+            ),
+            (Some(time), _) => format_compact(time),
+            (None, _) => "skipped".to_string(),
+        });
+        table.add_row(Row::new(
+            [name.to_string()]
+                .into_iter()
+                .chain(times)
+                .map(|cell| Cell::new(&cell))
+                .collect(),
+        ));
+    }
+    table.print(out)?;
+    Ok(())
+}
+
+/// How the time of one product grows with the size of the input.
+#[derive(Serialize)]
+pub struct Complexity {
+    /// The name of the product.
+    pub name: String,
+    /// The number of points the product ran at.
+    pub points: usize,
+    /// The exponent `k` of the best fitting `n^k`, where `n` is the size of
+    /// the bigger array, unless there were fewer than three points.
+    pub exponent: Option<f64>,
+    /// The candidate curve that fits the times best, like `n log n`.
+    pub closest: Option<&'static str>,
+}
+
+/// The slope of the least squares line through `points`, if their `x`
+/// values differ.
+fn slope(points: &[(f64, f64)]) -> Option<f64> {
+    let count = points.len() as f64;
+    let mean_x = points.iter().map(|p| p.0).sum::<f64>() / count;
+    let mean_y = points.iter().map(|p| p.1).sum::<f64>() / count;
+    let spread: f64 = points.iter().map(|p| (p.0 - mean_x).powi(2)).sum();
+    let covariance: f64 = points.iter().map(|p| (p.0 - mean_x) * (p.1 - mean_y)).sum();
+    (spread > 0.0).then(|| covariance / spread)
+}
+
+/// Estimates the complexity of every product from the points it ran at.
+///
+/// The exponent is the slope of the log-log line through the times. The
+/// closest curve is the one whose logarithm differs from the logarithm of the
+/// times by the most constant amount.
+pub fn estimate_complexity<T>(points: &[SweepPoint<T>]) -> Vec<Complexity> {
+    names(points)
+        .into_iter()
+        .map(|name| {
+            let samples: Vec<(f64, f64, Duration)> = points
+                .iter()
+                .filter_map(|point| {
+                    let product = point.products.iter().find(|p| p.name == name)?;
+                    (product.skipped.is_none() && product.time > Duration::ZERO).then_some((
+                        point.big as f64,
+                        point.small as f64,
+                        product.time,
+                    ))
+                })
+                .collect();
+            let log_time = |time: Duration| (time.as_nanos() as f64).ln();
+            let exponent = (samples.len() >= MIN_FIT_POINTS)
+                .then(|| {
+                    let log_log: Vec<(f64, f64)> = samples
+                        .iter()
+                        .map(|&(n, _, time)| (n.ln(), log_time(time)))
+                        .collect();
+                    slope(&log_log)
+                })
+                .flatten();
+            let closest = exponent.and_then(|_| {
+                let residuals: Vec<f64> = CURVES
+                    .iter()
+                    .map(|(_, f)| {
+                        let offsets = samples
+                            .iter()
+                            .map(|&(n, m, time)| log_time(time) - f(n, m).ln());
+                        let mean = offsets.clone().sum::<f64>() / samples.len() as f64;
+                        offsets.map(|x| (x - mean).powi(2)).sum()
+                    })
+                    .collect();
+                let best = residuals.iter().copied().fold(f64::INFINITY, f64::min);
+                // Curves that grow alike fit equally well, prefer the simpler one.
+                CURVES
+                    .iter()
+                    .zip(&residuals)
+                    .find(|(_, residual)| **residual <= best + TIE_TOLERANCE)
+                    .map(|((curve, _), _)| *curve)
+            });
+            Complexity {
+                name: name.to_string(),
+                points: samples.len(),
+                exponent,
+                closest,
+            }
+        })
+        .collect()
+}
+
+/// Prints the estimated complexity of every product, one per line.
+pub fn print_complexity(out: &mut impl Write, complexities: &[Complexity]) -> io::Result<()> {
+    writeln!(
+        out,
+        "estimated complexity, where n is the size of the bigger and m of the smaller array"
+    )?;
+    for complexity in complexities {
+        match (complexity.exponent, complexity.closest) {
+            (Some(exponent), Some(closest)) => writeln!(
+                out,
+                "{}: ~O(n^{:.2}), closest to O({})",
+                complexity.name, exponent, closest
+            )?,
+            _ => writeln!(
+                out,
+                "{}: insufficient data ({} of the {} points needed)",
+                complexity.name, complexity.points, MIN_FIT_POINTS
+            )?,
+        }
+    }
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct SweepSize {
+    big: usize,
+    small: usize,
+    products: Vec<ProductRecord>,
+}
+
+/// Everything about a sweep, serialized as the JSON report.
+#[derive(Serialize)]
+pub struct SweepReport<'a> {
+    schema_version: u32,
+    seed: u64,
+    sizes: Vec<SweepSize>,
+    complexity: &'a [Complexity],
+    all_equal: bool,
+    environment: Environment,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scenario: Option<Scenario>,
+}
+
+impl<'a> SweepReport<'a> {
+    /// Summarizes a sweep with the given seed.
+    pub fn new<T: Debug>(
+        seed: u64,
+        points: &'a [SweepPoint<T>],
+        complexity: &'a [Complexity],
+        all_equal: bool,
+    ) -> Self {
+        SweepReport {
+            schema_version: SCHEMA_VERSION,
+            seed,
+            sizes: points
+                .iter()
+                .map(|point| SweepSize {
+                    big: point.big,
+                    small: point.small,
+                    products: point.products.iter().map(ProductRecord::from).collect(),
+                })
+                .collect(),
+            complexity,
+            all_equal,
+            environment: Environment::current(),
+            scenario: None,
+        }
+    }
+
+    /// Records the scenario the settings of the sweep came from.
+    pub fn with_scenario(self, scenario: Option<Scenario>) -> Self {
+        SweepReport { scenario, ..self }
+    }
+}
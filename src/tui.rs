@@ -0,0 +1,168 @@
+//! Showing the results on the alternate screen while the benchmark runs.
+
+use std::{
+    cmp::Reverse,
+    io::{self, Write},
+    process::exit,
+    sync::{atomic::Ordering::Relaxed, mpsc},
+    thread,
+    time::Duration,
+};
+
+use crossterm::{
+    cursor::{Hide, MoveTo, MoveToNextLine, Show},
+    event::{self, Event, KeyCode, KeyEventKind, KeyModifiers},
+    execute, queue,
+    style::Print,
+    terminal::{
+        disable_raw_mode, enable_raw_mode, size, Clear, ClearType, EnterAlternateScreen,
+        LeaveAlternateScreen,
+    },
+};
+
+use crate::{
+    print_graph, print_table, run_benchmark_observed, BenchmarkOptions, Element, GraphOptions,
+    Intersect, Product, TableOptions,
+};
+
+/// How often the screen checks for input and new products.
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// The alternate screen in raw mode, restored when dropped.
+struct Screen;
+
+impl Screen {
+    fn enter() -> io::Result<Self> {
+        enable_raw_mode()?;
+        execute!(io::stdout(), EnterAlternateScreen, Hide)?;
+        Ok(Screen)
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        // nothing else can be done if the terminal can't be restored
+        let _ = execute!(io::stdout(), Show, LeaveAlternateScreen);
+        let _ = disable_raw_mode();
+    }
+}
+
+/// The products measured so far, without their results.
+#[derive(Default)]
+struct Live {
+    ran: Vec<Product>,
+    skipped: Vec<Product>,
+}
+
+impl Live {
+    fn add(&mut self, product: Product) {
+        if product.skipped.is_some() {
+            self.skipped.push(product);
+        } else {
+            self.ran.push(product);
+            self.ran.sort_by_key(|product| Reverse(product.time));
+        }
+    }
+
+    /// Draws the table and graph, cut off at the bottom of the screen.
+    fn draw(&self, graph: GraphOptions, finished: bool) -> io::Result<()> {
+        let (width, height) = size()?;
+        let mut text = Vec::new();
+        let done = self.ran.len() + self.skipped.len();
+        if finished {
+            writeln!(text, "{} done, press any key for the report", done)?;
+        } else {
+            writeln!(text, "{} done, running...", done)?;
+        }
+        if !self.ran.is_empty() {
+            writeln!(text)?;
+            print_table(
+                &mut text,
+                &self.ran,
+                &self.skipped,
+                &TableOptions::default(),
+                false,
+            )?;
+            let graph = GraphOptions {
+                width: width as usize,
+                ..graph
+            };
+            print_graph(&mut text, &self.ran, graph, false)?;
+        }
+        let mut out = io::stdout();
+        queue!(out, MoveTo(0, 0), Clear(ClearType::All))?;
+        for line in String::from_utf8_lossy(&text).lines().take(height as usize) {
+            queue!(out, Print(line), MoveToNextLine(1))?;
+        }
+        out.flush()
+    }
+}
+
+/// Runs the benchmark like [`run_benchmark_with`](crate::run_benchmark_with)
+/// while the alternate screen shows the table and graph of the products
+/// measured so far, then waits for a key press.
+///
+/// The graph is as wide as the terminal. Ctrl+C restores the terminal and
+/// sets [`BenchmarkOptions::interrupted`], returning the products measured
+/// once the running method finished, or exits the process with status 130
+/// if there is no flag to set.
+pub fn run_tui<T: Element>(
+    methods: &[Box<dyn Intersect<T>>],
+    a: &[T],
+    b: &[T],
+    options: &BenchmarkOptions,
+    graph: GraphOptions,
+) -> io::Result<Vec<Product<T>>> {
+    let screen = Screen::enter()?;
+    thread::scope(|scope| {
+        let (sender, receiver) = mpsc::channel();
+        let worker = scope.spawn(move || {
+            run_benchmark_observed(methods, a, b, options, |product| {
+                // the screen only goes away once the benchmark finished
+                let _ = sender.send(product.summary());
+            })
+        });
+        let mut live = Live::default();
+        let (mut dirty, mut finished) = (true, false);
+        loop {
+            for product in receiver.try_iter() {
+                live.add(product);
+                dirty = true;
+            }
+            if !finished && worker.is_finished() {
+                // products sent right before finishing are drawn next time
+                (finished, dirty) = (true, true);
+                continue;
+            }
+            if dirty {
+                live.draw(graph, finished)?;
+                dirty = false;
+            }
+            if !event::poll(POLL_INTERVAL)? {
+                continue;
+            }
+            match event::read()? {
+                Event::Key(key)
+                    if key.code == KeyCode::Char('c')
+                        && key.modifiers.contains(KeyModifiers::CONTROL) =>
+                {
+                    match &options.interrupted {
+                        Some(interrupted) => {
+                            interrupted.store(true, Relaxed);
+                            break;
+                        }
+                        None => {
+                            drop(screen);
+                            exit(130);
+                        }
+                    }
+                }
+                Event::Key(key) if finished && key.kind == KeyEventKind::Press => break,
+                Event::Resize(..) => dirty = true,
+                _ => {}
+            }
+        }
+        drop(screen);
+        Ok(worker.join().expect("the benchmark panicked"))
+    })
+}
@@ -0,0 +1,230 @@
+//! Checking that every method found the same elements.
+
+use std::io::{self, Write};
+
+use prettytable::{Cell, Row, Table};
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
+
+use crate::{benchmark::SWITCHED_ORDER, Element, Op, Product, Semantics};
+
+/// Sorts a result so that methods returning elements in different orders
+/// compare equal, and removes duplicates unless `multiset` is set.
+pub fn normalize<T: Ord + Clone>(result: &[T], multiset: bool) -> Vec<T> {
+    let mut result = result.to_vec();
+    result.sort_unstable();
+    if !multiset {
+        result.dedup();
+    }
+    result
+}
+
+/// How the result of a product differs from the reference result.
+struct Mismatch<'a, T> {
+    name: &'a str,
+    reference: &'a str,
+    reference_len: usize,
+    len: usize,
+    /// Only the counts were compared, because the elements weren't collected.
+    counted: bool,
+    missing: usize,
+    extra: usize,
+    missing_examples: Vec<T>,
+    extra_examples: Vec<T>,
+}
+
+impl<T> Mismatch<'_, T> {
+    /// The same elements were found, just with different duplicate counts.
+    fn only_duplicates(&self) -> bool {
+        !self.counted && self.missing == 0 && self.extra == 0
+    }
+}
+
+/// How many elements a product found, counting duplicates or not.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Multiplicity<'a> {
+    /// The name of the product.
+    pub name: &'a str,
+    /// The number of elements in the result.
+    pub len: usize,
+    /// The number of distinct elements in the result.
+    pub distinct: usize,
+}
+
+/// The outcome of comparing every result against a reference result.
+pub struct Verification<'a, T = usize> {
+    mismatches: Vec<Mismatch<'a, T>>,
+    multiplicities: Vec<Multiplicity<'a>>,
+}
+
+impl<'a, T: Element> Verification<'a, T> {
+    /// Compares every product that didn't time out against Hash, or the
+    /// first product if Hash didn't run.
+    ///
+    /// The results must contain every element as often as `semantics` asks
+    /// for. If `op` isn't symmetric, or the intersection keeps the duplicates
+    /// of the first array, the orders compute different results, so each
+    /// product is compared against Hash run in the same order instead.
+    pub fn new(products: &'a [Product<T>], op: Op, semantics: Semantics, examples: usize) -> Self {
+        let finished = || products.iter().filter(|p| p.timed_out.is_none());
+        let symmetric = match op {
+            Op::Intersection => semantics != Semantics::MultisetBig,
+            op => op.is_symmetric(),
+        };
+        let groups: Vec<Vec<&Product<T>>> = if symmetric {
+            vec![finished().collect()]
+        } else {
+            [false, true]
+                .into_iter()
+                .map(|switched| finished().filter(|p| p.switched == switched).collect())
+                .collect()
+        };
+        let mismatches = groups
+            .iter()
+            .flat_map(|group| Self::compare(group, examples))
+            .collect();
+        let collected: Vec<&Product<T>> = finished().filter(|p| p.count.is_none()).collect();
+        let multiplicities = collected
+            .par_iter()
+            .map(|product| Multiplicity {
+                name: &product.name,
+                len: product.result.len(),
+                distinct: normalize(&product.result, false).len(),
+            })
+            .collect();
+        Verification {
+            mismatches,
+            multiplicities,
+        }
+    }
+
+    fn compare(group: &[&'a Product<T>], examples: usize) -> Vec<Mismatch<'a, T>> {
+        let Some(reference) = ["Hash".to_string(), format!("Hash{}", SWITCHED_ORDER)]
+            .iter()
+            .find_map(|name| group.iter().find(|p| p.name == *name))
+            .or(group.first())
+        else {
+            return Vec::new();
+        };
+        if reference.count.is_some() {
+            return group
+                .iter()
+                .filter(|product| product.count != reference.count)
+                .map(|product| Mismatch {
+                    name: &product.name,
+                    reference: &reference.name,
+                    reference_len: reference.result_len(),
+                    len: product.result_len(),
+                    counted: true,
+                    missing: 0,
+                    extra: 0,
+                    missing_examples: Vec::new(),
+                    extra_examples: Vec::new(),
+                })
+                .collect();
+        }
+        let expected = normalize(&reference.result, true);
+        let mut expected_set = expected.clone();
+        expected_set.dedup();
+        group
+            .par_iter()
+            .filter_map(|product| {
+                let actual = normalize(&product.result, true);
+                if actual == expected {
+                    return None;
+                }
+                let mut actual_set = actual;
+                actual_set.dedup();
+                let missing: Vec<T> = expected_set
+                    .iter()
+                    .filter(|x| actual_set.binary_search(x).is_err())
+                    .cloned()
+                    .collect();
+                let extra: Vec<T> = actual_set
+                    .iter()
+                    .filter(|x| expected_set.binary_search(x).is_err())
+                    .cloned()
+                    .collect();
+                Some(Mismatch {
+                    name: &product.name,
+                    reference: &reference.name,
+                    reference_len: reference.result.len(),
+                    len: product.result.len(),
+                    counted: false,
+                    missing: missing.len(),
+                    extra: extra.len(),
+                    missing_examples: missing.into_iter().take(examples).collect(),
+                    extra_examples: extra.into_iter().take(examples).collect(),
+                })
+            })
+            .collect()
+    }
+
+    /// Whether every product found the same elements as the reference, with
+    /// the same duplicate counts.
+    pub fn passed(&self) -> bool {
+        self.mismatches.is_empty()
+    }
+
+    /// The names of the products whose results differ from their reference.
+    pub fn disagreeing(&self) -> Vec<&'a str> {
+        self.mismatches.iter().map(|m| m.name).collect()
+    }
+
+    /// The number of elements and distinct elements every product that
+    /// collected its result found, in the order of the products.
+    pub fn multiplicities(&self) -> &[Multiplicity<'a>] {
+        &self.multiplicities
+    }
+
+    /// Writes whether all values are equal, how the others differ, and the
+    /// [`Verification::multiplicities`] if any result holds duplicates.
+    pub fn write(&self, out: &mut impl Write) -> io::Result<()> {
+        writeln!(out, "\nall values are equal: {}", self.passed())?;
+        if self.multiplicities.iter().any(|m| m.len != m.distinct) {
+            writeln!(out, "the results hold duplicates:")?;
+            let mut table = Table::new();
+            table.add_row(Row::new(
+                ["Name", "Elements", "Distinct"]
+                    .iter()
+                    .map(|cell| Cell::new(cell))
+                    .collect(),
+            ));
+            for m in &self.multiplicities {
+                table.add_row(Row::new(vec![
+                    Cell::new(m.name),
+                    Cell::new(&m.len.to_string()),
+                    Cell::new(&m.distinct.to_string()),
+                ]));
+            }
+            table.print(out)?;
+        }
+        self.mismatches.iter().try_for_each(|m| {
+            if m.counted {
+                return writeln!(
+                    out,
+                    "{} counted {} elements instead of {} like {}",
+                    m.name, m.len, m.reference_len, m.reference
+                );
+            }
+            if m.only_duplicates() {
+                return writeln!(
+                    out,
+                    "{} found the same elements as {} with different duplicate counts ({} instead of {} elements)",
+                    m.name, m.reference, m.len, m.reference_len
+                );
+            }
+            writeln!(
+                out,
+                "{} disagrees with {}: {} elements instead of {}, {} missing, {} extra",
+                m.name, m.reference, m.len, m.reference_len, m.missing, m.extra
+            )?;
+            if m.missing > 0 {
+                writeln!(out, "  missing: {:?}", m.missing_examples)?;
+            }
+            if m.extra > 0 {
+                writeln!(out, "  extra: {:?}", m.extra_examples)?;
+            }
+            Ok(())
+        })
+    }
+}
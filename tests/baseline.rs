@@ -0,0 +1,128 @@
+mod common;
+
+use std::time::Duration;
+
+use common::product;
+use compare_speed::{Baseline, Environment, Preset, Product, SCHEMA_VERSION};
+
+#[test]
+fn baselines_round_trip_without_skipped_products() {
+    let products = [
+        product("Hash", 100),
+        Product::skipped("Simd".to_string(), "no AVX2".to_string()),
+    ];
+    let baseline = Baseline::new(3, (10, 20), &products);
+    assert_eq!(baseline.times.len(), 1);
+
+    let mut file = Vec::new();
+    baseline.write(&mut file).unwrap();
+    assert_eq!(Baseline::read(&file[..]).unwrap(), baseline);
+    assert!(Baseline::read(&b"{}"[..]).is_err());
+}
+
+#[test]
+fn slower_products_regress_above_the_threshold() {
+    let baseline = Baseline::new(
+        3,
+        (10, 20),
+        &[
+            product("Hash", 100),
+            product("Merge", 100),
+            product("Old", 1),
+        ],
+    );
+    let mut products = [
+        product("Hash", 105),
+        product("Merge", 150),
+        product("New", 1),
+    ];
+
+    let comparison = baseline.compare(&mut products, 3, (10, 20), Preset::Random, 10.0);
+    let hash = products[0].baseline.unwrap();
+    assert_eq!(hash.baseline, Duration::from_nanos(100));
+    assert!(!hash.regressed);
+    assert_eq!(hash.format(), "+5.00%");
+    assert_eq!(
+        products[1].baseline.unwrap().format(),
+        "+50.00% (regressed)"
+    );
+    assert_eq!(products[2].baseline, None);
+    assert_eq!(comparison.regressed, ["Merge"]);
+    assert_eq!(comparison.only_current, ["New"]);
+    assert_eq!(comparison.only_baseline, ["Old"]);
+    assert_eq!(comparison.other_input, None);
+    assert!(comparison.regressed());
+
+    let comparison = baseline.compare(&mut products, 4, (10, 20), Preset::Random, 60.0);
+    assert!(!comparison.regressed());
+    assert_eq!(comparison.other_input, Some((3, 10, 20)));
+    assert_eq!(comparison.other_preset, None);
+
+    let comparison = baseline.compare(&mut products, 3, (10, 20), Preset::Disjoint, 60.0);
+    assert_eq!(comparison.other_input, None);
+    assert_eq!(comparison.other_preset, Some(Preset::Random));
+    let mut out = Vec::new();
+    comparison.write(&mut out).unwrap();
+    assert!(String::from_utf8(out)
+        .unwrap()
+        .starts_with("note: the baseline ran on arrays of the random preset\n"));
+}
+
+#[test]
+fn baselines_from_another_environment_warn() {
+    let mut baseline = Baseline::new(3, (10, 20), &[product("Hash", 100)]);
+    let mut products = [product("Hash", 100)];
+    let comparison = baseline.compare(&mut products, 3, (10, 20), Preset::Random, 10.0);
+    assert!(comparison.other_environment.is_empty());
+
+    let current = Environment::current();
+    baseline.environment = Some(Environment {
+        threads: current.threads + 1,
+        profile: "other".to_string(),
+        ..current.clone()
+    });
+    let comparison = baseline.compare(&mut products, 3, (10, 20), Preset::Random, 10.0);
+    assert_eq!(
+        comparison.other_environment,
+        [
+            format!(
+                "threads: {} in the baseline, {} now",
+                current.threads + 1,
+                current.threads
+            ),
+            format!("build: other in the baseline, {} now", current.profile),
+        ]
+    );
+    let mut out = Vec::new();
+    comparison.write(&mut out).unwrap();
+    assert!(String::from_utf8(out)
+        .unwrap()
+        .contains("warning: the baseline ran in another environment"));
+
+    let old = br#"{"seed":3,"size_a":10,"size_b":20,"times":[]}"#;
+    assert_eq!(Baseline::read(&old[..]).unwrap().environment, None);
+}
+
+#[test]
+fn baselines_of_a_newer_schema_are_rejected() {
+    let old = br#"{"seed":3,"size_a":10,"size_b":20,"times":[{"name":"Hash","time_ns":5}]}"#;
+    let baseline = Baseline::read(&old[..]).unwrap();
+    assert_eq!(baseline.schema_version, 0);
+    assert_eq!(baseline.times[0].time_ns, 5);
+    assert_eq!(baseline.times[0].runs, 0);
+    assert_eq!(
+        Baseline::new(3, (10, 20), &[product("Hash", 100)]).schema_version,
+        SCHEMA_VERSION
+    );
+
+    let newer = format!(
+        r#"{{"schema_version":{},"seed":3,"size_a":10,"size_b":20,"times":[]}}"#,
+        SCHEMA_VERSION + 1
+    );
+    let error = Baseline::read(newer.as_bytes()).unwrap_err();
+    assert!(
+        error.contains("is newer than the supported version"),
+        "{}",
+        error
+    );
+}
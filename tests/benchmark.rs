@@ -0,0 +1,1079 @@
+use std::{
+    collections::HashSet,
+    sync::{
+        atomic::{AtomicBool, Ordering::Relaxed},
+        Arc,
+    },
+    time::Duration,
+};
+
+use compare_speed::{
+    generate, normalize, plan, run_benchmark, run_benchmark_many, run_benchmark_observed,
+    run_benchmark_with, run_benchmark_with_timeout, Aggregate, Api, Auto, BTree, BTreeBoth,
+    BenchmarkOptions, Binary, BinaryStable, Bitset, BloomHash, DirectIndex, Distribution, Flavor,
+    Galloping, Hash, HashA, HashFx, Intersect, Merge, Mode, Multiplicity, Op, Orders,
+    PartitionedHash, Retain, Semantics, Simd, Squared, SquaredBreak, Stability, StdIntersection,
+    Verification,
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+fn methods() -> Vec<Box<dyn Intersect>> {
+    vec![
+        Box::new(SquaredBreak),
+        Box::new(BTree),
+        Box::new(BTreeBoth),
+        Box::new(Binary),
+        Box::new(BinaryStable),
+        Box::new(Hash),
+        Box::new(HashFx),
+        Box::new(HashA),
+        Box::new(PartitionedHash::new(Some(3))),
+        Box::new(Merge),
+        Box::new(Galloping),
+        Box::new(Auto::default()),
+        Box::new(StdIntersection),
+        Box::new(Retain),
+        Box::new(Simd),
+        Box::new(Bitset::new(1)),
+        Box::new(DirectIndex::new(1)),
+        Box::new(BloomHash::new(0.01)),
+    ]
+}
+
+#[test]
+fn every_method_finds_the_same_elements() {
+    let a: Vec<usize> = (0..500).map(|x| x * 3 % 701).collect();
+    let b: Vec<usize> = (0..300).map(|x| x * 7 % 401).chain([5, 5, 9]).collect();
+    let mut expected: Vec<usize> = a.iter().filter(|x| b.contains(x)).copied().collect();
+    expected.sort_unstable();
+
+    let products = run_benchmark(&methods(), &a, &b);
+    assert_eq!(products.len(), methods().len() * 4);
+    for product in products.iter().filter(|p| p.skipped.is_none()) {
+        assert_eq!(
+            normalize(&product.result, false),
+            normalize(&expected, false),
+            "{}",
+            product.name
+        );
+    }
+}
+
+#[test]
+fn products_are_sorted_slowest_first_with_skipped_last() {
+    let a: Vec<usize> = (0..200).collect();
+    let b = vec![1, 2, usize::MAX];
+    for concurrent_methods in [false, true] {
+        let options = BenchmarkOptions {
+            concurrent_methods,
+            ..BenchmarkOptions::default()
+        };
+        let products = run_benchmark_with(&methods(), &a, &b, &options);
+
+        let ran = products.iter().take_while(|p| p.skipped.is_none()).count();
+        assert!(products[..ran].windows(2).all(|w| w[0].time >= w[1].time));
+        let skipped: Vec<&str> = products[ran..].iter().map(|p| p.name.as_str()).collect();
+        assert!(skipped.contains(&"Bitset"), "{:?}", skipped);
+        assert!(products[ran..].iter().all(|p| p.skipped.is_some()));
+    }
+}
+
+#[test]
+fn options_control_flavors_and_repeats() {
+    let options = BenchmarkOptions {
+        flavors: vec![Flavor::Sequential],
+        repeats: 3,
+        warmup: 0,
+        ..BenchmarkOptions::default()
+    };
+    let a: Vec<usize> = (0..100).collect();
+    let products = run_benchmark_with(
+        &[Box::new(Hash) as Box<dyn Intersect>],
+        &a,
+        &a[..10],
+        &options,
+    );
+
+    let mut names: Vec<&str> = products.iter().map(|p| p.name.as_str()).collect();
+    names.sort_unstable();
+    assert_eq!(names, ["Hash seq", "Hash seq switched order"]);
+    assert!(products.iter().all(|p| p.samples.len() == 3));
+}
+
+#[test]
+fn empty_input_is_handled_by_every_method() {
+    let values: Vec<usize> = (0..50).collect();
+    for (a, b) in [
+        (&[][..], &values[..]),
+        (&values[..], &[][..]),
+        (&[][..], &[][..]),
+    ] {
+        for product in run_benchmark(&methods(), a, b) {
+            assert!(product.result.is_empty(), "{}", product.name);
+        }
+    }
+}
+
+#[test]
+fn strings_can_be_benchmarked() {
+    let methods: Vec<Box<dyn Intersect<String>>> = vec![
+        Box::new(Squared),
+        Box::new(SquaredBreak),
+        Box::new(BTree),
+        Box::new(Binary),
+        Box::new(Hash),
+        Box::new(Merge),
+        Box::new(Galloping),
+    ];
+    let a: Vec<String> = (0..300).map(|x| format!("id-{}", x * 7 % 101)).collect();
+    let b: Vec<String> = (0..80).map(|x| format!("id-{}", x * 3)).collect();
+
+    let products = run_benchmark(&methods, &a, &b);
+    assert_eq!(products.len(), methods.len() * 4);
+    assert!(Verification::new(&products, Op::Intersection, Semantics::MultisetBig, 5).passed());
+    let hash = products.iter().find(|p| p.name == "Hash").unwrap();
+    assert!(normalize(&hash.result, false).contains(&"id-42".to_string()));
+}
+
+struct Presorted;
+
+impl Intersect for Presorted {
+    fn name(&self) -> String {
+        "Binary search (pre-sorted)".to_string()
+    }
+
+    fn intersect(&self, big: &[usize], small: &[usize]) -> Vec<usize> {
+        self.intersect_seq(big, small)
+    }
+
+    fn intersect_seq(&self, big: &[usize], small: &[usize]) -> Vec<usize> {
+        big.iter()
+            .filter(|x| small.binary_search(x).is_ok())
+            .copied()
+            .collect()
+    }
+}
+
+#[test]
+#[should_panic(expected = "the false positive rate must be between 0 and 1, not 0")]
+fn bloom_filters_need_a_false_positive_rate_below_1() {
+    BloomHash::new(0.0);
+}
+
+#[test]
+fn methods_are_named_by_name() {
+    let methods: Vec<Box<dyn Intersect>> = vec![
+        Box::new(Presorted),
+        Box::new(Hash),
+        Box::new(BloomHash::new(0.05)),
+    ];
+    let options = BenchmarkOptions {
+        flavors: vec![Flavor::Parallel],
+        ..BenchmarkOptions::default()
+    };
+    let a: Vec<usize> = (0..100).collect();
+    let mut names: Vec<String> = run_benchmark_with(&methods, &a, &a[..10], &options)
+        .into_iter()
+        .map(|p| p.name)
+        .collect();
+    names.sort_unstable();
+    assert_eq!(
+        names,
+        [
+            "Binary search (pre-sorted)",
+            "Binary search (pre-sorted) switched order",
+            "BloomHash(0.05)",
+            "BloomHash(0.05) switched order",
+            "Hash",
+            "Hash switched order",
+        ]
+    );
+}
+
+#[test]
+fn interleaved_repeats_measure_the_same_products() {
+    let a: Vec<usize> = (0..400).map(|x| x * 7 % 331).collect();
+    let b: Vec<usize> = (0..120).map(|x| x * 3).collect();
+    let grouped = BenchmarkOptions {
+        repeats: 3,
+        ..BenchmarkOptions::default()
+    };
+    let interleaved = BenchmarkOptions {
+        interleave: Some(5),
+        ..grouped.clone()
+    };
+    let sorted = |options| {
+        let mut products = run_benchmark_with(&methods(), &a, &b, options);
+        products.sort_by(|x, y| x.name.cmp(&y.name));
+        products
+    };
+
+    let (grouped, interleaved) = (sorted(&grouped), sorted(&interleaved));
+    assert_eq!(grouped.len(), interleaved.len());
+    for (expected, product) in grouped.iter().zip(&interleaved) {
+        assert_eq!(product.name, expected.name);
+        assert_eq!(product.skipped, expected.skipped);
+        assert_eq!(product.result, expected.result, "{}", product.name);
+        if product.skipped.is_none() {
+            assert_eq!(product.samples.len(), 3, "{}", product.name);
+        }
+    }
+}
+
+#[test]
+fn union_is_the_same_set_for_every_method() {
+    let a: Vec<usize> = (0..400).map(|x| x * 5 % 301).collect();
+    let b: Vec<usize> = (0..150).map(|x| x % 90 + 250).collect();
+    let mut expected: Vec<usize> = a.iter().chain(&b).copied().collect();
+    expected.sort_unstable();
+    expected.dedup();
+
+    let options = BenchmarkOptions {
+        op: Op::Union,
+        ..BenchmarkOptions::default()
+    };
+    let products = run_benchmark_with(&methods(), &a, &b, &options);
+    let (ran, skipped): (Vec<_>, Vec<_>) = products.iter().partition(|p| p.skipped.is_none());
+
+    assert_eq!(ran.len(), 5 * 4);
+    for product in ran {
+        let mut result = product.result.clone();
+        result.sort_unstable();
+        assert_eq!(result, expected, "{}", product.name);
+    }
+    assert!(skipped
+        .iter()
+        .all(|p| p.skipped.as_deref() == Some("does not support the union")));
+    assert!(skipped.iter().any(|p| p.name == "Simd"));
+}
+
+#[test]
+fn difference_depends_on_the_order() {
+    let a: Vec<usize> = (0..300).map(|x| x % 120).collect();
+    let b: Vec<usize> = (100..160).collect();
+    let options = BenchmarkOptions {
+        op: Op::Difference,
+        ..BenchmarkOptions::default()
+    };
+    let (products, _): (Vec<_>, Vec<_>) = run_benchmark_with(&methods(), &a, &b, &options)
+        .into_iter()
+        .partition(|p| p.skipped.is_none());
+
+    let bigger_minus_smaller: Vec<usize> = (0..100).collect();
+    let smaller_minus_bigger: Vec<usize> = (120..160).collect();
+    for product in &products {
+        let mut result = product.result.clone();
+        result.sort_unstable();
+        let expected = if product.switched {
+            &smaller_minus_bigger
+        } else {
+            &bigger_minus_smaller
+        };
+        assert_eq!(&result, expected, "{}", product.name);
+        assert_eq!(product.switched, product.name.ends_with("switched order"));
+    }
+    assert!(Verification::new(&products, Op::Difference, Semantics::MultisetBig, 5).passed());
+}
+
+#[test]
+fn symmetric_difference_is_the_same_in_both_orders() {
+    let a: Vec<usize> = (0..300).map(|x| x % 120).collect();
+    let b: Vec<usize> = (100..160).chain(100..110).collect();
+    let options = BenchmarkOptions {
+        op: Op::SymmetricDifference,
+        ..BenchmarkOptions::default()
+    };
+    let (products, _): (Vec<_>, Vec<_>) = run_benchmark_with(&methods(), &a, &b, &options)
+        .into_iter()
+        .partition(|p| p.skipped.is_none());
+
+    let expected: Vec<usize> = (0..100).chain(120..160).collect();
+    for product in &products {
+        let mut result = product.result.clone();
+        result.sort_unstable();
+        assert_eq!(result, expected, "{}", product.name);
+    }
+    assert!(Verification::new(
+        &products,
+        Op::SymmetricDifference,
+        Semantics::MultisetBig,
+        5
+    )
+    .passed());
+}
+
+#[test]
+fn count_only_counts_the_intersection() {
+    let a: Vec<usize> = (0..500).map(|x| x * 3 % 701).collect();
+    let b: Vec<usize> = (0..300).map(|x| x * 7 % 401).collect();
+    let expected = a.iter().filter(|x| b.contains(x)).count();
+    let options = BenchmarkOptions {
+        count_only: true,
+        ..BenchmarkOptions::default()
+    };
+    let mut methods = methods();
+    methods.push(Box::new(Squared));
+
+    for product in run_benchmark_with(&methods, &a, &b, &options) {
+        assert!(product.result.is_empty(), "{}", product.name);
+        if product.skipped.is_none() && !product.switched {
+            assert_eq!(product.count, Some(expected), "{}", product.name);
+        }
+    }
+}
+
+#[test]
+fn pre_sorted_input_finds_the_same_elements() {
+    let mut a: Vec<usize> = (0..500).map(|x| x * 3 % 701).collect();
+    let mut b: Vec<usize> = (0..300).map(|x| x * 7 % 401).collect();
+    a.sort_unstable();
+    b.sort_unstable();
+    let expected: Vec<usize> = a.iter().filter(|x| b.contains(x)).copied().collect();
+    let options = BenchmarkOptions {
+        pre_sorted: true,
+        ..BenchmarkOptions::default()
+    };
+
+    for product in run_benchmark_with(&methods(), &a, &b, &options) {
+        if product.skipped.is_none() {
+            assert_eq!(
+                normalize(&product.result, false),
+                expected,
+                "{}",
+                product.name
+            );
+        }
+    }
+}
+
+#[test]
+fn quadratic_methods_are_skipped_above_the_limit() {
+    let methods: Vec<Box<dyn Intersect>> =
+        vec![Box::new(Squared), Box::new(SquaredBreak), Box::new(Hash)];
+    let options = BenchmarkOptions {
+        quadratic_limit: Some(50),
+        ..BenchmarkOptions::default()
+    };
+    let a: Vec<usize> = (0..100).collect();
+
+    let products = run_benchmark_with(&methods, &a, &a[..10], &options);
+    let skipped: Vec<&str> = products
+        .iter()
+        .filter(|p| p.skipped.is_some())
+        .map(|p| p.name.as_str())
+        .collect();
+    assert_eq!(skipped.len(), 8);
+    assert!(skipped.iter().all(|name| name.starts_with("Squared")));
+
+    let products = run_benchmark_with(&methods, &a[..50], &a[..10], &options);
+    assert!(products.iter().all(|p| p.skipped.is_none()));
+}
+
+#[test]
+fn phases_are_timed_apart_with_the_same_result() {
+    let a: Vec<usize> = (0..500).map(|x| x * 3 % 701).collect();
+    let b: Vec<usize> = (0..300).map(|x| x * 7 % 401).chain([5, 5, 9]).collect();
+    let mut expected: Vec<usize> = a.iter().filter(|x| b.contains(x)).copied().collect();
+    expected.sort_unstable();
+    let mut methods = methods();
+    methods.push(Box::new(Squared));
+    let options = BenchmarkOptions {
+        phases: true,
+        ..BenchmarkOptions::default()
+    };
+
+    for product in run_benchmark_with(&methods, &a, &b, &options) {
+        if product.skipped.is_some() {
+            assert_eq!(product.phases, None);
+            continue;
+        }
+        let phases = product.phases.unwrap();
+        assert!(
+            phases.setup + phases.probe <= product.time,
+            "{}",
+            product.name
+        );
+        if product.name.starts_with("Squared") || product.name.starts_with("Merge") {
+            assert_eq!(phases.setup, Duration::ZERO, "{}", product.name);
+        }
+        assert_eq!(
+            normalize(&product.result, false),
+            normalize(&expected, false),
+            "{}",
+            product.name
+        );
+    }
+
+    let count_only = BenchmarkOptions {
+        count_only: true,
+        ..options
+    };
+    let products = run_benchmark_with(&methods, &a, &b, &count_only);
+    assert!(products.iter().all(|p| p.phases.is_none()));
+}
+
+#[test]
+fn aggregates_combine_the_samples() {
+    let samples: Vec<Duration> = [1, 2, 3, 4, 5, 6, 7, 8, 9, 100]
+        .map(Duration::from_nanos)
+        .to_vec();
+    let of = |aggregate: Aggregate| aggregate.of(&samples).as_nanos();
+    assert_eq!(of(Aggregate::Min), 1);
+    assert_eq!(of(Aggregate::Median), 5);
+    assert_eq!(of(Aggregate::Mean), 14);
+    assert_eq!(of(Aggregate::TrimmedMean), 5);
+    assert_eq!(Aggregate::TrimmedMean.of(&samples[..3]).as_nanos(), 2);
+
+    let options = BenchmarkOptions {
+        repeats: 5,
+        aggregate: Aggregate::Min,
+        ..BenchmarkOptions::default()
+    };
+    let a: Vec<usize> = (0..1000).collect();
+    let products = run_benchmark_with(&methods(), &a, &a[..100], &options);
+    for product in products.iter().filter(|p| p.skipped.is_none()) {
+        assert_eq!(product.time, product.samples[0], "{}", product.name);
+        assert_eq!(product.aggregate, Aggregate::Min);
+    }
+    let mut ran: Vec<_> = products.iter().filter(|p| p.skipped.is_none()).collect();
+    ran.dedup_by_key(|p| p.time);
+    assert!(ran.windows(2).all(|pair| pair[0].time > pair[1].time));
+}
+
+struct Sleepy;
+
+impl Intersect for Sleepy {
+    fn intersect(&self, big: &[usize], small: &[usize]) -> Vec<usize> {
+        self.intersect_seq(big, small)
+    }
+
+    fn intersect_seq(&self, _big: &[usize], _small: &[usize]) -> Vec<usize> {
+        std::thread::sleep(Duration::from_secs(60));
+        Vec::new()
+    }
+}
+
+#[test]
+fn methods_running_past_the_timeout_are_abandoned() {
+    let methods: Vec<Box<dyn Intersect>> = vec![Box::new(Sleepy), Box::new(Hash)];
+    let a: Vec<usize> = (0..1000).collect();
+    let timeout = Duration::from_millis(100);
+    let products = run_benchmark_with_timeout(
+        Box::leak(Box::new(methods)),
+        a.leak(),
+        &[1, 2, 3],
+        &BenchmarkOptions::default(),
+        timeout,
+    );
+
+    assert_eq!(products.len(), 8);
+    let (timed_out, finished) = products.split_at(4);
+    for product in timed_out {
+        assert!(product.name.starts_with("Sleepy"), "{}", product.name);
+        assert_eq!(product.timed_out, Some(timeout));
+        assert_eq!(product.time, timeout);
+        assert!(product.skipped.is_none());
+    }
+    for product in finished {
+        assert_eq!(product.timed_out, None, "{}", product.name);
+        assert_eq!(normalize(&product.result, false), [1, 2, 3]);
+    }
+    assert!(Verification::new(&products, Op::Intersection, Semantics::MultisetBig, 5).passed());
+}
+
+#[test]
+fn every_method_honors_the_semantics() {
+    let mut methods = methods();
+    methods.push(Box::new(Squared));
+    let big = [1, 1, 2, 5];
+    let small = [1, 2, 2];
+    for (semantics, normal, switched) in [
+        (Semantics::Set, vec![1, 2], vec![1, 2]),
+        (Semantics::MultisetBig, vec![1, 1, 2], vec![1, 2, 2]),
+        (Semantics::Pairs, vec![1, 1, 2, 2], vec![1, 1, 2, 2]),
+    ] {
+        for count_only in [false, true] {
+            let options = BenchmarkOptions {
+                semantics,
+                count_only,
+                ..BenchmarkOptions::default()
+            };
+            let (products, _): (Vec<_>, Vec<_>) =
+                run_benchmark_with(&methods, &big, &small, &options)
+                    .into_iter()
+                    .partition(|p| p.skipped.is_none());
+            for product in &products {
+                let expected = if product.switched { &switched } else { &normal };
+                if count_only {
+                    assert_eq!(product.count, Some(expected.len()), "{}", product.name);
+                } else {
+                    assert_eq!(
+                        normalize(&product.result, true),
+                        *expected,
+                        "{:?} {}",
+                        semantics,
+                        product.name
+                    );
+                }
+            }
+            assert!(Verification::new(&products, Op::Intersection, semantics, 5).passed());
+        }
+    }
+}
+
+#[test]
+fn every_method_reports_its_duplicates() {
+    let mut methods = methods();
+    methods.push(Box::new(Squared));
+    let big = [1, 1, 2, 3, 3, 3];
+    let small = [1, 3, 3, 4];
+    for (semantics, len) in [
+        (Semantics::Set, 2),
+        (Semantics::MultisetBig, 5),
+        (Semantics::Pairs, 8),
+    ] {
+        let options = BenchmarkOptions {
+            flavors: vec![Flavor::Sequential],
+            orders: Orders::Normal,
+            semantics,
+            ..BenchmarkOptions::default()
+        };
+        let products: Vec<_> = run_benchmark_with(&methods, &big, &small, &options)
+            .into_iter()
+            .filter(|p| p.skipped.is_none())
+            .collect();
+        let verification = Verification::new(&products, Op::Intersection, semantics, 5);
+        assert!(verification.passed());
+        assert_eq!(verification.multiplicities().len(), products.len());
+        for (multiplicity, product) in verification.multiplicities().iter().zip(&products) {
+            assert_eq!(
+                *multiplicity,
+                Multiplicity {
+                    name: &product.name,
+                    len,
+                    distinct: 2
+                },
+                "{:?}",
+                semantics
+            );
+        }
+    }
+}
+
+#[test]
+fn every_method_computes_every_semantics() {
+    let mut methods = methods();
+    methods.extend([
+        Box::new(Squared) as Box<dyn Intersect>,
+        Box::new(Auto::new(100, 0)),
+    ]);
+    #[cfg(feature = "roaring")]
+    methods.push(Box::new(compare_speed::Roaring));
+    let big = [3, 1, 1, 2, 5, 1, 2];
+    let small = [2, 1, 2, 4];
+    for (semantics, expected) in [
+        (Semantics::Set, vec![1, 2]),
+        (Semantics::MultisetBig, vec![1, 1, 1, 2, 2]),
+        (Semantics::Pairs, vec![1, 1, 1, 2, 2, 2, 2]),
+    ] {
+        for method in &methods {
+            let name = format!("{:?} {}", semantics, method.name_for(&big, &small));
+            assert!(method.honors(semantics), "{}", name);
+            let results = [
+                method.intersect_as(semantics, &big, &small),
+                method.intersect_as_seq(semantics, &big, &small),
+            ];
+            for result in results {
+                assert_eq!(normalize(&result, true), expected, "{}", name);
+            }
+            assert_eq!(
+                method.intersect_count_as(semantics, &big, &small),
+                expected.len(),
+                "{}",
+                name
+            );
+            assert_eq!(
+                method.intersect_count_as_seq(semantics, &big, &small),
+                expected.len(),
+                "{}",
+                name
+            );
+        }
+    }
+}
+
+#[test]
+fn every_method_computes_every_semantics_on_blocks() {
+    // long enough for the blocks of Simd and several buckets and chunks
+    let big: Vec<usize> = (0..200).map(|x| x * 7 % 50).collect();
+    let small: Vec<usize> = (0..90).map(|x| x * 3 % 40).collect();
+    for semantics in [Semantics::Set, Semantics::MultisetBig, Semantics::Pairs] {
+        let expected = normalize(&Squared.intersect_as(semantics, &big, &small), true);
+        for method in methods() {
+            let name = format!("{:?} {}", semantics, method.name());
+            for result in [
+                method.intersect_as(semantics, &big, &small),
+                method.intersect_as_seq(semantics, &big, &small),
+            ] {
+                assert_eq!(normalize(&result, true), expected, "{}", name);
+            }
+        }
+    }
+}
+
+/// Returns every distinct element once, and only knows that semantics.
+struct Distinct;
+
+impl Intersect for Distinct {
+    fn intersect(&self, big: &[usize], small: &[usize]) -> Vec<usize> {
+        self.intersect_seq(big, small)
+    }
+
+    fn intersect_seq(&self, big: &[usize], small: &[usize]) -> Vec<usize> {
+        let big: HashSet<usize> = big.iter().copied().collect();
+        let mut result: Vec<usize> = small.iter().filter(|x| big.contains(x)).copied().collect();
+        result.sort_unstable();
+        result.dedup();
+        result
+    }
+
+    fn keeps_duplicates(&self) -> bool {
+        false
+    }
+}
+
+#[test]
+fn methods_that_dont_compute_the_semantics_are_skipped() {
+    let methods: Vec<Box<dyn Intersect>> = vec![Box::new(Hash), Box::new(Distinct)];
+    let big = [1, 1, 2, 5];
+    let small = [1, 2, 2];
+    for (semantics, skipped) in [
+        (Semantics::Set, None),
+        (Semantics::MultisetBig, Some("Distinct seq")),
+        (Semantics::Pairs, Some("Distinct seq")),
+    ] {
+        let options = BenchmarkOptions {
+            flavors: vec![Flavor::Sequential],
+            orders: Orders::Normal,
+            semantics,
+            ..BenchmarkOptions::default()
+        };
+        let products = run_benchmark_with(&methods, &big, &small, &options);
+        let names: Vec<&str> = products.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, ["Hash seq", "Distinct seq"]);
+        assert_eq!(plan(&methods, 2, &options).0, names);
+        let found = products.iter().find(|p| p.skipped.is_some());
+        assert_eq!(found.map(|p| p.name.as_str()), skipped, "{:?}", semantics);
+    }
+}
+
+#[test]
+fn iterators_only_run_with_the_default_semantics() {
+    let methods: Vec<Box<dyn Intersect>> = vec![Box::new(Hash), Box::new(Retain)];
+    let options = BenchmarkOptions {
+        flavors: vec![Flavor::Sequential],
+        orders: Orders::Normal,
+        semantics: Semantics::Set,
+        api: Api::Iter,
+        ..BenchmarkOptions::default()
+    };
+    let products = run_benchmark_with(&methods, &[1, 1, 2, 5], &[1, 2, 2], &options);
+    for product in &products {
+        assert_eq!(
+            product.skipped.as_deref(),
+            Some("iterators only yield the multiset-big semantics"),
+            "{}",
+            product.name
+        );
+    }
+}
+
+/// Counts the pairs it is asked for without ever collecting an intersection.
+struct CountsPairs;
+
+impl Intersect for CountsPairs {
+    fn intersect(&self, _big: &[usize], _small: &[usize]) -> Vec<usize> {
+        panic!("collected the intersection")
+    }
+
+    fn intersect_seq(&self, _big: &[usize], _small: &[usize]) -> Vec<usize> {
+        panic!("collected the intersection")
+    }
+
+    fn honors(&self, _semantics: Semantics) -> bool {
+        true
+    }
+
+    fn intersect_count_as(&self, semantics: Semantics, _big: &[usize], _small: &[usize]) -> usize {
+        assert_eq!(semantics, Semantics::Pairs);
+        42
+    }
+
+    fn intersect_count_as_seq(
+        &self,
+        semantics: Semantics,
+        big: &[usize],
+        small: &[usize],
+    ) -> usize {
+        self.intersect_count_as(semantics, big, small)
+    }
+}
+
+#[test]
+fn counting_with_semantics_a_method_honors_doesnt_collect() {
+    let options = BenchmarkOptions {
+        count_only: true,
+        semantics: Semantics::Pairs,
+        ..BenchmarkOptions::default()
+    };
+    let methods: Vec<Box<dyn Intersect>> = vec![Box::new(CountsPairs)];
+    let products = run_benchmark_with(&methods, &[1, 2], &[2], &options);
+    assert_eq!(products.len(), 4);
+    for product in &products {
+        assert_eq!(product.count, Some(42), "{}", product.name);
+    }
+}
+
+#[test]
+fn iterators_yield_what_the_sequential_flavor_returns() {
+    let mut methods = methods();
+    methods.push(Box::new(Squared));
+    let big = [5, 1, 1, 3, 9, 3, 12, 7];
+    let small = [3, 1, 7, 3, 20];
+    for method in &methods {
+        for (big, small) in [(&big[..], &small[..]), (&small[..], &big[..])] {
+            let yielded: Vec<usize> = method.intersect_iter(big, small).collect();
+            assert_eq!(
+                normalize(&yielded, true),
+                normalize(&method.intersect_seq(big, small), true),
+                "{}",
+                method.name()
+            );
+        }
+    }
+}
+
+#[test]
+fn iter_api_counts_the_sequential_flavor() {
+    let a: Vec<usize> = (0..300).map(|x| x * 7 % 250).collect();
+    let b: Vec<usize> = (100..180).collect();
+    let options = BenchmarkOptions {
+        api: Api::Iter,
+        ..BenchmarkOptions::default()
+    };
+    let (products, _): (Vec<_>, Vec<_>) = run_benchmark_with(&methods(), &a, &b, &options)
+        .into_iter()
+        .partition(|p| p.skipped.is_none());
+
+    assert!(!products.is_empty());
+    for product in &products {
+        assert!(product.name.contains(" seq"), "{}", product.name);
+        assert!(product.result.is_empty(), "{}", product.name);
+        let expected = if product.switched {
+            b.iter().filter(|x| a.contains(x)).count()
+        } else {
+            a.iter().filter(|x| b.contains(x)).count()
+        };
+        assert_eq!(product.count, Some(expected), "{}", product.name);
+    }
+    assert!(Verification::new(&products, Op::Intersection, Semantics::MultisetBig, 5).passed());
+}
+
+#[test]
+fn many_arrays_intersect_to_their_distinct_shared_elements() {
+    let arrays: Vec<Vec<usize>> = vec![
+        (0..400).map(|x| x % 150).collect(),
+        (0..90).map(|x| x * 2).collect(),
+        (0..300).map(|x| x * 3 % 200).collect(),
+        (50..500).rev().collect(),
+    ];
+    let expected: Vec<usize> = (0..150)
+        .filter(|x| arrays.iter().all(|array| array.contains(x)))
+        .collect();
+    let mut methods = methods();
+    methods.push(Box::new(Squared));
+    let (ran, skipped): (Vec<_>, Vec<_>) =
+        run_benchmark_many(&methods, &arrays, &BenchmarkOptions::default())
+            .into_iter()
+            .partition(|p| p.skipped.is_none());
+
+    assert!(!expected.is_empty());
+    for product in &ran {
+        assert!(!product.switched, "{}", product.name);
+        assert_eq!(
+            normalize(&product.result, true),
+            expected,
+            "{}",
+            product.name
+        );
+    }
+    assert!(skipped.iter().any(|p| p.name == "Squared"
+        && p.skipped.as_deref() == Some("quadratic methods only intersect two arrays")));
+    assert!(Verification::new(&ran, Op::Intersection, Semantics::Set, 5).passed());
+    assert_eq!(
+        normalize(&Squared.intersect_many(&arrays[..2]), true),
+        normalize(&Hash.intersect_many_seq(&arrays[..2]), true)
+    );
+}
+
+#[test]
+fn probing_counts_the_queries_that_hit() {
+    let small: Vec<usize> = (0..300).map(|x| x * 5).collect();
+    let queries: Vec<usize> = (0..2000).collect();
+    let options = BenchmarkOptions {
+        mode: Mode::Probe,
+        repeats: 3,
+        ..BenchmarkOptions::default()
+    };
+    let (ran, skipped): (Vec<_>, Vec<_>) =
+        run_benchmark_with(&methods(), &queries, &small, &options)
+            .into_iter()
+            .partition(|p| p.skipped.is_none());
+
+    // the queries come first even though there are more of them
+    assert!(!ran.is_empty());
+    for product in &ran {
+        assert_eq!(product.count, Some(300), "{}", product.name);
+        assert!(!product.switched, "{}", product.name);
+        let phases = product.phases.expect("probing times the setup apart");
+        assert_eq!(product.time, phases.probe, "{}", product.name);
+    }
+    assert!(skipped.iter().any(|p| p.name == "Merge seq"
+        && p.skipped.as_deref() == Some("builds no structure to answer queries with")));
+    assert!(Verification::new(&ran, Op::Intersection, Semantics::MultisetBig, 5).passed());
+}
+
+#[test]
+fn interrupting_returns_the_products_measured_so_far() {
+    let a: Vec<usize> = (0..1000).collect();
+    let interrupted = Arc::new(AtomicBool::new(false));
+    let options = BenchmarkOptions {
+        repeats: 3,
+        interrupted: Some(Arc::clone(&interrupted)),
+        ..BenchmarkOptions::default()
+    };
+    let products = run_benchmark_observed(&methods(), &a, &a[..100], &options, |_| {
+        interrupted.store(true, Relaxed)
+    });
+
+    assert_eq!(products.len(), 1);
+    assert!(options.interrupted());
+    assert_eq!(products[0].samples.len(), 3);
+    assert!(run_benchmark_with(&methods(), &a, &a, &options).is_empty());
+}
+
+#[test]
+fn orders_pick_which_order_runs() {
+    let a: Vec<usize> = (0..100).collect();
+    for (orders, expected) in [
+        (Orders::Normal, vec!["Hash seq"]),
+        (Orders::Switched, vec!["Hash seq switched order"]),
+        (Orders::Both, vec!["Hash seq", "Hash seq switched order"]),
+    ] {
+        let options = BenchmarkOptions {
+            flavors: vec![Flavor::Sequential],
+            orders,
+            ..BenchmarkOptions::default()
+        };
+        let products = run_benchmark_with(
+            &[Box::new(Hash) as Box<dyn Intersect>],
+            &a,
+            &a[..10],
+            &options,
+        );
+        let mut names: Vec<&str> = products.iter().map(|p| p.name.as_str()).collect();
+        names.sort_unstable();
+        assert_eq!(names, expected);
+        assert!(products.iter().all(|p| p.pair_name() == "Hash seq"));
+    }
+}
+
+/// The lengths of the results don't depend on the profile, so running the
+/// tests with `--release` as well catches work the optimizer dropped.
+#[test]
+fn result_lengths_match_a_fixed_seed() {
+    let mut rng = StdRng::seed_from_u64(11);
+    let a = generate(&mut rng, Some(2000), Distribution::Uniform, Some(1500));
+    let b = generate(&mut rng, Some(1000), Distribution::Uniform, Some(1500));
+    // recorded from a debug build, instead of computed by code the optimizer
+    // could drop work from just the same
+    let (normal, switched) = (988, 787);
+    let mut methods = methods();
+    methods.push(Box::new(Squared));
+    for count_only in [false, true] {
+        let options = BenchmarkOptions {
+            count_only,
+            warmup: 2,
+            ..BenchmarkOptions::default()
+        };
+        for product in run_benchmark_with(&methods, &a, &b, &options) {
+            if product.skipped.is_some() {
+                continue;
+            }
+            let expected = if product.switched { switched } else { normal };
+            assert_eq!(product.result_len(), expected, "{}", product.name);
+        }
+    }
+}
+
+#[test]
+fn auto_picks_binary_search_at_its_thresholds() {
+    let auto = Auto::new(10, 2);
+    let name = |big: usize, small: usize| {
+        Intersect::<usize>::name_for(&auto, &vec![0; big], &vec![0; small])
+    };
+    assert_eq!(name(5, 2), "Auto(Binary)");
+    assert_eq!(name(5, 3), "Auto(Hash)");
+    assert_eq!(name(39, 4), "Auto(Hash)");
+    assert_eq!(name(40, 4), "Auto(Binary)");
+    assert_eq!(Intersect::<usize>::name(&auto), "Auto");
+
+    let a: Vec<usize> = (0..40).collect();
+    let products = run_benchmark(&[Box::new(auto) as Box<dyn Intersect>], &a, &a[..4]);
+    let names: Vec<&str> = products.iter().map(|p| p.name.as_str()).collect();
+    assert!(names.contains(&"Auto(Binary)"), "{:?}", names);
+    assert!(
+        names.iter().any(|name| name.starts_with("Auto(Hash)")),
+        "{:?}",
+        names
+    );
+}
+
+#[test]
+fn set_intersections_skip_duplicates_they_would_drop() {
+    let methods: Vec<Box<dyn Intersect>> = vec![
+        Box::new(StdIntersection),
+        Box::new(BTreeBoth),
+        Box::new(Retain),
+    ];
+    let big = [4, 1, 1, 3, 9];
+    let small = [3, 1, 7];
+    let products = run_benchmark_with(&methods, &big, &small, &BenchmarkOptions::default());
+    let skipped: Vec<&str> = products
+        .iter()
+        .filter(|p| p.skipped.is_some())
+        .map(|p| p.name.as_str())
+        .collect();
+    assert_eq!(
+        skipped,
+        [
+            "StdIntersection",
+            "StdIntersection seq",
+            "BTreeBoth",
+            "BTreeBoth seq"
+        ]
+    );
+
+    for (semantics, expected) in [
+        (Semantics::Set, vec![1, 3]),
+        (Semantics::Pairs, vec![1, 1, 3]),
+    ] {
+        let options = BenchmarkOptions {
+            semantics,
+            ..BenchmarkOptions::default()
+        };
+        let products = run_benchmark_with(&methods, &big, &small, &options);
+        for product in &products {
+            assert_eq!(product.skipped, None, "{}", product.name);
+            assert_eq!(
+                normalize(&product.result, true),
+                expected,
+                "{}",
+                product.name
+            );
+        }
+    }
+}
+
+#[test]
+fn direct_index_finds_zero_and_the_max_value_within_its_budget() {
+    let index = DirectIndex::new(1);
+    // the biggest value that still fits the budget of 1 MiB
+    let max = (1 << 20) - 1;
+    let small = [0, 7, max];
+    let big = [max, 3, 0, 7, max + 1, max - 1];
+    assert_eq!(index.skip_reason(&big, &small), None);
+    assert_eq!(index.intersect(&big, &small), [max, 0, 7]);
+    assert_eq!(index.intersect_seq(&big, &small), [max, 0, 7]);
+    assert_eq!(index.intersect_count(&big, &small), 3);
+    assert!(index.prepare(&small).unwrap().contains(&0));
+
+    let products = run_benchmark(
+        &[Box::new(index) as Box<dyn Intersect>],
+        &big,
+        &[0, usize::MAX],
+    );
+    let skipped: Vec<_> = products.iter().filter(|p| !p.switched).collect();
+    assert_eq!(skipped.len(), 2);
+    for product in skipped {
+        assert_eq!(
+            product.skipped.as_deref(),
+            Some("presence array needs 17592186044415 MiB, budget is 1 MiB")
+        );
+    }
+}
+
+#[test]
+fn partitioned_hash_keeps_the_duplicates_of_big_in_any_partitioning() {
+    let big: Vec<usize> = (0..1000).map(|x| x * 7 % 300).collect();
+    let small: Vec<usize> = (0..200).map(|x| x * 2 % 150).collect();
+    let expected = normalize(&Hash.intersect_seq(&big, &small), true);
+    for partitions in [None, Some(1), Some(2), Some(7), Some(2000)] {
+        let method = PartitionedHash::new(partitions);
+        for result in [
+            method.intersect(&big, &small),
+            method.intersect_seq(&big, &small),
+        ] {
+            assert_eq!(normalize(&result, true), expected, "{:?}", partitions);
+        }
+        assert_eq!(method.intersect_count(&big, &small), expected.len());
+        assert_eq!(method.intersect_count_seq(&big, &small), expected.len());
+    }
+    assert_eq!(
+        Intersect::<usize>::name(&PartitionedHash::new(Some(7))),
+        "PartitionedHash(7)"
+    );
+}
+
+struct Slow;
+
+impl Intersect for Slow {
+    fn intersect(&self, big: &[usize], small: &[usize]) -> Vec<usize> {
+        self.intersect_seq(big, small)
+    }
+
+    fn intersect_seq(&self, _big: &[usize], _small: &[usize]) -> Vec<usize> {
+        std::thread::sleep(Duration::from_millis(30));
+        Vec::new()
+    }
+}
+
+#[test]
+fn timing_until_stable_stops_at_the_budget_or_the_most_runs() {
+    let methods: Vec<Box<dyn Intersect>> = vec![Box::new(Slow), Box::new(Hash)];
+    let a: Vec<usize> = (0..1000).collect();
+    let until = |threshold, max_runs| BenchmarkOptions {
+        flavors: vec![Flavor::Sequential],
+        orders: Orders::Normal,
+        warmup: 0,
+        until_stable: Some(Stability {
+            threshold,
+            max_runs,
+            budget: Duration::from_millis(10),
+        }),
+        ..BenchmarkOptions::default()
+    };
+
+    // a stable run needs a second sample, but the slow method used up its budget
+    let products = run_benchmark_with(&methods, &a, &a, &until(1.0, 100));
+    let slow = products.iter().find(|p| p.name == "Slow seq").unwrap();
+    assert_eq!(slow.samples.len(), 1);
+    assert_eq!(slow.converged, Some(false));
+    let hash = products.iter().find(|p| p.name == "Hash seq").unwrap();
+    assert!(hash.samples.len() >= 2);
+
+    let products = run_benchmark_with(&methods[1..], &a, &a, &until(0.0, 5));
+    assert_eq!(products[0].samples.len(), 5);
+    assert_eq!(products[0].converged, Some(false));
+    assert_eq!(run_benchmark(&methods[1..], &a, &a)[0].converged, None);
+}
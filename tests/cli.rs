@@ -0,0 +1,864 @@
+use std::process::{Command, Stdio};
+
+fn run(args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_compare-speed"))
+        .args(args)
+        .env("COLUMNS", "60")
+        .stdin(Stdio::null())
+        .output()
+        .expect("failed to run the binary")
+}
+
+#[test]
+fn piped_output_prints_the_graph() {
+    let output = run(&["--size-a", "300", "--size-b", "200", "--seed", "1"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(stdout.contains("times as a log graph"));
+    assert!(stdout.contains("all values are equal"));
+}
+
+#[test]
+fn empty_array_is_handled_by_every_method() {
+    for (size_a, size_b) in [("0", "50"), ("50", "0"), ("0", "0")] {
+        let output = run(&["--size-a", size_a, "--size-b", size_b, "--seed", "1"]);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        assert!(stdout.contains("trivially empty"));
+        assert!(stdout.contains("all values are equal: true"));
+    }
+}
+
+fn graph_lines(stdout: &str) -> Vec<&str> {
+    stdout
+        .lines()
+        .skip_while(|line| !line.starts_with("times as a"))
+        .skip(1)
+        .take_while(|line| !line.is_empty())
+        .collect()
+}
+
+#[test]
+fn graph_fits_the_given_width() {
+    for ascii in [false, true] {
+        let mut args = vec![
+            "--size-a", "300", "--size-b", "200", "--seed", "1", "--width", "40",
+        ];
+        if ascii {
+            args.push("--ascii");
+        }
+        let output = run(&args);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let lines = graph_lines(&stdout);
+        assert!(!lines.is_empty());
+        for line in lines {
+            assert!(line.chars().count() <= 40, "{:?} is too wide", line);
+            let bar = line.split(": ").nth(1).unwrap_or_default();
+            if ascii {
+                assert!(bar.chars().all(|c| c == '*'), "{:?}", line);
+            } else {
+                assert!(!bar.contains('*'), "{:?}", line);
+            }
+        }
+    }
+}
+
+fn shared_line(args: &[&str]) -> String {
+    let output = run(args);
+    assert!(output.status.success());
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find(|line| line.starts_with("the arrays share"))
+        .unwrap()
+        .to_string()
+}
+
+#[test]
+fn overlap_is_reported() {
+    let args = [
+        "--size-a",
+        "2000",
+        "--size-b",
+        "1000",
+        "--seed",
+        "3",
+        "--overlap",
+        "40",
+        "--methods",
+        "hash",
+    ];
+    let line = shared_line(&args);
+    let (_, second) = line.split_once(" and ").unwrap();
+    let shared: usize = second.split(' ').next().unwrap().parse().unwrap();
+    assert!(
+        second.contains("of the 1000 elements of the second"),
+        "{}",
+        line
+    );
+    assert!((300..500).contains(&shared), "{}", line);
+    assert_eq!(shared_line(&args), line);
+}
+
+#[test]
+fn input_files_replace_the_generated_data() {
+    let dir = std::env::temp_dir().join(format!("compare-speed-input-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let a = dir.join("a.txt");
+    let b = dir.join("b.txt");
+    std::fs::write(&a, "1 2 3\n4 5\n").unwrap();
+    std::fs::write(&b, "4\n5\n6\n").unwrap();
+
+    let output = run(&[
+        "--input-a",
+        a.to_str().unwrap(),
+        "--input-b",
+        b.to_str().unwrap(),
+    ]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(stdout.contains("loading test data took"));
+    assert!(stdout.contains("the arrays have the sizes 5 and 3"));
+
+    std::fs::write(&b, "4\nfive\n").unwrap();
+    let output = run(&[
+        "--input-a",
+        a.to_str().unwrap(),
+        "--input-b",
+        b.to_str().unwrap(),
+    ]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("line 2: invalid value 'five'"));
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn inputs_with_duplicates_agree_on_every_semantics() {
+    let dir = std::env::temp_dir().join(format!("compare-speed-dups-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let a = dir.join("a.txt");
+    let b = dir.join("b.txt");
+    std::fs::write(&a, "3 1 3 2 3 7 1 9\n").unwrap();
+    std::fs::write(&b, "3 3 1 8 1\n").unwrap();
+
+    for semantics in ["set", "multiset-big", "pairs"] {
+        let output = run(&[
+            "--input-a",
+            a.to_str().unwrap(),
+            "--input-b",
+            b.to_str().unwrap(),
+            "--semantics",
+            semantics,
+            "--check",
+        ]);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(output.status.success(), "{}: {}", semantics, stdout);
+        assert!(
+            stdout.contains("all values are equal: true"),
+            "{}: {}",
+            semantics,
+            stdout
+        );
+    }
+    std::fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn saved_data_is_benchmarked_again() {
+    let path = std::env::temp_dir().join(format!("compare-speed-{}.data", std::process::id()));
+    let path = path.to_str().unwrap();
+    let saved = run(&[
+        "--size-a",
+        "300",
+        "--size-b",
+        "200",
+        "--seed",
+        "4",
+        "--save-data",
+        path,
+    ]);
+    let loaded = run(&["--load-data", path]);
+    std::fs::remove_file(path).unwrap();
+
+    assert!(
+        loaded.status.success(),
+        "{}",
+        String::from_utf8_lossy(&loaded.stderr)
+    );
+    let header = |output: &std::process::Output| {
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let line = stdout.lines().next().unwrap().to_string();
+        line[line.find("(seed").unwrap()..].to_string()
+    };
+    assert_eq!(header(&saved), "(seed 4)");
+    assert_eq!(header(&loaded), "(seed 4)");
+    assert!(
+        String::from_utf8_lossy(&loaded.stdout).contains("the arrays have the sizes 300 and 200")
+    );
+}
+
+#[test]
+fn threads_are_reported_and_validated() {
+    let output = run(&["--size-a", "300", "--size-b", "200", "--threads", "1"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(stdout.contains("running on 1 thread\n"));
+    assert!(stdout.contains("the methods run one at a time, each with all of its runs"));
+
+    let output = run(&["--threads", "0"]);
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("0 is not in 1..=4096"));
+}
+
+#[test]
+fn scheduling_is_reported_in_the_header() {
+    let output = run(&[
+        "--size-a",
+        "300",
+        "--size-b",
+        "200",
+        "--repeats",
+        "2",
+        "--interleave",
+    ]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(stdout.contains("interleaving their timed runs in shuffled rounds"));
+
+    let output = run(&["--interleave", "--timeout", "1"]);
+    assert!(!output.status.success());
+
+    let output = run(&["--size-a", "300", "--size-b", "200", "--concurrent-methods"]);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("the methods run concurrently"));
+    let output = run(&["--concurrent-methods", "--measure-memory"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn many_arrays_are_described_in_the_header() {
+    let output = run(&[
+        "--arrays",
+        "3",
+        "--size-a",
+        "300",
+        "--size-b",
+        "200",
+        "--max-value",
+        "100",
+    ]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(
+        stdout.contains("the 3 arrays have the sizes 300, 200, "),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("computing the intersection of all 3 arrays"));
+    assert!(stdout.contains("skipped: quadratic methods only intersect two arrays"));
+
+    let output = run(&["--arrays", "1"]);
+    assert!(!output.status.success());
+}
+
+#[test]
+fn probe_mode_reports_queries_per_second() {
+    let output = run(&[
+        "--mode",
+        "probe",
+        "--queries",
+        "1000",
+        "--size-a",
+        "300",
+        "--size-b",
+        "200",
+        "--columns",
+        "name,time,matches,throughput",
+    ]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(
+        stdout.contains(
+            "answering 1000 membership queries with a structure built from the smaller array"
+        ),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("queries/s"), "{}", stdout);
+    assert!(stdout.contains("skipped: builds no structure to answer queries with"));
+    assert!(!stdout.contains("switched order"), "{}", stdout);
+
+    let output = run(&["--mode", "probe", "--count-only"]);
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("--mode probe can't be used with --count-only"));
+}
+
+#[test]
+fn methods_are_listed_with_their_complexity() {
+    let output = run(&["--list-methods"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(
+        stdout.lines().any(|line| line.starts_with("Binary ")
+            && line.ends_with(
+                " — sorts small, binary searches it for every element of big — O((n + m) log m)"
+            )),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("BloomHash(0.01) "), "{}", stdout);
+    assert!(!stdout.contains("no description"), "{}", stdout);
+    assert!(!stdout.contains("took"), "{}", stdout);
+
+    let output = run(&["--list-methods", "--element-type", "u32"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!stdout.contains("Bitset "), "{}", stdout);
+    assert!(
+        stdout.contains("only intersect --element-type usize"),
+        "{}",
+        stdout
+    );
+
+    let output = run(&["--list-methods", "--element-type", "u32", "--quiet"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Binary "), "{}", stdout);
+    assert!(!stdout.contains("\n\n"), "{}", stdout);
+}
+
+#[test]
+fn dry_runs_print_the_plan_without_running() {
+    let path =
+        std::env::temp_dir().join(format!("compare-speed-dry-run-{}.txt", std::process::id()));
+    let output = run(&[
+        "--dry-run",
+        "--seed",
+        "7",
+        "--size-a",
+        "1000",
+        "--methods",
+        "hash,binary",
+        "--flavors",
+        "sequential",
+        "--repeats",
+        "3",
+        "--output",
+        path.to_str().unwrap(),
+    ]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.contains("seed 7\n"), "{}", stdout);
+    assert!(
+        stdout.contains("the arrays have the sizes 1000 and a random size below 65536"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("4 products:\n  Binary seq\n"), "{}", stdout);
+    assert!(
+        stdout.contains("every product has 1 warmup and 3 timed runs, for 16 runs in total\n"),
+        "{}",
+        stdout
+    );
+    assert!(!stdout.contains("took"), "{}", stdout);
+    assert!(!path.exists());
+}
+
+#[test]
+fn regressions_against_the_baseline_fail_the_run() {
+    let path = std::env::temp_dir().join(format!("compare-speed-{}.json", std::process::id()));
+    std::fs::write(
+        &path,
+        r#"{"seed": 5, "size_a": 300, "size_b": 200, "times": [
+            {"name": "Hash", "time_ns": 1},
+            {"name": "Removed", "time_ns": 1000}
+        ]}"#,
+    )
+    .unwrap();
+    let path = path.to_str().unwrap();
+    let args = [
+        "--size-a",
+        "300",
+        "--size-b",
+        "200",
+        "--seed",
+        "5",
+        "--methods",
+        "Hash,Binary",
+        "--compare-baseline",
+        path,
+        "--wide",
+    ];
+    let output = run(&args);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.contains("Delta vs baseline"));
+    assert!(stdout.contains("only in the baseline: Removed\n"));
+    assert!(stdout.contains("not in the baseline: "));
+    assert!(stdout.contains("regressed: Hash\n"));
+    assert!(!stdout.contains("note: the baseline ran on"));
+
+    let output = run(&[&args[..], &["--fail-on-regression"]].concat());
+    std::fs::remove_file(path).unwrap();
+    assert!(!output.status.success());
+}
+
+#[test]
+fn piped_output_has_no_progress_line() {
+    let output = run(&["--size-a", "300", "--size-b", "200", "--repeats", "3"]);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(!stdout.contains("done, running"));
+    assert!(!stdout.contains('\x1b') && !stdout.contains('\r'));
+}
+
+#[test]
+fn tui_is_ignored_without_a_terminal() {
+    let output = run(&["--size-a", "300", "--size-b", "200", "--seed", "1", "--tui"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("--tui needs a terminal"));
+    assert!(String::from_utf8_lossy(&output.stdout).contains("all values are equal: true"));
+}
+
+#[test]
+fn quiet_prints_one_line_per_method() {
+    let args = ["--size-a", "300", "--size-b", "200", "--seed", "1"];
+    let output = run(&[
+        &args[..],
+        &[
+            "--quiet",
+            "--methods",
+            "Hash,Merge",
+            "--flavors",
+            "sequential",
+        ],
+    ]
+    .concat());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 4, "{}", stdout);
+    for line in lines {
+        let (_, nanos) = line.split_once('\t').unwrap();
+        nanos.parse::<u128>().unwrap();
+    }
+
+    let output = run(&[&args[..], &["-v", "--repeats", "3"]].concat());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.contains("samples of every method:"));
+    assert!(stdout.contains("all values are equal: true"));
+}
+
+#[test]
+fn runs_are_appended_to_the_history() {
+    let path = std::env::temp_dir().join(format!("history-{}.jsonl", std::process::id()));
+    let path = path.to_str().unwrap();
+    let args = ["--size-a", "300", "--size-b", "200", "--methods", "Hash"];
+    for _ in 0..2 {
+        let output = run(&[&args[..], &["--flavors", "sequential", "--history", path]].concat());
+        assert!(output.status.success());
+    }
+    std::fs::OpenOptions::new()
+        .append(true)
+        .open(path)
+        .and_then(|mut file| std::io::Write::write_all(&mut file, b"{\"cut off"))
+        .unwrap();
+
+    let output = run(&["--history", path, "--show-history"]);
+    let quiet = run(&["--history", path, "--show-history", "--quiet"]);
+    std::fs::remove_file(path).unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(stdout.contains("2 runs in"));
+    assert!(stdout.contains("Hash seq switched order"));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("skipped line 3"));
+
+    let stdout = String::from_utf8_lossy(&quiet.stdout);
+    assert!(!stdout.contains("runs in"), "{}", stdout);
+    assert!(stdout.contains("Hash seq switched order"), "{}", stdout);
+}
+
+#[test]
+fn every_element_type_runs_the_generic_methods() {
+    for (element_type, line) in [
+        ("u32", "the elements are u32 values of 4 bytes"),
+        ("u64", "the elements are u64 values of 8 bytes"),
+        ("i64", "the elements are i64 values of 8 bytes"),
+        ("string", "the elements are strings of 16 characters"),
+    ] {
+        let output = run(&[
+            "--size-a",
+            "300",
+            "--size-b",
+            "200",
+            "--seed",
+            "1",
+            "--element-type",
+            element_type,
+        ]);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        assert!(stdout.contains(line), "{}", stdout);
+        assert!(stdout.contains("Galloping"));
+        assert!(!stdout.contains("Simd"));
+        assert!(stdout.contains("all values are equal: true"));
+    }
+}
+
+#[test]
+fn input_values_must_fit_the_element_type() {
+    let dir = std::env::temp_dir().join(format!("compare-speed-fit-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let a = dir.join("a.txt");
+    let b = dir.join("b.txt");
+    std::fs::write(&a, "1 4294967296\n").unwrap();
+    std::fs::write(&b, "1\n").unwrap();
+
+    let output = run(&[
+        "--input-a",
+        a.to_str().unwrap(),
+        "--input-b",
+        b.to_str().unwrap(),
+        "--element-type",
+        "u32",
+    ]);
+    std::fs::remove_dir_all(dir).unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr).contains("4294967296 doesn't fit in a u32"));
+}
+
+#[test]
+fn check_fails_when_a_method_disagrees() {
+    let args = [
+        "--size-a",
+        "300",
+        "--size-b",
+        "200",
+        "--overlap",
+        "50",
+        "--seed",
+        "3",
+        "--check",
+        "--quiet",
+    ];
+    let output = run(&[&args[..], &["--methods", "Hash,Binary"]].concat());
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+
+    let output = run(&[&args[..], &["--methods", "Hash,Broken", "--broken-method"]].concat());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(1));
+    assert!(stderr.contains("Broken disagrees with Hash"), "{}", stderr);
+    assert!(stderr.ends_with("error: the check failed\n"), "{}", stderr);
+}
+
+#[test]
+fn disagreeing_methods_only_fail_with_check() {
+    let args = [
+        "--seed",
+        "3",
+        "--overlap",
+        "50",
+        "--methods",
+        "Hash,Broken",
+        "--broken-method",
+    ];
+    let sizes = ["--size-a", "300", "--size-b", "200", "--quiet"];
+    for sizes in [&sizes[..], &["--sweep", "200,300"]] {
+        let output = run(&[&args[..], sizes].concat());
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+
+        let output = run(&[&args[..], sizes, &["--check"]].concat());
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert_eq!(output.status.code(), Some(1), "{}", stderr);
+        assert!(stderr.ends_with("error: the check failed\n"), "{}", stderr);
+    }
+}
+
+#[test]
+fn scenarios_set_flags_the_command_line_overrides() {
+    let dir = std::env::temp_dir().join(format!("compare-speed-scenario-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let config = dir.join("compare_times.toml");
+    std::fs::write(
+        &config,
+        "[scenarios.tiny]\nsize_a = 400\nsize_b = 100\nseed = 7\nmethods = [\"Hash\", \"Binary\"]\n",
+    )
+    .unwrap();
+
+    let args = ["--config", config.to_str().unwrap(), "--scenario", "tiny"];
+    let output = run(&[&args[..], &["--size-b", "300"]].concat());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(stdout.contains("the arrays have the sizes 400 and 300"));
+    assert!(stdout.contains(
+        ": methods = Hash,Binary, seed = 7, size_a = 400 (size_b set on the command line)"
+    ));
+
+    let output = run(&[&args[..], &["--json", "-"]].concat());
+    let report: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(report["scenario"]["name"], "tiny");
+    assert_eq!(report["scenario"]["settings"]["size_b"][0], "100");
+
+    std::fs::write(&config, "[scenarios.tiny]\nsize_c = 400\n").unwrap();
+    let output = run(&args);
+    std::fs::remove_dir_all(dir).unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("unknown field `size_c` in scenario `tiny`"));
+}
+
+#[test]
+fn presets_are_named_in_the_header_and_the_baseline() {
+    let dir = std::env::temp_dir().join(format!("compare-speed-preset-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let baseline = dir.join("baseline.json");
+    let args = [
+        "--size-a",
+        "500",
+        "--size-b",
+        "200",
+        "--seed",
+        "4",
+        "--methods",
+        "Hash",
+    ];
+    let output = run(&[
+        &args[..],
+        &[
+            "--preset",
+            "disjoint",
+            "--save-baseline",
+            baseline.to_str().unwrap(),
+        ],
+    ]
+    .concat());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(stdout.contains("the arrays follow the preset disjoint: even values"));
+    assert!(stdout.contains("the arrays share 0 distinct values"));
+    let saved: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(&baseline).unwrap()).unwrap();
+    assert_eq!(saved["preset"], "disjoint");
+
+    let output = run(&[
+        &args[..],
+        &["--compare-baseline", baseline.to_str().unwrap()],
+    ]
+    .concat());
+    std::fs::remove_dir_all(dir).unwrap();
+    assert!(String::from_utf8_lossy(&output.stdout)
+        .contains("note: the baseline ran on arrays of the disjoint preset"));
+}
+
+#[test]
+fn reports_and_baselines_are_diffed_without_benchmarking() {
+    let dir = std::env::temp_dir().join(format!("compare-speed-diff-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let (old, new) = (dir.join("old.json"), dir.join("new.json"));
+    let args = [
+        "--size-a",
+        "300",
+        "--size-b",
+        "200",
+        "--flavors",
+        "sequential",
+    ];
+    let output = run(&[
+        &args[..],
+        &[
+            "--seed",
+            "1",
+            "--methods",
+            "Hash,Binary",
+            "--json",
+            old.to_str().unwrap(),
+        ],
+    ]
+    .concat());
+    assert!(output.status.success());
+    let output = run(&[
+        &args[..],
+        &[
+            "--seed",
+            "2",
+            "--methods",
+            "Hash",
+            "--save-baseline",
+            new.to_str().unwrap(),
+        ],
+    ]
+    .concat());
+    assert!(output.status.success());
+
+    let output = run(&["--diff", old.to_str().unwrap(), new.to_str().unwrap()]);
+    std::fs::remove_dir_all(dir).unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success());
+    assert!(
+        stdout.starts_with("note: the runs were measured differently"),
+        "{}",
+        stdout
+    );
+    assert!(stdout.contains("seed: 1 in the old run, 2 in the new one"));
+    assert!(stdout.contains("| Hash seq "));
+    assert!(stdout.contains("| Binary seq ") && stdout.contains("only in the old run"));
+    assert!(!String::from_utf8_lossy(&output.stderr).contains("debug build"));
+}
+
+/// The cells of the table rows of `stdout`, without the header.
+fn table_rows(stdout: &str) -> Vec<Vec<String>> {
+    stdout
+        .lines()
+        .filter(|line| line.starts_with("| "))
+        .skip(1)
+        .map(|line| {
+            line.trim_matches('|')
+                .split('|')
+                .map(|cell| cell.trim().to_string())
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn the_table_has_a_row_per_method_with_the_same_matches() {
+    let args = [
+        "--size-a",
+        "300",
+        "--size-b",
+        "200",
+        "--overlap",
+        "30",
+        "--seed",
+        "5",
+        "--methods",
+        "Hash,Binary,Merge",
+        "--flavors",
+        "sequential",
+        "--orders",
+        "normal",
+        "--width",
+        "100",
+    ];
+    let output = run(&args);
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(
+        output.status.code(),
+        Some(0),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(stdout.contains("\nall values are equal: true\n"));
+
+    let rows = table_rows(&stdout);
+    let mut names: Vec<&str> = rows.iter().map(|row| row[0].as_str()).collect();
+    names.sort_unstable();
+    assert_eq!(names, ["Binary seq", "Hash seq", "Merge seq"]);
+    let matches: Vec<&String> = rows.iter().map(|row| row.last().unwrap()).collect();
+    assert!(
+        matches.iter().all(|m| *m == matches[0] && *m != "0"),
+        "{:?}",
+        matches
+    );
+
+    // the same seed finds the same matches again
+    let again = run(&args);
+    let again = table_rows(&String::from_utf8_lossy(&again.stdout));
+    assert_eq!(again[0].last(), Some(matches[0]));
+}
+
+#[test]
+fn bloom_false_positive_rates_must_be_between_0_and_1() {
+    for rate in ["0", "1", "1.5", "-0.1", "NaN"] {
+        let output = run(&[
+            "--size-a",
+            "1000",
+            "--size-b",
+            "100",
+            "--seed",
+            "1",
+            "--methods",
+            "BloomHash",
+            &format!("--bloom-fp-rates={}", rate),
+        ]);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert_eq!(output.status.code(), Some(2), "{}: {}", rate, stderr);
+        assert!(
+            stderr.contains("must be between 0 and 1, exclusive"),
+            "{}",
+            stderr
+        );
+    }
+}
+
+#[test]
+fn every_method_agrees_on_every_semantics() {
+    for semantics in ["set", "multiset-big", "pairs"] {
+        let output = run(&[
+            "--size-a",
+            "2000",
+            "--size-b",
+            "300",
+            "--max-value",
+            "500",
+            "--seed",
+            "1",
+            "--semantics",
+            semantics,
+            "--check",
+        ]);
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(output.status.success(), "{}: {}", semantics, stdout);
+        assert!(!stdout.contains("compute the"), "{}: {}", semantics, stdout);
+    }
+
+    let output = run(&["--api", "iter", "--semantics", "set"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(String::from_utf8_lossy(&output.stderr)
+        .contains("--api iter can't be used with --semantics other than multiset-big"));
+}
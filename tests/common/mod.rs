@@ -0,0 +1,27 @@
+//! Fixtures shared by the integration tests.
+
+#![allow(dead_code)]
+
+use std::time::{Duration, SystemTime};
+
+use compare_speed::{HistoryRecord, Product};
+
+/// A product with a single sample of `nanos` and an empty result.
+pub fn product(name: &str, nanos: u64) -> Product {
+    Product::new(
+        name.to_string(),
+        vec![Duration::from_nanos(nanos)],
+        Duration::ZERO,
+        vec![],
+    )
+}
+
+/// A history record of a run on arrays of `sizes` with seed 1, in which
+/// every `(name, nanos)` product took that long.
+pub fn record(sizes: (usize, usize), times: &[(&str, u64)]) -> HistoryRecord {
+    let products: Vec<Product> = times
+        .iter()
+        .map(|&(name, nanos)| product(name, nanos))
+        .collect();
+    HistoryRecord::new(SystemTime::now(), "box".to_string(), 1, sizes, &products)
+}
@@ -0,0 +1,236 @@
+use compare_speed::{
+    disjoint, distinct, distinct_shared, duplicates, generate, identical, overlap, queries,
+    read_binary, read_dataset, read_text, shared, skewed, subset, write_dataset, Distribution,
+    FromValue, Preset, Similarity,
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+fn arrays(seed: u64, percent: f64) -> (Vec<usize>, Vec<usize>) {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let big = generate(&mut rng, Some(20_000), Distribution::Uniform, None);
+    let mut small = generate(&mut rng, Some(10_000), Distribution::Uniform, None);
+    overlap(&mut rng, &big, &mut small, percent / 100.0);
+    (big, small)
+}
+
+#[test]
+fn the_same_seed_generates_the_same_data() {
+    assert_eq!(arrays(7, 30.0), arrays(7, 30.0));
+    assert_ne!(arrays(7, 30.0), arrays(8, 30.0));
+}
+
+#[test]
+fn overlap_draws_about_the_given_fraction_from_the_bigger_array() {
+    for percent in [0.0, 25.0, 100.0] {
+        let (big, small) = arrays(1, percent);
+        let expected = small.len() as f64 * percent / 100.0;
+        let actual = shared(&big, &small) as f64;
+        assert!((actual - expected).abs() < 300.0, "{} {}", percent, actual);
+    }
+}
+
+#[test]
+fn zipf_values_are_skewed() {
+    let mut rng = StdRng::seed_from_u64(1);
+    let values = generate(&mut rng, Some(10_000), Distribution::Zipf(1.2), None);
+    let ones = values.iter().filter(|x| **x == 1).count();
+    assert!(ones > 1_000, "{}", ones);
+    assert!(values.iter().all(|x| (1..=1 << 20).contains(x)));
+}
+
+#[test]
+fn half_of_the_queries_are_drawn_from_the_values() {
+    let values: Vec<usize> = (0..100).map(|x| (x + 1) << 40).collect();
+    let mut rng = StdRng::seed_from_u64(4);
+    let drawn = queries(
+        &mut rng,
+        &values,
+        1001,
+        Distribution::Uniform,
+        Some(1 << 40),
+    );
+    assert_eq!(drawn.len(), 1001);
+    assert_eq!(drawn.iter().filter(|x| values.contains(x)).count(), 500);
+    let mut rng = StdRng::seed_from_u64(4);
+    assert_eq!(
+        queries(
+            &mut rng,
+            &values,
+            1001,
+            Distribution::Uniform,
+            Some(1 << 40)
+        ),
+        drawn
+    );
+    let mut rng = StdRng::seed_from_u64(4);
+    assert_eq!(
+        queries(&mut rng, &[], 10, Distribution::Uniform, None).len(),
+        10
+    );
+}
+
+#[test]
+fn clustered_arrays_share_their_ranges() {
+    let mut rng = StdRng::seed_from_u64(1);
+    let a = generate(&mut rng, Some(10_000), Distribution::Clustered(5), None);
+    let b = generate(&mut rng, Some(10_000), Distribution::Clustered(5), None);
+    let mut starts: Vec<usize> = a.iter().map(|x| x >> 16).collect();
+    starts.sort_unstable();
+    starts.dedup();
+    assert!(starts.len() <= 16, "{}", starts.len());
+    assert!(shared(&a, &b) > 0);
+}
+
+#[test]
+fn text_input_reports_the_line_of_invalid_values() {
+    let values = read_text("1 2\n\n3\t4\n".as_bytes()).unwrap();
+    assert_eq!(values, [1, 2, 3, 4]);
+
+    let error = read_text("1\n2 x3\n".as_bytes()).unwrap_err();
+    assert!(error.starts_with("line 2: invalid value 'x3'"), "{}", error);
+}
+
+#[test]
+fn binary_input_reads_little_endian_values() {
+    let bytes: Vec<u8> = [7u64, u64::MAX]
+        .iter()
+        .flat_map(|x| x.to_le_bytes())
+        .collect();
+    assert_eq!(read_binary(&bytes[..]).unwrap(), [7, usize::MAX]);
+    assert!(read_binary(&bytes[..5]).is_err());
+}
+
+#[test]
+fn datasets_round_trip_with_their_seed() {
+    let a = vec![0, 1, usize::MAX, 42];
+    let b = vec![usize::MAX - 1];
+    let mut file = Vec::new();
+    write_dataset(&mut file, 9, &a, &b).unwrap();
+    assert_eq!(read_dataset(&file[..]).unwrap(), (9, a, b));
+
+    assert!(read_dataset(&file[..file.len() - 8]).is_err());
+    assert!(read_dataset(&b"not a dataset"[..]).is_err());
+}
+
+#[test]
+fn max_value_caps_every_distribution() {
+    let mut rng = StdRng::seed_from_u64(3);
+    for distribution in [
+        Distribution::Uniform,
+        Distribution::Zipf(1.0),
+        Distribution::Clustered(3),
+    ] {
+        for max in [1, 1000] {
+            let values = generate(&mut rng, Some(5_000), distribution, Some(max));
+            assert!(
+                values.iter().all(|x| *x < max),
+                "{:?} {}",
+                distribution,
+                max
+            );
+        }
+    }
+    let values = generate(&mut rng, Some(5_000), Distribution::Uniform, Some(1000));
+    let mut distinct = values.clone();
+    distinct.sort_unstable();
+    distinct.dedup();
+    assert!(distinct.len() < values.len());
+}
+
+#[test]
+fn equal_values_generate_equal_strings() {
+    let first = String::from_generated(42, 12);
+    assert_eq!(first.len(), 12);
+    assert!(first.chars().all(|c| c.is_ascii_alphanumeric()));
+    assert_eq!(String::from_generated(42, 12), first);
+    assert_ne!(String::from_generated(43, 12), first);
+    assert_eq!(String::from_input(42).as_deref(), Some("42"));
+    assert_eq!(String::describe(12), "strings of 12 characters");
+}
+
+#[test]
+fn values_that_dont_fit_are_rejected_or_truncated() {
+    assert_eq!(u32::from_input(1 << 32), None);
+    assert_eq!(u32::from_generated((1 << 32) + 5, ()), 5);
+    assert_eq!(i64::from_input(7), Some(7));
+    assert_eq!(u32::describe(()), "u32 values of 4 bytes");
+}
+
+#[test]
+fn similarity_counts_duplicates_once() {
+    let a = [1, 1, 2, 3, 3, 3, 4];
+    let b = [3, 3, 4, 4, 5, 6];
+    // a multiset intersection of the arrays, as the methods return it
+    let intersection = [3, 3, 4];
+    let similarity = Similarity::new((distinct(&a), distinct(&b)), &intersection);
+    assert_eq!(similarity.shared, 2);
+    assert_eq!(similarity.union, 6);
+    assert_eq!(similarity.jaccard, 2.0 / 6.0);
+    assert_eq!(similarity.containment, 2.0 / 4.0);
+    let empty = Similarity::new::<usize>((0, 0), &[]);
+    assert_eq!(
+        (empty.union, empty.jaccard, empty.containment),
+        (0, 0.0, 0.0)
+    );
+}
+
+#[test]
+fn disjoint_arrays_share_nothing() {
+    let (a, b) = disjoint(&mut StdRng::seed_from_u64(1), (Some(5_000), Some(3_000)));
+    assert_eq!((a.len(), b.len()), (5_000, 3_000));
+    assert_eq!(distinct_shared(&a, &b), 0);
+}
+
+#[test]
+fn identical_arrays_hold_the_same_values() {
+    let (a, mut b) = identical(&mut StdRng::seed_from_u64(1), Some(5_000));
+    assert_ne!(a, b);
+    b.sort_unstable();
+    let mut a = a;
+    a.sort_unstable();
+    assert_eq!(a, b);
+}
+
+#[test]
+fn subsets_are_drawn_from_the_first_array() {
+    let (a, b) = subset(&mut StdRng::seed_from_u64(1), (Some(5_000), Some(8_000)));
+    assert_eq!(b.len(), 8_000);
+    assert_eq!(shared(&a, &b), b.len());
+
+    let (a, b) = subset(&mut StdRng::seed_from_u64(1), (Some(0), Some(8_000)));
+    assert!(a.is_empty() && b.is_empty());
+}
+
+#[test]
+fn skewed_arrays_are_ten_million_and_ten_elements_by_default() {
+    let (a, b) = skewed(&mut StdRng::seed_from_u64(1), (None, None));
+    assert_eq!((a.len(), b.len()), (10_000_000, 10));
+
+    let (a, b) = skewed(&mut StdRng::seed_from_u64(1), (Some(10_000), None));
+    assert_eq!(b.len(), 10);
+    assert!(shared(&a, &b) >= 5);
+}
+
+#[test]
+fn presets_only_depend_on_the_seed() {
+    for preset in [
+        Preset::Random,
+        Preset::Disjoint,
+        Preset::Identical,
+        Preset::Subset,
+        Preset::Skewed,
+        Preset::Duplicates,
+    ] {
+        let arrays = |seed| preset.generate(&mut StdRng::seed_from_u64(seed), (Some(1_000), None));
+        assert_eq!(arrays(7), arrays(7));
+        assert_ne!(arrays(7), arrays(8));
+    }
+}
+
+#[test]
+fn duplicate_arrays_repeat_their_values() {
+    let (a, b) = duplicates(&mut StdRng::seed_from_u64(1), (Some(5_000), Some(3_000)));
+    assert_eq!((a.len(), b.len()), (5_000, 3_000));
+    assert!(distinct(&a) <= 1_000 && distinct(&b) <= 1_000);
+    assert!(distinct_shared(&a, &b) > 900);
+}
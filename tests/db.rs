@@ -0,0 +1,82 @@
+#![cfg(feature = "sqlite")]
+
+mod common;
+
+use std::time::Duration;
+
+use common::record;
+use compare_speed::{Database, DB_SCHEMA_VERSION};
+use rusqlite::Connection;
+
+#[test]
+fn runs_are_summarized_by_size() {
+    let dir = std::env::temp_dir().join(format!("compare-speed-db-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("runs.db");
+
+    let mut database = Database::open(&path).unwrap();
+    let mut first = record((10, 20), &[("Hash", 100), ("Merge", 300)]);
+    first.run.seed = u64::MAX;
+    database.insert(&first).unwrap();
+    database
+        .insert(&record((1000, 20), &[("Merge", 200), ("Hash", 500)]))
+        .unwrap();
+    drop(database);
+
+    // reopening keeps the runs
+    let mut database = Database::open(&path).unwrap();
+    database.insert(&record((30, 5), &[("Hash", 50)])).unwrap();
+    assert_eq!(database.version().unwrap(), DB_SCHEMA_VERSION);
+    assert_eq!(database.runs(None, None).unwrap(), 3);
+
+    let stats = database.summary(None, None).unwrap();
+    assert_eq!(stats.len(), 2);
+    assert_eq!((stats[0].name.as_str(), stats[0].runs), ("Hash", 3));
+    assert_eq!(stats[0].min, Duration::from_nanos(50));
+    assert_eq!(stats[0].median, Duration::from_nanos(100));
+    assert_eq!(stats[0].max, Duration::from_nanos(500));
+
+    assert_eq!(database.runs(None, Some(100)).unwrap(), 2);
+    let small = database.summary(None, Some(100)).unwrap();
+    assert_eq!(small[0].max, Duration::from_nanos(100));
+    assert_eq!(small[1].runs, 1);
+    let big = database.summary(Some(100), None).unwrap();
+    assert_eq!(big[0].name, "Merge");
+    drop(database);
+
+    let connection = Connection::open(&path).unwrap();
+    let seed: i64 = connection
+        .query_row("SELECT seed FROM runs LIMIT 1", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(seed as u64, u64::MAX);
+    connection
+        .execute("UPDATE schema_version SET version = 99", [])
+        .unwrap();
+    drop(connection);
+    let error = Database::open(&path).err().unwrap();
+    std::fs::remove_dir_all(dir).unwrap();
+    assert_eq!(
+        error,
+        format!(
+            "schema version 99 is newer than the supported version {}",
+            DB_SCHEMA_VERSION
+        )
+    );
+}
+
+#[test]
+fn databases_without_a_version_are_migrated() {
+    let dir = std::env::temp_dir().join(format!("compare-speed-db-old-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("runs.db");
+    Connection::open(&path)
+        .unwrap()
+        .execute_batch("CREATE TABLE notes (text TEXT)")
+        .unwrap();
+
+    let mut database = Database::open(&path).unwrap();
+    assert_eq!(database.version().unwrap(), DB_SCHEMA_VERSION);
+    database.insert(&record((1, 2), &[("Hash", 1)])).unwrap();
+    assert_eq!(database.runs(None, None).unwrap(), 1);
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -0,0 +1,96 @@
+use std::time::Duration;
+
+use compare_speed::{print_diff, DiffRow, Preset, ProductRecord, RunDiff, SavedRun, TimeUnit};
+
+fn record(name: &str, time_ns: u64) -> ProductRecord {
+    ProductRecord {
+        name: name.to_string(),
+        time_ns,
+        ..ProductRecord::default()
+    }
+}
+
+fn run(seed: u64, products: Vec<ProductRecord>) -> SavedRun {
+    SavedRun {
+        schema_version: 1,
+        seed,
+        size_a: 10,
+        size_b: 20,
+        products,
+        environment: None,
+        preset: Preset::Random,
+    }
+}
+
+#[test]
+fn runs_are_paired_by_name() {
+    let old = run(
+        3,
+        vec![
+            record("Hash", 100),
+            record("Merge", 200),
+            record("Old", 1),
+            ProductRecord {
+                skipped: Some("no AVX2".to_string()),
+                ..record("Simd", 0)
+            },
+        ],
+    );
+    let new = run(
+        4,
+        vec![
+            record("Merge", 150),
+            record("Hash", 125),
+            ProductRecord {
+                timed_out_ns: Some(5),
+                ..record("New", 5)
+            },
+        ],
+    );
+    let diff = RunDiff::new(&old, &new);
+    assert_eq!(
+        diff.rows,
+        [
+            DiffRow {
+                name: "Merge".to_string(),
+                old: Some(Duration::from_nanos(200)),
+                new: Some(Duration::from_nanos(150)),
+            },
+            DiffRow {
+                name: "Hash".to_string(),
+                old: Some(Duration::from_nanos(100)),
+                new: Some(Duration::from_nanos(125)),
+            },
+        ]
+    );
+    assert_eq!(diff.rows[0].percent(), Some(-25.0));
+    assert_eq!(
+        diff.only_old,
+        [("Old".to_string(), Some(Duration::from_nanos(1)))]
+    );
+    assert_eq!(diff.only_new, [("New".to_string(), None)]);
+    assert_eq!(
+        diff.differences,
+        ["seed: 3 in the old run, 4 in the new one"]
+    );
+
+    let mut out = Vec::new();
+    print_diff(&mut out, &diff, TimeUnit::Ns, false).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(
+        out.contains("| Merge | 200ns    | 150ns     | -50ns      | -25.00% "),
+        "{}",
+        out
+    );
+    assert!(
+        out.contains("| Hash  | 100ns    | 125ns     | +25ns      | +25.00% "),
+        "{}",
+        out
+    );
+    assert!(out.contains("| Old   | 1ns      | -         | n/a        | only in the old run |"));
+    assert!(out.contains("| New   | -        | timed out | n/a        | only in the new run |"));
+    assert!(SavedRun::read(
+        &br#"{"schema_version": 9, "seed": 1, "size_a": 1, "size_b": 1, "times": []}"#[..]
+    )
+    .is_err());
+}
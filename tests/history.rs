@@ -0,0 +1,42 @@
+mod common;
+
+use std::time::Duration;
+
+use common::record;
+use compare_speed::{read_history, HistoryStats};
+
+#[test]
+fn broken_lines_are_skipped_with_a_warning() {
+    let first = record((10, 20), &[("Hash", 100)]);
+    let second = record((10, 20), &[("Hash", 300), ("Merge", 50)]);
+    let mut log = Vec::new();
+    first.append(&mut log).unwrap();
+    log.extend_from_slice(b"{\"timestamp_ms\": 12\n\n");
+    second.append(&mut log).unwrap();
+    log.extend_from_slice(b"{\"timestamp_ms\": 1, \"host");
+
+    let (records, warnings) = read_history(&log[..]).unwrap();
+    assert_eq!(records, vec![first, second]);
+    assert_eq!(warnings.len(), 2);
+    assert!(warnings[0].starts_with("skipped line 2"), "{}", warnings[0]);
+    assert!(warnings[1].starts_with("skipped line 5"), "{}", warnings[1]);
+}
+
+#[test]
+fn stats_span_every_run_of_a_method() {
+    let records = [
+        record((10, 20), &[("Hash", 100), ("Merge", 50)]),
+        record((10, 20), &[("Hash", 300)]),
+        record((10, 20), &[("Hash", 200), ("Merge", 70)]),
+    ];
+    let stats = HistoryStats::collect(&records);
+
+    assert_eq!(stats.len(), 2);
+    assert_eq!(stats[0].name, "Hash");
+    assert_eq!(stats[0].runs, 3);
+    assert_eq!(stats[0].min, Duration::from_nanos(100));
+    assert_eq!(stats[0].median, Duration::from_nanos(200));
+    assert_eq!(stats[0].max, Duration::from_nanos(300));
+    assert_eq!(stats[1].name, "Merge");
+    assert_eq!(stats[1].median, Duration::from_nanos(60));
+}
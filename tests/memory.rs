@@ -0,0 +1,76 @@
+use std::sync::atomic::{AtomicUsize, Ordering::Relaxed};
+
+use compare_speed::{
+    print_table, run_benchmark_with, BenchmarkOptions, CountingAllocator, Flavor, Hash, Intersect,
+    Orders, Squared, TableOptions,
+};
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+#[test]
+fn allocations_are_counted_per_method() {
+    let methods: Vec<Box<dyn Intersect>> = vec![Box::new(Squared), Box::new(Hash)];
+    let options = BenchmarkOptions {
+        flavors: vec![Flavor::Sequential],
+        measure_memory: true,
+        ..BenchmarkOptions::default()
+    };
+    let a: Vec<usize> = (0..10_000).collect();
+    let b: Vec<usize> = (20_000..21_000).collect();
+    let products = run_benchmark_with(&methods, &a, &b, &options);
+
+    let memory = |name: &str| {
+        let product = products.iter().find(|p| p.name == name).unwrap();
+        product.memory.unwrap()
+    };
+    // Nothing is found, so Squared only allocates an empty result.
+    assert_eq!(memory("Squared seq").allocated, 0);
+    let hash = memory("Hash seq");
+    assert!(hash.allocated >= 1_000 * size_of::<usize>(), "{:?}", hash);
+    assert!(hash.peak > 0 && hash.peak <= hash.allocated, "{:?}", hash);
+
+    let mut out = Vec::new();
+    print_table(&mut out, &products, &[], &TableOptions::default(), false).unwrap();
+    let table = String::from_utf8(out).unwrap();
+    assert!(table.contains("Bytes allocated") && table.contains("Peak bytes"));
+    assert!(table.contains("KiB"), "{}", table);
+}
+
+static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+/// Counts how often it runs.
+struct Calls;
+
+impl Intersect for Calls {
+    fn intersect(&self, big: &[usize], small: &[usize]) -> Vec<usize> {
+        self.intersect_seq(big, small)
+    }
+
+    fn intersect_seq(&self, big: &[usize], _small: &[usize]) -> Vec<usize> {
+        CALLS.fetch_add(1, Relaxed);
+        big.to_vec()
+    }
+}
+
+#[test]
+fn allocations_are_counted_in_an_extra_untimed_run() {
+    let options = BenchmarkOptions {
+        flavors: vec![Flavor::Sequential],
+        orders: Orders::Normal,
+        repeats: 2,
+        warmup: 0,
+        measure_memory: true,
+        ..BenchmarkOptions::default()
+    };
+    let products = run_benchmark_with(
+        &[Box::new(Calls) as Box<dyn Intersect>],
+        &[1, 2, 3],
+        &[1],
+        &options,
+    );
+
+    assert_eq!(products[0].samples.len(), 2);
+    assert_eq!(CALLS.load(Relaxed), 3);
+    assert!(products[0].memory.unwrap().allocated >= 3 * size_of::<usize>());
+}
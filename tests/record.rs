@@ -0,0 +1,57 @@
+use std::time::Duration;
+
+use compare_speed::{Product, ProductRecord, RESULT_SAMPLE_LEN};
+
+fn round_trip(record: &ProductRecord) -> ProductRecord {
+    serde_json::from_str(&serde_json::to_string(record).unwrap()).unwrap()
+}
+
+#[test]
+fn records_round_trip_zero_and_multi_hour_times() {
+    let hours = Duration::from_secs(5 * 3600) + Duration::from_nanos(7);
+    let product = Product::new(
+        "Squared".to_string(),
+        vec![hours, Duration::ZERO, hours],
+        Duration::ZERO,
+        (0..100usize).collect(),
+    );
+    let record = ProductRecord::from(&product);
+
+    assert_eq!(record.time_ns, 18_000_000_000_007);
+    assert_eq!(record.min_ns, 0);
+    assert_eq!(record.max_ns, 18_000_000_000_007);
+    assert_eq!(record.runs, 3);
+    assert_eq!(record.result_len, 100);
+    assert_eq!(record.result_sample.len(), RESULT_SAMPLE_LEN);
+    assert_eq!(record.result_sample[..2], ["0", "1"]);
+    assert_eq!(round_trip(&record), record);
+
+    let zero = ProductRecord::from(&Product::new(
+        "Merge".to_string(),
+        vec![Duration::ZERO],
+        Duration::ZERO,
+        vec!["a".to_string()],
+    ));
+    assert_eq!((zero.time_ns, zero.mean_ns, zero.std_dev_ns), (0, 0, 0));
+    assert_eq!(zero.result_sample, ["\"a\""]);
+    assert_eq!(round_trip(&zero), zero);
+}
+
+#[test]
+fn records_of_products_that_did_not_finish_round_trip() {
+    let skipped = ProductRecord::from(&Product::<usize>::skipped(
+        "Simd".to_string(),
+        "no AVX2".to_string(),
+    ));
+    assert_eq!(skipped.skipped.as_deref(), Some("no AVX2"));
+    assert_eq!(skipped.runs, 0);
+    assert_eq!(round_trip(&skipped), skipped);
+
+    let limit = Duration::from_secs(3 * 3600);
+    let timed_out = ProductRecord::from(&Product::<usize>::timed_out("Squared".to_string(), limit));
+    assert_eq!(timed_out.timed_out_ns, Some(10_800_000_000_000));
+    assert_eq!(round_trip(&timed_out), timed_out);
+    assert!(!serde_json::to_string(&skipped)
+        .unwrap()
+        .contains("timed_out_ns"));
+}
@@ -0,0 +1,77 @@
+use compare_speed::{Binary, BloomHash, Hash, HashA, HashFx, Intersect, MethodRegistry, Squared};
+
+fn registry() -> MethodRegistry {
+    let mut registry = MethodRegistry::new();
+    let methods: Vec<Box<dyn Intersect>> = vec![
+        Box::new(Squared),
+        Box::new(Hash),
+        Box::new(BloomHash::new(0.01)),
+        Box::new(BloomHash::new(0.1)),
+        Box::new(Binary),
+    ];
+    for method in methods {
+        registry.register(method).unwrap();
+    }
+    registry
+}
+
+fn names(registry: &MethodRegistry) -> Vec<String> {
+    registry.iter().map(|method| method.name()).collect()
+}
+
+#[test]
+fn methods_keep_their_registration_order() {
+    assert_eq!(
+        names(&registry()),
+        [
+            "Squared",
+            "Hash",
+            "BloomHash(0.01)",
+            "BloomHash(0.1)",
+            "Binary"
+        ]
+    );
+}
+
+#[test]
+fn duplicate_names_are_rejected() {
+    let mut registry = registry();
+    let error = registry.register(Box::new(Hash)).unwrap_err();
+    assert_eq!(error, "method 'Hash' is already registered");
+    assert_eq!(registry.len(), 5);
+}
+
+#[test]
+fn methods_can_be_looked_up_by_name() {
+    let registry = registry();
+    assert!(registry.get("BloomHash(0.1)").is_some());
+    assert!(registry.get("Merge").is_none());
+}
+
+#[test]
+fn select_matches_names_without_parameters_ignoring_case() {
+    let selected = registry()
+        .select(&["bloomhash".to_string(), "SQUARED".to_string()])
+        .unwrap();
+    assert_eq!(
+        names(&selected),
+        ["Squared", "BloomHash(0.01)", "BloomHash(0.1)"]
+    );
+
+    let error = registry().select(&["merge".to_string()]).err().unwrap();
+    assert_eq!(
+        error,
+        "unknown method 'merge', valid methods are: Squared, Hash, BloomHash, Binary"
+    );
+}
+
+#[test]
+fn hasher_variants_are_selected_apart_from_hash() {
+    let mut registry = registry();
+    registry.register(Box::new(HashFx)).unwrap();
+    registry.register(Box::new(HashA)).unwrap();
+    let selected = registry
+        .select(&["hashfx".to_string(), "hash".to_string()])
+        .unwrap();
+    assert_eq!(names(&selected), ["Hash", "HashFx"]);
+}
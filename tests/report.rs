@@ -0,0 +1,1222 @@
+use std::time::Duration;
+
+use compare_speed::{
+    choose_layout, estimate_complexity, group_orders, normalize, print_chart, print_complexity,
+    print_graph, print_markdown, print_sweep, print_table, print_thread_sweep, throughput,
+    write_csv, write_dat, write_dat_sweep, write_html, write_svg, Aggregate, Column, Comparison,
+    DatOptions, Environment, GraphOptions, GraphScale, HtmlOptions, Layout, Op, Phases, Product,
+    Reference, Semantics, SortOrder, Summary, SvgOptions, SweepPoint, TableOptions, ThreadPoint,
+    TimeUnit, Verification,
+};
+
+fn product(name: &str, nanos: u64, result: Vec<usize>) -> Product {
+    Product::new(
+        name.to_string(),
+        vec![Duration::from_nanos(nanos)],
+        Duration::ZERO,
+        result,
+    )
+}
+
+fn render(products: &[Product], skipped: &[Product]) -> String {
+    render_with(products, skipped, &TableOptions::default())
+}
+
+fn render_with(products: &[Product], skipped: &[Product], options: &TableOptions) -> String {
+    let mut out = Vec::new();
+    print_table(&mut out, products, skipped, options, false).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+fn with_total() -> TableOptions {
+    TableOptions {
+        total: true,
+        ..TableOptions::default()
+    }
+}
+
+#[test]
+fn equal_times_are_one_times_faster() {
+    let comparison = Comparison::new(Duration::from_millis(2), Duration::from_millis(2));
+    assert_eq!(comparison.ratio(), "1.00x");
+    assert_eq!(comparison.percent(), "100.00%");
+    assert_eq!(comparison.difference(|d| format!("{:?}", d)), "0ns");
+}
+
+#[test]
+fn slower_times_have_a_negative_difference() {
+    let comparison = Comparison::new(Duration::from_millis(1), Duration::from_millis(4));
+    assert_eq!(comparison.ratio(), "0.25x");
+    assert_eq!(comparison.percent(), "400.00%");
+    assert_eq!(comparison.difference(|d| format!("{:?}", d)), "-3ms");
+}
+
+#[test]
+fn tables_with_equal_and_inverted_times_show_signed_differences() {
+    let products = [
+        product("A", 3_000, vec![]),
+        product("B", 3_000, vec![]),
+        product("C", 1_000, vec![]),
+        product("D", 4_000, vec![]),
+    ];
+    let table = render_with(&products, &[], &with_total());
+    // the ratio, the difference and the percentage of every row
+    let compared = |name: &str| {
+        let cells: Vec<_> = table
+            .lines()
+            .map(|line| line.split('|').map(str::trim).collect::<Vec<_>>())
+            .find(|cells| cells.get(1) == Some(&name))
+            .unwrap();
+        cells[3..6].join(" ")
+    };
+    assert_eq!(compared("B"), "1.00x 0ns 100.00%");
+    assert_eq!(compared("C"), "3.00x 2µs 33.33%");
+    assert_eq!(compared("D"), "0.25x -3µs 400.00%");
+    assert_eq!(compared("Total"), "0.75x -1µs 133.33%");
+}
+
+#[test]
+fn zero_durations_are_not_divided_by() {
+    let comparison = Comparison::new(Duration::ZERO, Duration::ZERO);
+    assert_eq!(comparison.ratio(), "n/a");
+    assert_eq!(comparison.percent(), "n/a");
+
+    let comparison = Comparison::new(Duration::from_nanos(5), Duration::ZERO);
+    assert_eq!(comparison.ratio(), "n/a");
+    assert_eq!(comparison.percent(), "0.00%");
+    let comparison = Comparison::new(Duration::ZERO, Duration::from_nanos(5));
+    assert_eq!(comparison.ratio(), "0.00x");
+    assert_eq!(comparison.percent(), "n/a");
+
+    let table = render(&[product("A", 0, vec![]), product("B", 0, vec![])], &[]);
+    assert!(table.contains("n/a"));
+    assert!(!table.contains("NaN") && !table.contains("inf"));
+}
+
+fn log_graph(products: &[Product]) -> String {
+    let graph = GraphOptions {
+        width: 30,
+        scale: GraphScale::Log,
+        ascii: true,
+        pairs: false,
+        labels: None,
+        axis: true,
+    };
+    let mut out = Vec::new();
+    print_graph(&mut out, products, graph, false).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn zero_durations_are_drawn_as_one_nanosecond() {
+    let graph = log_graph(&[
+        product("Slow", 1_000, vec![]),
+        product("One", 1, vec![]),
+        product("Zero", 0, vec![]),
+    ]);
+    assert!(
+        graph.contains(&format!("Slow: {}\n", "*".repeat(24))),
+        "{}",
+        graph
+    );
+    assert!(graph.contains("One : \n"), "{}", graph);
+    assert!(graph.contains("Zero: \n"), "{}", graph);
+    assert!(!graph.contains("NaN") && !graph.contains("inf"));
+}
+
+#[test]
+fn equal_fastest_and_slowest_times_draw_full_bars() {
+    for nanos in [0, 5_000] {
+        let graph = log_graph(&[product("A", nanos, vec![]), product("B", nanos, vec![])]);
+        let bars = format!("A: {0}\nB: {0}\n", "*".repeat(27));
+        assert!(graph.contains(&bars), "{}", graph);
+    }
+}
+
+#[test]
+fn table_lists_products_skipped_ones_and_the_total() {
+    let products = [
+        product("Slow", 4000, vec![1]),
+        product("Fast", 1000, vec![1]),
+    ];
+    let skipped = [Product::skipped(
+        "Bitset".to_string(),
+        "too big".to_string(),
+    )];
+    let table = render_with(&products, &skipped, &with_total());
+
+    assert!(table.contains("4.00x"));
+    assert!(table.contains("skipped: too big"));
+    assert!(table.contains("Total"));
+    assert!(table.find("Slow").unwrap() < table.find("Fast").unwrap());
+}
+
+#[test]
+fn ratios_compare_to_a_fixed_baseline() {
+    let products = [
+        product("Slow", 8000, vec![]),
+        product("Middle", 4000, vec![]),
+        product("Fast", 1000, vec![]),
+    ];
+    let options = TableOptions {
+        reference: Reference::Fastest,
+        ..TableOptions::default()
+    };
+    let table = render_with(&products, &[], &options);
+
+    assert!(table.contains("times faster than baseline"));
+    assert!(table.contains("Fast (baseline)"));
+    assert!(table.contains("0.12x") && table.contains("800.00%"));
+    assert!(table.contains("0.25x") && table.contains("400.00%"));
+
+    let unknown = Reference::Method("Hash".to_string());
+    let error = unknown.resolve(&products).unwrap_err();
+    assert!(error.contains("Slow, Middle, Fast"), "{}", error);
+    assert_eq!(
+        Reference::Method("Middle".to_string()).resolve(&products),
+        Ok(Some(1))
+    );
+}
+
+#[test]
+fn times_are_compared_to_the_fastest() {
+    let products = [product("Slow", 4000, vec![]), product("Fast", 1000, vec![])];
+    let table = render_with(&products, &[], &with_total());
+    let cells = |name: &str| {
+        let line = table
+            .lines()
+            .find(|line| line.starts_with(&format!("| {} ", name)));
+        line.unwrap().split('|').map(str::trim).collect::<Vec<_>>()
+    };
+
+    assert!(table.contains("vs fastest"));
+    assert_eq!(cells("Slow")[7], "4.00x");
+    assert_eq!(cells("Fast")[7], "1.00x");
+    assert_eq!(cells("Total")[7], "5.00x");
+}
+
+#[test]
+fn summary_names_the_extremes_and_the_geometric_mean() {
+    let products = [
+        product("Slow", 8000, vec![]),
+        product("Middle", 4000, vec![]),
+        product("Fast", 2000, vec![]),
+    ];
+    let summary = Summary::new(&products, Duration::from_millis(3)).unwrap();
+
+    assert_eq!(summary.wall_time, Duration::from_millis(3));
+    assert_eq!(
+        summary.fastest,
+        ("Fast".to_string(), Duration::from_nanos(2000))
+    );
+    assert_eq!(
+        summary.slowest,
+        ("Slow".to_string(), Duration::from_nanos(8000))
+    );
+    assert_eq!(summary.geometric_mean, Duration::from_nanos(4000));
+    assert_eq!(summary.spread, Some(4.0));
+    assert!(!render(&products, &[]).contains("Total"));
+}
+
+#[test]
+fn time_units_format_nanoseconds_to_minutes() {
+    let durations = [
+        Duration::from_nanos(7),
+        Duration::from_nanos(1_234_567),
+        Duration::from_secs(150) + Duration::from_nanos(5),
+    ];
+    let formatted = |unit: TimeUnit| durations.map(|duration| unit.format(duration));
+
+    assert_eq!(formatted(TimeUnit::Auto), ["7ns", "1.23ms", "150.00s"]);
+    assert_eq!(
+        formatted(TimeUnit::Ns),
+        ["7ns", "1234567ns", "150000000005ns"]
+    );
+    assert_eq!(
+        formatted(TimeUnit::Us),
+        ["0.007µs", "1234.567µs", "150000000.005µs"]
+    );
+    assert_eq!(
+        formatted(TimeUnit::Ms),
+        ["0.000ms", "1.235ms", "150000.000ms"]
+    );
+    assert_eq!(formatted(TimeUnit::S), ["0.000s", "0.001s", "150.000s"]);
+}
+
+#[test]
+fn tables_and_summaries_show_every_time_in_one_unit() {
+    let products = [
+        product("Squared", 2_000_000, vec![]),
+        product("Hash", 1500, vec![]),
+    ];
+    let options = TableOptions {
+        time_unit: TimeUnit::Us,
+        ..TableOptions::default()
+    };
+    let table = render_with(&products, &[], &options);
+    assert!(table.contains("| 2000.000µs "), "{}", table);
+    assert!(table.contains("| 1.500µs "), "{}", table);
+    assert!(table.contains("| 1998.500µs "), "{}", table);
+
+    let summary = Summary::new(&products, Duration::from_millis(3)).unwrap();
+    let mut out = Vec::new();
+    summary.write(&mut out, TimeUnit::Ms).unwrap();
+    let summary = String::from_utf8(out).unwrap();
+    assert!(
+        summary.contains("the benchmark took 3.000ms\n"),
+        "{}",
+        summary
+    );
+    assert!(summary.contains("fastest: Hash (0.002ms)\n"), "{}", summary);
+}
+
+#[test]
+fn summary_ignores_timed_out_products_and_zero_times() {
+    let products = [
+        Product::timed_out("Squared".to_string(), Duration::from_secs(2)),
+        product("Hash", 1000, vec![]),
+        product("Merge", 0, vec![]),
+    ];
+    let summary = Summary::new(&products, Duration::from_secs(3)).unwrap();
+
+    assert_eq!(summary.slowest.0, "Hash");
+    assert_eq!(summary.fastest.0, "Merge");
+    // the zero time counts as 1ns, so the mean is the square root of 1000ns
+    assert_eq!(summary.geometric_mean, Duration::from_nanos(32));
+    assert_eq!(summary.spread, None);
+
+    let timed_out: [Product; 1] = [Product::timed_out(
+        "Squared".to_string(),
+        Duration::from_secs(2),
+    )];
+    assert_eq!(Summary::new(&timed_out, Duration::from_secs(2)), None);
+    assert_eq!(Summary::new::<usize>(&[], Duration::ZERO), None);
+}
+
+#[test]
+fn html_has_the_table_and_log_scaled_bars() {
+    let products = [
+        product("Slow<1>", 1_000_000, vec![]),
+        product("Middle", 1000, vec![]),
+        product("Fast", 1, vec![]),
+    ];
+    let options = HtmlOptions {
+        seed: 7,
+        sizes: (10, 20),
+        table: TableOptions::default(),
+        scale: GraphScale::Log,
+        all_equal: true,
+    };
+    let mut out = Vec::new();
+    write_html(&mut out, &products, &[], &options).unwrap();
+    let html = String::from_utf8(out).unwrap();
+
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("sizes 10 and 20 (seed 7)"));
+    assert!(html.contains("<th>times faster than previous</th>"));
+    assert!(html.contains("Slow&lt;1&gt;") && !html.contains("Slow<1>"));
+    assert!(html.contains("width: 100.00%"));
+    assert!(html.contains("width: 50.00%"));
+    assert!(html.contains("width: 0.00%"));
+    assert!(html.contains("All values are equal: yes"));
+    assert!(!html.contains("<script") && !html.contains("http"));
+}
+
+#[test]
+fn svg_matches_the_golden_file() {
+    let products = [
+        Product::timed_out("Squared".to_string(), Duration::from_secs(2)),
+        product("Hash & \"Co\" switched order", 1_000_000, vec![]),
+        product("Merge", 1000, vec![]),
+        product("Binary<1>", 1, vec![]),
+    ];
+    let options = SvgOptions {
+        seed: 7,
+        sizes: (10, 20),
+        scale: GraphScale::Log,
+        time_unit: TimeUnit::Auto,
+    };
+    let mut out = Vec::new();
+    write_svg(&mut out, &products, &options).unwrap();
+    let svg = String::from_utf8(out).unwrap();
+
+    assert_eq!(svg, include_str!("golden/graph.svg"));
+}
+
+#[test]
+fn the_layout_is_compact_when_the_full_table_is_too_wide() {
+    // a table takes one character more than its columns and three per column
+    assert_eq!(choose_layout(28, &[10, 11], &[10, 4]), Layout::Full);
+    assert_eq!(
+        choose_layout(27, &[10, 11], &[10, 4]),
+        Layout::Compact { name_width: None }
+    );
+    assert_eq!(
+        choose_layout(18, &[20, 11], &[20, 4]),
+        Layout::Compact {
+            name_width: Some(16)
+        }
+    );
+    assert_eq!(
+        choose_layout(30, &[20, 11], &[20, 4]),
+        Layout::Compact {
+            name_width: Some(19)
+        }
+    );
+}
+
+#[test]
+fn narrow_tables_drop_columns_and_shorten_the_names() {
+    let products = [
+        product("PartitionedHash seq switched order", 2000, vec![]),
+        product("Hash", 1000, vec![]),
+    ];
+    let narrow = TableOptions {
+        width: Some(67),
+        ..TableOptions::default()
+    };
+    let table = render_with(&products, &[], &narrow);
+    let lines: Vec<&str> = table.lines().collect();
+
+    assert!(
+        lines.iter().all(|line| line.chars().count() <= 67),
+        "{}",
+        table
+    );
+    assert!(lines[1].starts_with("| Name  "), "{}", table);
+    assert!(lines[1].contains("| x prev |"), "{}", table);
+    assert!(!table.contains("Compared to"), "{}", table);
+    assert!(table.contains("| P…h seq switched |"), "{}", table);
+
+    let wide = TableOptions {
+        width: None,
+        ..narrow
+    };
+    let table = render_with(&products, &[], &wide);
+    assert!(table.contains("| PartitionedHash seq switched order |"));
+    assert!(table.contains("| Absolute time difference |"));
+}
+
+#[test]
+fn shortened_names_stay_distinct() {
+    let products: Vec<Product> = [
+        "SquaredBreak switched order",
+        "SquaredBreak seq switched order",
+        "SquaredBreak",
+        "SquaredBreak seq",
+        "PartitionedHash(3) seq",
+        "PartitionedHash(4) seq",
+    ]
+    .iter()
+    .enumerate()
+    .map(|(i, name)| product(name, 1000 * (i as u64 + 1), vec![]))
+    .collect();
+    let narrow = TableOptions {
+        width: Some(60),
+        ..TableOptions::default()
+    };
+    let table = render_with(&products, &[], &narrow);
+    let names: Vec<&str> = table
+        .lines()
+        .filter(|line| line.starts_with('|'))
+        .skip(1)
+        .map(|line| line.split('|').nth(1).unwrap().trim())
+        .collect();
+
+    assert_eq!(names.len(), products.len(), "{}", table);
+    for (i, name) in names.iter().enumerate() {
+        assert!(!names[..i].contains(name), "{}", table);
+    }
+    assert!(names.contains(&"Squ…eak switched"), "{}", table);
+    assert!(names.contains(&"S…k seq switched"), "{}", table);
+    assert!(
+        names.iter().any(|name| name.ends_with("(3) seq")),
+        "{}",
+        table
+    );
+}
+
+#[test]
+fn narrow_tables_move_skip_reasons_into_footnotes() {
+    let reason = "quadratic methods are skipped above 100000 elements";
+    let skipped = [
+        Product::skipped("Squared".to_string(), reason.to_string()),
+        Product::skipped("SquaredBreak".to_string(), reason.to_string()),
+    ];
+    let products = [
+        Product::timed_out("BTree".to_string(), Duration::from_secs(2)),
+        product("Hash", 1000, vec![]),
+    ];
+    let narrow = TableOptions {
+        width: Some(70),
+        ..TableOptions::default()
+    };
+    let table = render_with(&products, &skipped, &narrow);
+
+    assert!(
+        table
+            .lines()
+            .filter(|line| !line.starts_with('['))
+            .all(|line| line.chars().count() <= 70),
+        "{}",
+        table
+    );
+    assert!(table.contains("| > 2s [1] "), "{}", table);
+    assert_eq!(table.matches("| skipped [2] ").count(), 2, "{}", table);
+    assert!(table.ends_with(&format!("[1] timed out\n[2] skipped: {}\n", reason)));
+
+    let wide = TableOptions {
+        width: None,
+        ..narrow
+    };
+    let table = render_with(&products, &skipped, &wide);
+    assert!(table.contains(&format!("skipped: {}", reason)), "{}", table);
+    assert!(!table.contains("[1]"), "{}", table);
+}
+
+#[test]
+fn only_the_selected_columns_are_shown_in_order() {
+    let products = [
+        product("Slow", 4000, vec![1, 2]),
+        product("Fast", 1000, vec![1, 2]),
+    ];
+    let options = TableOptions {
+        total: true,
+        columns: vec![Column::Time, Column::Name, Column::Matches],
+        ..TableOptions::default()
+    };
+    let table = render_with(&products, &[], &options);
+    let rows: Vec<Vec<&str>> = table
+        .lines()
+        .filter(|line| line.starts_with('|'))
+        .map(|line| line.trim_matches('|').split('|').map(str::trim).collect())
+        .collect();
+
+    assert_eq!(rows[0], ["Time taken", "Name", "Matches"]);
+    assert_eq!(rows[1], ["4µs", "Slow", "2"]);
+    assert_eq!(rows[3], ["5µs", "Total", "-"]);
+    assert!(rows.iter().all(|row| row.len() == 3));
+}
+
+#[test]
+fn the_graph_does_not_depend_on_the_order() {
+    let mut products = vec![
+        product("Slow", 1_000_000, vec![]),
+        product("Middle", 1000, vec![]),
+        product("Fast", 1, vec![]),
+    ];
+    let graph = GraphOptions {
+        width: 30,
+        scale: GraphScale::Log,
+        ascii: true,
+        pairs: false,
+        labels: None,
+        axis: false,
+    };
+    let bars = |products: &[Product]| {
+        let mut out = Vec::new();
+        print_graph(&mut out, products, graph, false).unwrap();
+        let mut lines: Vec<String> = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(String::from)
+            .collect();
+        lines.sort();
+        lines
+    };
+    let descending = bars(&products);
+
+    SortOrder::TimeAsc.sort(&mut products);
+    assert_eq!(products[0].name, "Fast");
+    assert_eq!(bars(&products), descending);
+    let summary = Summary::new(&products, Duration::ZERO).unwrap();
+    assert_eq!(
+        (summary.fastest.0.as_str(), summary.slowest.0.as_str()),
+        ("Fast", "Slow")
+    );
+
+    SortOrder::Name.sort(&mut products);
+    let names: Vec<&str> = products.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, ["Fast", "Middle", "Slow"]);
+    assert_eq!(bars(&products), descending);
+    assert_eq!(Reference::Fastest.resolve(&products), Ok(Some(0)));
+    assert_eq!(Reference::Slowest.resolve(&products), Ok(Some(2)));
+}
+
+#[test]
+fn markdown_escapes_pipes() {
+    let products = [product("a|b", 2000, vec![]), product("c", 1000, vec![])];
+    let mut out = Vec::new();
+    print_markdown(&mut out, &products, &[], &TableOptions::default()).unwrap();
+    let markdown = String::from_utf8(out).unwrap();
+
+    assert!(markdown.starts_with("| Name | Time taken |"));
+    assert!(markdown.contains("| --- |"));
+    assert!(markdown.contains("a\\|b"));
+    assert!(markdown.contains("2.00µs"));
+}
+
+#[test]
+fn timed_out_products_are_marked() {
+    let products = [
+        Product::timed_out("Squared".to_string(), Duration::from_secs(2)),
+        product("Hash", 1000, vec![]),
+    ];
+    let table = render(&products, &[]);
+    assert!(table.contains("> 2s (timed out)"), "{}", table);
+
+    let graph = GraphOptions {
+        width: 30,
+        scale: GraphScale::Linear,
+        ascii: true,
+        pairs: false,
+        labels: None,
+        axis: false,
+    };
+    let mut out = Vec::new();
+    print_graph(&mut out, &products, graph, false).unwrap();
+    let graph = String::from_utf8(out).unwrap();
+    let bar = graph
+        .lines()
+        .find(|line| line.starts_with("Squared"))
+        .unwrap();
+    assert_eq!(bar, format!("Squared: {}>", "*".repeat(20)));
+}
+
+#[test]
+fn graph_bars_fit_the_width() {
+    let products = [
+        product("Slow", 1_000_000, vec![]),
+        product("Fast", 1, vec![]),
+    ];
+    let graph = GraphOptions {
+        width: 30,
+        scale: GraphScale::Linear,
+        ascii: true,
+        pairs: false,
+        labels: None,
+        axis: false,
+    };
+    let mut out = Vec::new();
+    print_graph(&mut out, &products, graph, false).unwrap();
+    let graph = String::from_utf8(out).unwrap();
+
+    assert!(graph.contains("times as a linear graph"));
+    assert!(graph.contains(&format!("Slow: {}\n", "*".repeat(24))));
+    assert!(graph.contains("Fast: \n"));
+}
+
+#[test]
+fn graph_labels_nearly_filling_the_line_fit_the_width() {
+    let products = [
+        product("Squared switched order", 1_234_567, vec![]),
+        Product::timed_out("BTree seq".to_string(), Duration::from_secs(90)),
+        product("Hash", 7, vec![]),
+    ];
+    let graph = GraphOptions {
+        width: 50,
+        scale: GraphScale::Linear,
+        ascii: true,
+        pairs: false,
+        labels: Some(TimeUnit::Auto),
+        axis: false,
+    };
+    let mut out = Vec::new();
+    print_graph(&mut out, &products, graph, false).unwrap();
+    let lines: Vec<String> = String::from_utf8(out)
+        .unwrap()
+        .lines()
+        .skip(2)
+        .map(String::from)
+        .collect();
+
+    // the name column of 24 characters and the label of 19 leave 7 for the bars
+    assert_eq!(
+        lines,
+        [
+            "Squared switched order:  1.23ms",
+            "BTree seq             : ******> 90.00s (timed out)",
+            "Hash                  :  7ns",
+        ]
+    );
+    assert_eq!(lines[1].chars().count(), 50);
+
+    let graph = GraphOptions {
+        labels: Some(TimeUnit::Ns),
+        width: 10,
+        ..graph
+    };
+    let mut out = Vec::new();
+    print_graph(&mut out, &products, graph, false).unwrap();
+    assert!(String::from_utf8(out)
+        .unwrap()
+        .contains("Hash                  : \n"));
+}
+
+#[test]
+fn log_axis_ticks_every_power_of_ten_or_labels_the_extremes() {
+    let products = [
+        product("Slow", 1_000_000, vec![]),
+        product("Fast", 100, vec![]),
+    ];
+    let graph = GraphOptions {
+        width: 30,
+        scale: GraphScale::Log,
+        ascii: true,
+        pairs: false,
+        labels: None,
+        axis: true,
+    };
+    let axis = |graph: GraphOptions| {
+        let mut out = Vec::new();
+        print_graph(&mut out, &products, graph, false).unwrap();
+        String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .skip(4)
+            .map(String::from)
+            .collect::<Vec<_>>()
+    };
+
+    // 36 columns span the 4 decades from 100ns to 1ms, 9 columns each
+    assert_eq!(
+        axis(GraphOptions { width: 42, ..graph }),
+        [
+            "      +--------+--------+--------+-------+",
+            "      100ns    1µs      10µs     100µs 1ms",
+            "log scale, ticks at powers of ten",
+        ]
+    );
+    // the last label moved left would touch the one before
+    assert_eq!(
+        axis(graph),
+        [
+            "      +----------------------+",
+            "      100ns             1.00ms",
+            "log scale, ticks at powers of ten",
+        ]
+    );
+    assert_eq!(
+        axis(GraphOptions { width: 14, ..graph }),
+        ["      +------+", "log scale, ticks at powers of ten"]
+    );
+    assert_eq!(
+        axis(GraphOptions {
+            scale: GraphScale::Linear,
+            ..graph
+        }),
+        Vec::<String>::new()
+    );
+}
+
+#[test]
+fn csv_has_a_record_per_product() {
+    let products = [product("Hash", 1500, vec![1, 2])];
+    let skipped = [Product::skipped("Simd".to_string(), "no SIMD".to_string())];
+    let mut out = Vec::new();
+    write_csv(&mut out, &products, &skipped, 10, 20).unwrap();
+
+    let environment = Environment::current();
+    let environment = format!(
+        "{},{},{},{},{},{}",
+        environment.cpus,
+        environment.threads,
+        environment.target,
+        environment.profile,
+        environment.version,
+        environment.rustc
+    );
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        format!(
+            "name,time_ns,result_len,size_a,size_b,skipped,throughput_elem_per_s,\
+             cpus,threads,target,profile,version,rustc\n\
+             Hash,1500,2,10,20,,20000000,{0}\nSimd,,0,10,20,no SIMD,,{0}\n",
+            environment
+        )
+    );
+}
+
+#[test]
+fn throughput_is_shown_with_si_prefixes() {
+    let products = [
+        product("Slow", 2_000_000, vec![]),
+        product("Fast", 2430, vec![]),
+        product("Instant", 0, vec![]),
+    ];
+    let options = TableOptions {
+        elements: Some(1_000_000),
+        ..TableOptions::default()
+    };
+    let table = render_with(&products, &[], &options);
+
+    assert!(table.contains("Throughput"));
+    assert!(table.contains("500 Melem/s"), "{}", table);
+    assert!(table.contains("412 Gelem/s"), "{}", table);
+    assert!(!table.contains("inf"), "{}", table);
+    assert!(!render(&products, &[]).contains("Throughput"));
+    assert_eq!(throughput(10, Duration::from_millis(2)), Some(5000.0));
+    assert_eq!(throughput(10, Duration::ZERO), None);
+}
+
+#[test]
+fn normalize_ignores_order_and_duplicates_unless_multiset() {
+    assert_eq!(normalize(&[3, 1, 3, 2], false), [1, 2, 3]);
+    assert_eq!(normalize(&[3, 1, 3, 2], true), [1, 2, 3, 3]);
+}
+
+fn verify(products: &[Product], semantics: Semantics) -> (bool, String) {
+    let verification = Verification::new(products, Op::Intersection, semantics, 5);
+    let mut out = Vec::new();
+    verification.write(&mut out).unwrap();
+    (verification.passed(), String::from_utf8(out).unwrap())
+}
+
+#[test]
+fn results_with_duplicates_agree_in_any_order() {
+    // positionally these differ, which used to report "all values are equal: false"
+    let products = [
+        product("Squared", 2, vec![3, 1, 3, 2, 3]),
+        product("Hash", 1, vec![2, 3, 3, 1, 3]),
+    ];
+    assert!(verify(&products, Semantics::MultisetBig).0);
+    assert!(verify(&products, Semantics::Pairs).0);
+
+    let products = [
+        product("Squared", 2, vec![3, 1, 2]),
+        product("Hash", 1, vec![2, 3, 1]),
+    ];
+    assert!(verify(&products, Semantics::Set).0);
+}
+
+#[test]
+fn different_duplicate_counts_fail() {
+    let products = [
+        product("Squared", 2, vec![1, 1, 2]),
+        product("Hash", 1, vec![2, 1]),
+    ];
+    let (passed, out) = verify(&products, Semantics::Set);
+    assert!(!passed);
+    assert!(out.contains("Squared found the same elements as Hash"));
+}
+
+#[test]
+fn duplicates_are_counted_per_product() {
+    let products = [
+        product("Squared", 2, vec![1, 1, 2]),
+        product("Hash", 1, vec![2, 1]),
+        counted("Merge", 3),
+    ];
+    let (_, out) = verify(&products, Semantics::Set);
+    assert!(out.contains("the results hold duplicates:"));
+    assert!(out.contains("| Squared | 3        | 2        |"), "{}", out);
+    assert!(out.contains("| Hash    | 2        | 2        |"), "{}", out);
+    assert!(!out.contains("Merge  "), "{}", out);
+
+    let (_, out) = verify(&products[1..], Semantics::Set);
+    assert!(!out.contains("duplicates"), "{}", out);
+}
+
+#[test]
+fn multiset_big_compares_the_orders_apart() {
+    let mut switched = product("Hash switched order", 1, vec![1, 2, 2]);
+    switched.switched = true;
+    let products = [product("Hash", 2, vec![1, 1, 2]), switched];
+
+    assert!(verify(&products, Semantics::MultisetBig).0);
+    assert!(!verify(&products, Semantics::Pairs).0);
+}
+
+#[test]
+fn disagreeing_methods_are_reported() {
+    let products = [
+        product("Broken", 2, vec![1, 4]),
+        product("Hash", 1, vec![1, 2]),
+    ];
+    let (passed, out) = verify(&products, Semantics::Set);
+
+    assert!(!passed);
+    assert!(out.contains("all values are equal: false"));
+    assert!(out.contains("Broken disagrees with Hash: 2 elements instead of 2, 1 missing, 1 extra"));
+    assert!(out.contains("missing: [2]"));
+    assert!(out.contains("extra: [4]"));
+}
+
+#[test]
+fn symmetric_operations_compare_both_orders() {
+    let mut switched = product("Hash switched order", 1, vec![1, 3]);
+    switched.switched = true;
+    let products = [product("Hash", 2, vec![1, 2]), switched];
+
+    assert!(!Verification::new(&products, Op::SymmetricDifference, Semantics::Set, 5).passed());
+    assert!(Verification::new(&products, Op::Difference, Semantics::Set, 5).passed());
+}
+
+fn counted(name: &str, count: usize) -> Product {
+    Product {
+        count: Some(count),
+        ..product(name, 1, vec![])
+    }
+}
+
+#[test]
+fn counts_are_shown_and_compared() {
+    let products = [counted("Squared", 4), counted("Hash", 3)];
+    let table = render(&products, &[]);
+    assert!(table.contains("Matches"));
+    assert!(
+        table.contains("4 (mismatch)") && table.contains("| 3 "),
+        "{}",
+        table
+    );
+
+    let (passed, out) = verify(&products, Semantics::Set);
+    assert!(!passed);
+    assert!(out.contains("Squared counted 4 elements instead of 3 like Hash"));
+    assert!(verify(&[counted("Binary", 3), counted("Hash", 3)], Semantics::Set).0);
+}
+
+fn sampled(name: &str, nanos: &[u64]) -> Product {
+    let samples = nanos.iter().map(|&x| Duration::from_nanos(x)).collect();
+    Product::new(name.to_string(), samples, Duration::ZERO, vec![])
+}
+
+#[test]
+fn adjacent_rows_are_tested_for_significance() {
+    let products = [
+        sampled("Squared", &[900, 950, 1000, 1050]),
+        sampled("BTree", &[100, 110, 120, 130]),
+        sampled("Hash", &[100, 115, 125, 105]),
+    ];
+    let table = render_with(&products, &[], &with_total());
+    let cell = |row: usize| {
+        let line = table.lines().nth(row * 2 + 1).unwrap();
+        line.trim_end_matches('|')
+            .rsplit('|')
+            .next()
+            .unwrap()
+            .trim()
+            .to_string()
+    };
+    assert_eq!(cell(0), "Significance");
+    assert_eq!(cell(1), "-");
+    assert!(cell(2).starts_with("significant"), "{}", cell(2));
+    assert!(cell(3).starts_with("noise"), "{}", cell(3));
+    assert_eq!(cell(4), "-");
+
+    let single = render(&[product("Hash", 1, vec![])], &[]);
+    assert!(!single.contains("Significance"));
+}
+
+#[test]
+fn the_aggregate_is_named_in_the_header() {
+    let fastest = Product {
+        aggregate: Aggregate::Min,
+        ..sampled("Hash", &[1, 2, 3])
+    };
+    let header = |products: &[Product]| {
+        let table = render(products, &[]);
+        table
+            .lines()
+            .nth(1)
+            .unwrap()
+            .split('|')
+            .nth(2)
+            .unwrap()
+            .trim()
+            .to_string()
+    };
+    assert_eq!(header(&[fastest]), "Min");
+    assert_eq!(header(&[sampled("Hash", &[1, 2])]), "Median");
+    assert_eq!(header(&[product("Hash", 1, vec![])]), "Time taken");
+}
+
+#[test]
+fn phases_are_shown_next_to_the_time() {
+    let phased = Product {
+        phases: Some(Phases {
+            setup: Duration::from_nanos(3),
+            probe: Duration::from_nanos(4),
+        }),
+        ..product("Hash", 7, vec![])
+    };
+    let table = render(&[phased], &[]);
+    let header = table.lines().nth(1).unwrap();
+    assert!(header.contains("Setup time") && header.contains("Probe time"));
+    assert!(
+        table.contains("| 3ns ") && table.contains("| 4ns "),
+        "{}",
+        table
+    );
+}
+
+#[test]
+fn sweep_table_has_a_column_per_size() {
+    let points = [
+        SweepPoint {
+            big: 10,
+            small: 5,
+            products: vec![
+                product("Hash", 1000, vec![]),
+                product("Squared", 500, vec![]),
+            ],
+        },
+        SweepPoint {
+            big: 100,
+            small: 50,
+            products: vec![
+                product("Hash", 3000, vec![]),
+                Product::skipped("Squared".to_string(), "too big".to_string()),
+            ],
+        },
+    ];
+    let mut out = Vec::new();
+    print_sweep(&mut out, &points).unwrap();
+    let table = String::from_utf8(out).unwrap();
+
+    assert!(table.contains("10 / 5") && table.contains("100 / 50"));
+    let squared = table.lines().find(|line| line.contains("Squared")).unwrap();
+    assert!(squared.contains("500ns") && squared.contains("skipped"));
+    assert!(table.find("Hash").unwrap() < table.find("Squared").unwrap());
+}
+
+fn sweep_point(big: usize, products: Vec<Product>) -> SweepPoint {
+    SweepPoint {
+        big,
+        small: big / 2,
+        products,
+    }
+}
+
+#[test]
+fn dat_has_a_block_per_size_without_the_skipped_products() {
+    let points = [
+        sweep_point(
+            10,
+            vec![
+                product("Hash \"fast\"", 1000, vec![]),
+                product("Squared switched order", 500, vec![]),
+            ],
+        ),
+        sweep_point(
+            100,
+            vec![
+                product("Hash \"fast\"", 3000, vec![]),
+                Product::skipped("Squared".to_string(), "too big".to_string()),
+                Product::timed_out("Merge".to_string(), Duration::from_secs(1)),
+            ],
+        ),
+    ];
+    let options = DatOptions {
+        seed: 7,
+        op: Op::Intersection,
+        semantics: Semantics::Set,
+    };
+    let mut out = Vec::new();
+    write_dat_sweep(&mut out, &points, &options).unwrap();
+    let dat = String::from_utf8(out).unwrap();
+
+    let (header, data): (Vec<&str>, Vec<&str>) = dat.lines().partition(|l| l.starts_with('#'));
+    assert!(header.iter().any(|l| l.contains("seed 7")));
+    assert!(header.iter().any(|l| l.contains("time in nanoseconds")));
+    assert_eq!(
+        data,
+        [
+            "0 \"Hash 'fast'\" 1000 10 5",
+            "1 \"Squared switched order\" 500 10 5",
+            "",
+            "",
+            "0 \"Hash 'fast'\" 3000 100 50",
+        ]
+    );
+
+    let mut out = Vec::new();
+    write_dat(&mut out, &points[0].products, (5, 10), &options).unwrap();
+    let dat = String::from_utf8(out).unwrap();
+    assert!(dat.ends_with("\n1 \"Squared switched order\" 500 5 10\n"));
+    assert!(!dat.contains("blank lines"));
+}
+
+#[test]
+fn complexity_is_fitted_from_at_least_three_points() {
+    let points: Vec<SweepPoint> = [100, 1000, 10_000]
+        .into_iter()
+        .map(|n| {
+            let mut products = vec![
+                product("Linear", 10 * n, vec![]),
+                product("Quadratic", n * n, vec![]),
+            ];
+            if n < 10_000 {
+                products.push(product("Short", n, vec![]));
+            }
+            sweep_point(n as usize, products)
+        })
+        .collect();
+    let complexity = estimate_complexity(&points);
+    let mut out = Vec::new();
+    print_complexity(&mut out, &complexity).unwrap();
+    let out = String::from_utf8(out).unwrap();
+
+    assert!(
+        out.contains("Linear: ~O(n^1.00), closest to O(n)"),
+        "{}",
+        out
+    );
+    assert!(
+        out.contains("Quadratic: ~O(n^2.00), closest to O(n*m)"),
+        "{}",
+        out
+    );
+    assert!(
+        out.contains("Short: insufficient data (2 of the 3 points needed)"),
+        "{}",
+        out
+    );
+}
+
+fn render_chart(points: &[SweepPoint]) -> String {
+    let mut out = Vec::new();
+    print_chart(&mut out, points, 50).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn chart_has_axes_and_a_legend() {
+    let points: Vec<SweepPoint> = [10, 100, 1000]
+        .into_iter()
+        .map(|n| {
+            sweep_point(
+                n as usize,
+                vec![product("Slow", n * n, vec![]), product("Fast", n, vec![])],
+            )
+        })
+        .collect();
+    let chart = render_chart(&points);
+
+    assert!(chart.contains("1.00ms |"), "{}", chart);
+    assert!(chart.contains("10ns |"), "{}", chart);
+    assert!(
+        chart.contains("10") && chart.contains("1000\n"),
+        "{}",
+        chart
+    );
+    assert!(chart.contains("a: Slow\nb: Fast"), "{}", chart);
+    assert!(
+        chart.lines().all(|line| line.chars().count() <= 50),
+        "{}",
+        chart
+    );
+    assert!(!chart.contains("only one size"));
+
+    let single = render_chart(&points[..1]);
+    assert!(single.contains("only one size ran"), "{}", single);
+}
+
+#[test]
+fn thread_sweep_shows_the_speedup_over_one_thread() {
+    let points = [
+        ThreadPoint {
+            threads: 1,
+            products: vec![product("Hash", 4000, vec![])],
+        },
+        ThreadPoint {
+            threads: 4,
+            products: vec![product("Hash", 1000, vec![])],
+        },
+    ];
+    let mut out = Vec::new();
+    print_thread_sweep(&mut out, &points).unwrap();
+    let table = String::from_utf8(out).unwrap();
+
+    assert!(
+        table.contains("1 thread ") && table.contains("4 threads"),
+        "{}",
+        table
+    );
+    assert!(table.contains("4.00µs (1.00x)"), "{}", table);
+    assert!(table.contains("1.00µs (4.00x)"), "{}", table);
+}
+
+#[test]
+fn grouped_orders_are_adjacent_with_their_sensitivity() {
+    let switched = |name: &str, nanos: u64| Product {
+        switched: true,
+        ..product(name, nanos, vec![])
+    };
+    let mut products = vec![
+        switched("Merge switched order", 4000),
+        product("Hash", 3000, vec![]),
+        product("Merge", 2000, vec![]),
+        switched("Hash switched order", 1000),
+    ];
+    group_orders(&mut products);
+    let names: Vec<&str> = products.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(
+        names,
+        [
+            "Merge",
+            "Merge switched order",
+            "Hash",
+            "Hash switched order"
+        ]
+    );
+
+    let options = TableOptions {
+        columns: vec![Column::Name, Column::OrderSensitivity],
+        group_orders: true,
+        ..TableOptions::default()
+    };
+    let table = render_with(&products, &[], &options);
+    assert!(
+        table.contains("| Merge                | 2.00x (normal order faster)   |"),
+        "{}",
+        table
+    );
+    assert!(
+        table.contains("| Hash                 | 3.00x (switched order faster) |"),
+        "{}",
+        table
+    );
+    assert!(
+        table.contains("| Merge switched order | -                             |"),
+        "{}",
+        table
+    );
+
+    let graph = GraphOptions {
+        width: 30,
+        scale: GraphScale::Linear,
+        ascii: true,
+        pairs: true,
+        labels: None,
+        axis: false,
+    };
+    let mut out = Vec::new();
+    print_graph(&mut out, &products, graph, false).unwrap();
+    let out = String::from_utf8(out).unwrap();
+    assert!(out.contains("Merge switched order: ***"), "{}", out);
+    assert!(out.contains("\n\nHash "), "{}", out);
+}
+
+#[test]
+fn runs_until_stable_flag_the_products_that_did_not_converge() {
+    let products = [
+        Product {
+            converged: Some(false),
+            ..sampled("Squared", &[900])
+        },
+        Product {
+            converged: Some(true),
+            ..sampled("Hash", &[100, 101, 102])
+        },
+    ];
+    let options = TableOptions {
+        columns: vec![Column::Name, Column::Runs],
+        ..TableOptions::default()
+    };
+    let table = render_with(&products, &[], &options);
+    assert!(table.contains("| Runs "), "{}", table);
+    assert!(table.contains("| 1 (not converged) |"), "{}", table);
+    assert!(table.contains("| 3 "), "{}", table);
+    assert!(!render(&[product("Hash", 1, vec![])], &[]).contains("Runs"));
+}
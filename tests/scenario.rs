@@ -0,0 +1,46 @@
+use compare_speed::Scenario;
+
+const FILE: &str = r#"
+[scenarios.huge-skewed]
+size_a = 10_000_000
+size_b = 1_000
+methods = ["Hash", "Binary"]
+pre_sorted = true
+ascii = false
+"#;
+
+fn known() -> Vec<String> {
+    ["size_a", "size_b", "methods", "pre_sorted", "ascii"]
+        .map(String::from)
+        .to_vec()
+}
+
+#[test]
+fn scenarios_become_command_line_values() {
+    let scenario = Scenario::parse(FILE, "compare_times.toml", "huge-skewed", &known()).unwrap();
+    assert_eq!(scenario.settings["size_a"], ["10000000"]);
+    assert_eq!(scenario.settings["methods"], ["Hash", "Binary"]);
+    assert!(scenario.settings["pre_sorted"].is_empty());
+    assert!(!scenario.settings.contains_key("ascii"));
+    assert_eq!(
+        scenario.description(),
+        "scenario huge-skewed from compare_times.toml: methods = Hash,Binary, pre_sorted, size_a = 10000000, size_b = 1000"
+    );
+}
+
+#[test]
+fn unknown_keys_and_scenarios_are_named() {
+    let known = &known()[1..];
+    let error = Scenario::parse(FILE, "f", "huge-skewed", known).unwrap_err();
+    assert!(
+        error.starts_with("unknown field `size_a` in scenario `huge-skewed`"),
+        "{}",
+        error
+    );
+
+    let error = Scenario::parse(FILE, "f", "tiny", known).unwrap_err();
+    assert_eq!(error, "no scenario `tiny`, expected one of huge-skewed");
+
+    let error = Scenario::parse("sizes = 3", "f", "tiny", known).unwrap_err();
+    assert_eq!(error, "unknown key `sizes`, expected `scenarios`");
+}
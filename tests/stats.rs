@@ -0,0 +1,52 @@
+use std::time::Duration;
+
+use compare_speed::{mann_whitney, relative_standard_error, Significance};
+
+fn close(actual: f64, expected: f64) -> bool {
+    (actual - expected).abs() < 1e-5
+}
+
+#[test]
+fn mann_whitney_matches_known_p_values() {
+    let low = [1.0, 2.0, 3.0, 4.0, 5.0];
+    let high = [6.0, 7.0, 8.0, 9.0, 10.0];
+    assert!(close(mann_whitney(&low, &high), 0.0121858));
+    assert!(close(mann_whitney(&high, &low), 0.0121858));
+
+    let tied = mann_whitney(&[1.0, 2.0, 2.0, 3.0], &[2.0, 3.0, 3.0, 4.0]);
+    assert!(close(tied, 0.1720337), "{}", tied);
+
+    assert!(close(mann_whitney(&low, &low), 1.0));
+    assert_eq!(mann_whitney(&[3.0, 3.0], &[3.0, 3.0]), 1.0);
+}
+
+fn nanos(values: &[u64]) -> Vec<Duration> {
+    values.iter().map(|&x| Duration::from_nanos(x)).collect()
+}
+
+#[test]
+fn significance_needs_two_samples_and_reports_the_ratio_range() {
+    assert_eq!(Significance::new(&nanos(&[10]), &nanos(&[5, 6])), None);
+
+    let slow = nanos(&[200, 210, 220, 230, 240, 250]);
+    let fast = nanos(&[100, 105, 110, 115, 120, 125]);
+    let significance = Significance::new(&slow, &fast).unwrap();
+    assert!(significance.significant());
+    assert!(significance.ratio_low >= 1.6 && significance.ratio_high <= 2.5);
+    assert!(significance.format().starts_with("significant ("));
+
+    let noisy = nanos(&[100, 130, 110, 125, 105, 120]);
+    let significance = Significance::new(&fast, &noisy).unwrap();
+    assert!(!significance.significant());
+    assert!(significance.ratio_low < 1.0 && significance.ratio_high > 1.0);
+    assert!(significance.format().starts_with("noise ("));
+}
+
+#[test]
+fn relative_standard_error_needs_two_samples() {
+    assert_eq!(relative_standard_error(&nanos(&[100])), None);
+    assert_eq!(relative_standard_error(&nanos(&[100, 100, 100])), Some(0.0));
+    // a sample standard deviation of 14.1 makes a standard error of 10
+    let error = relative_standard_error(&nanos(&[90, 110])).unwrap();
+    assert!(close(error, 0.1), "{}", error);
+}